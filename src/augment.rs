@@ -0,0 +1,418 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+use rand::distributions::StandardNormal;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use error::{Error, Result};
+use json::Json;
+use reader::sample::Shape;
+use trainer::Transform;
+
+/// Names of the augmentation settings, as written in the --augment option and
+/// in config files.
+pub const FIELDS: [&str; 8] = [
+  "translate", "rotate", "scale", "shear", "elastic", "sigma", "noise", "cutout",
+];
+
+/// Random distortions applied to image shaped training samples. Every setting
+/// is the largest magnitude of its distortion, each sample draws its own
+/// magnitude between none and that. Zero turns the distortion off.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Augmentation {
+
+  /// Shift along each axis in pixels.
+  pub translate: f64,
+
+  /// Rotation around the centre in degrees, either way.
+  pub rotate: f64,
+
+  /// Relative change of size, 0.1 zooms between 90% and 110%.
+  pub scale: f64,
+
+  /// Horizontal shear factor, either way.
+  pub shear: f64,
+
+  /// Strength of the elastic distortion, the displacement field of smoothed
+  /// noise in range -1 to 1 is multiplied by it.
+  pub elastic: f64,
+
+  /// Standard deviation in pixels of the Gaussian that smooths the elastic
+  /// displacement field. Zero picks a seventh of the longer image side.
+  pub sigma: f64,
+
+  /// Standard deviation of the Gaussian noise added to every feature.
+  pub noise: f64,
+
+  /// Side in pixels of the square erased at a random position, at least 1
+  /// unless turned off.
+  pub cutout: f64,
+
+}
+
+impl Augmentation {
+
+  /// @return Whether any distortion is turned on
+  pub fn is_enabled (&self) -> bool {
+    *self != Augmentation { sigma: self.sigma, ..Augmentation::default() }
+  }
+
+  /// Changes one setting.
+  ///
+  /// @param name One of FIELDS
+  /// @param value Non negative magnitude
+  pub fn set (&mut self, name: &str, value: f64) -> Result<()> {
+    if !value.is_finite() || value < 0_f64 {
+      return Err(Error::Invalid(format!(
+        "Augmentation {} must be a non negative number, found {}.", name, value,
+      )));
+    }
+
+    match name {
+      "translate" => self.translate = value,
+      "rotate" => self.rotate = value,
+      "scale" if value < 1_f64 => self.scale = value,
+      "scale" => return Err(Error::Invalid(format!(
+        "Augmentation scale must be below 1, found {}.", value,
+      ))),
+      "shear" => self.shear = value,
+      "elastic" => self.elastic = value,
+      "sigma" => self.sigma = value,
+      "noise" => self.noise = value,
+      "cutout" if value == 0_f64 || value >= 1_f64 => self.cutout = value,
+      "cutout" => return Err(Error::Invalid(format!(
+        "Augmentation cutout must be 0 or at least 1 pixel, found {}.", value,
+      ))),
+      _ => return Err(Error::Invalid(format!(
+        "Unknown augmentation '{}', use one of: {}.", name, FIELDS.join(", "),
+      ))),
+    }
+
+    Ok(())
+  }
+
+  /// @return Settings in the order of FIELDS
+  fn values (&self) -> [f64; 8] {
+    [
+      self.translate, self.rotate, self.scale, self.shear,
+      self.elastic, self.sigma, self.noise, self.cutout,
+    ]
+  }
+
+  /// @return JSON object with every setting
+  pub fn to_json (&self) -> Json {
+    Json::object(FIELDS.iter().cloned()
+      .zip(self.values().iter().map(|&value| value.into()))
+      .collect())
+  }
+
+}
+
+impl FromStr for Augmentation {
+  type Err = Error;
+
+  /// Parses settings written as NAME=VALUE separated by commas, such as
+  /// "translate=1,rotate=10,noise=0.05".
+  fn from_str (text: &str) -> Result<Augmentation> {
+    let mut augmentation = Augmentation::default();
+
+    for setting in text.split(',').map(str::trim).filter(|setting| !setting.is_empty()) {
+      let mut parts = setting.splitn(2, '=');
+      let name = parts.next().unwrap_or("").trim();
+      let value = parts.next()
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .ok_or_else(|| Error::Invalid(format!(
+          "Invalid augmentation '{}', expected NAME=NUMBER.", setting,
+        )))?;
+
+      augmentation.set(name, value)?;
+    }
+
+    Ok(augmentation)
+  }
+}
+
+impl fmt::Display for Augmentation {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let settings: Vec<String> = FIELDS.iter()
+      .zip(self.values().iter())
+      .filter(|(_, &value)| value != 0_f64)
+      .map(|(name, value)| format!("{}={}", name, value))
+      .collect();
+
+    write!(f, "{}", settings.join(","))
+  }
+}
+
+/// Applies an augmentation to samples of known shape. Geometric and elastic
+/// distortions move every pixel to a new position, the value there is read
+/// from the original image by bilinear interpolation and pixels that come
+/// from outside the image are 0.
+#[derive(Clone)]
+pub struct Augmenter {
+
+  /// Largest magnitude of each distortion.
+  augmentation: Augmentation,
+
+  /// Width, height and channels of the samples.
+  shape: Shape,
+
+  /// Draws the magnitudes of each sample.
+  rng: StdRng,
+
+}
+
+impl Augmenter {
+
+  /// Builds augmenter whose distortions differ between runs.
+  ///
+  /// @param augmentation Largest magnitude of each distortion
+  /// @param shape Shape of the samples
+  /// @return New Augmenter instance
+  pub fn new (augmentation: Augmentation, shape: Shape) -> Self {
+    Augmenter { augmentation, shape, rng: StdRng::from_entropy() }
+  }
+
+  /// Builds augmenter that distorts the samples the same way for every run
+  /// with the same seed.
+  ///
+  /// @param augmentation Largest magnitude of each distortion
+  /// @param shape Shape of the samples
+  /// @param seed Seed of the random distortions
+  /// @return New Augmenter instance
+  pub fn seeded (augmentation: Augmentation, shape: Shape, seed: u64) -> Self {
+    Augmenter { augmentation, shape, rng: StdRng::seed_from_u64(seed) }
+  }
+
+  /// Distorts one sample.
+  ///
+  /// @param features Pixels row by row, channels of each pixel together
+  /// @return Distorted pixels of the same shape
+  pub fn augment (&mut self, features: &[f64]) -> Vec<f64> {
+    let augmentation = self.augmentation;
+    let moves = augmentation.translate > 0_f64 || augmentation.rotate > 0_f64
+      || augmentation.scale > 0_f64 || augmentation.shear > 0_f64
+      || augmentation.elastic > 0_f64;
+
+    let mut pixels = if moves { self.warp(features) } else { features.to_vec() };
+
+    if augmentation.noise > 0_f64 {
+      let deviation = self.rng.gen_range(0_f64, augmentation.noise);
+
+      for value in pixels.iter_mut() {
+        let gaussian: f64 = self.rng.sample(StandardNormal);
+        *value += deviation * gaussian;
+      }
+    }
+
+    if augmentation.cutout > 0_f64 {
+      self.cut_out(&mut pixels);
+    }
+
+    pixels
+  }
+
+  /// Moves the pixels by a random affine transformation around the centre of
+  /// the image followed by a random elastic displacement.
+  ///
+  /// @param features Pixels of the sample
+  /// @return Warped pixels
+  fn warp (&mut self, features: &[f64]) -> Vec<f64> {
+    let Shape { width, height, channels } = self.shape;
+    let augmentation = self.augmentation;
+
+    let angle = symmetric(&mut self.rng, augmentation.rotate) * PI / 180_f64;
+    let zoom = 1_f64 + symmetric(&mut self.rng, augmentation.scale);
+    let shear = symmetric(&mut self.rng, augmentation.shear);
+    let shift_x = symmetric(&mut self.rng, augmentation.translate);
+    let shift_y = symmetric(&mut self.rng, augmentation.translate);
+
+    // The image is rotated after being zoomed and sheared, so the inverse
+    // that maps each pixel back to its origin undoes the rotation first.
+    let (sin, cos) = angle.sin_cos();
+    let inverse = [
+      (cos + shear * sin) / zoom, (sin - shear * cos) / zoom,
+      -sin / zoom, cos / zoom,
+    ];
+
+    let (offsets_x, offsets_y) = if augmentation.elastic > 0_f64 {
+      let sigma = if augmentation.sigma > 0_f64 {
+        augmentation.sigma
+      } else {
+        width.max(height) as f64 / 7_f64
+      };
+
+      (self.displacement(sigma), self.displacement(sigma))
+    } else {
+      (vec!(0_f64; width * height), vec!(0_f64; width * height))
+    };
+
+    let centre_x = (width as f64 - 1_f64) / 2_f64;
+    let centre_y = (height as f64 - 1_f64) / 2_f64;
+    let mut pixels = vec!(0_f64; features.len());
+
+    for y in 0..height {
+      for x in 0..width {
+        let dx = x as f64 - centre_x - shift_x;
+        let dy = y as f64 - centre_y - shift_y;
+        let source_x = inverse[0] * dx + inverse[1] * dy + centre_x + offsets_x[y * width + x];
+        let source_y = inverse[2] * dx + inverse[3] * dy + centre_y + offsets_y[y * width + x];
+
+        for channel in 0..channels {
+          pixels[(y * width + x) * channels + channel] =
+            self.interpolate(features, source_x, source_y, channel);
+        }
+      }
+    }
+
+    pixels
+  }
+
+  /// Draws displacement along one axis for every pixel: uniform noise
+  /// smoothed by a Gaussian, so that neighbouring pixels move alike, scaled
+  /// by the strength of the elastic distortion.
+  ///
+  /// @param sigma Standard deviation of the Gaussian in pixels
+  /// @return Displacement of each pixel
+  fn displacement (&mut self, sigma: f64) -> Vec<f64> {
+    let Shape { width, height, .. } = self.shape;
+    let noise: Vec<f64> = (0..width * height)
+      .map(|_| self.rng.gen_range(-1_f64, 1_f64))
+      .collect();
+
+    let radius = (3_f64 * sigma).ceil() as isize;
+    let kernel: Vec<f64> = (-radius..=radius)
+      .map(|offset| (-(offset as f64).powi(2) / (2_f64 * sigma * sigma)).exp())
+      .collect();
+    let total: f64 = kernel.iter().sum();
+
+    // The Gaussian is separable, rows are smoothed first and columns after.
+    let smooth = |values: &[f64], x: usize, y: usize, horizontal: bool| {
+      kernel.iter().enumerate()
+        .map(|(k, weight)| {
+          let offset = k as isize - radius;
+          let (x, y) = if horizontal {
+            (x as isize + offset, y as isize)
+          } else {
+            (x as isize, y as isize + offset)
+          };
+
+          if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+            0_f64
+          } else {
+            weight * values[y as usize * width + x as usize]
+          }
+        })
+        .sum::<f64>() / total
+    };
+
+    let rows: Vec<f64> = (0..width * height)
+      .map(|index| smooth(&noise, index % width, index / width, true))
+      .collect();
+
+    (0..width * height)
+      .map(|index| self.augmentation.elastic * smooth(&rows, index % width, index / width, false))
+      .collect()
+  }
+
+  /// Reads the value at a position between pixels from the four pixels
+  /// around it. Pixels outside the image are 0.
+  ///
+  /// @param features Pixels of the sample
+  /// @param x Horizontal position
+  /// @param y Vertical position
+  /// @param channel Which channel to read
+  /// @return Interpolated value
+  fn interpolate (&self, features: &[f64], x: f64, y: f64, channel: usize) -> f64 {
+    let Shape { width, height, channels } = self.shape;
+    let (left, top) = (x.floor(), y.floor());
+    let (right_weight, bottom_weight) = (x - left, y - top);
+
+    let pixel = |column: f64, row: f64| {
+      if column < 0_f64 || row < 0_f64 || column >= width as f64 || row >= height as f64 {
+        0_f64
+      } else {
+        features[(row as usize * width + column as usize) * channels + channel]
+      }
+    };
+
+    (1_f64 - bottom_weight) * (
+      (1_f64 - right_weight) * pixel(left, top) + right_weight * pixel(left + 1_f64, top)
+    ) + bottom_weight * (
+      (1_f64 - right_weight) * pixel(left, top + 1_f64)
+        + right_weight * pixel(left + 1_f64, top + 1_f64)
+    )
+  }
+
+  /// Erases a square of random size centred on a random pixel. Near the
+  /// border only the part of the square inside the image is erased.
+  ///
+  /// @param pixels Pixels of the sample, changed in place
+  fn cut_out (&mut self, pixels: &mut [f64]) {
+    let Shape { width, height, channels } = self.shape;
+    let side = self.rng.gen_range(0_f64, self.augmentation.cutout).round() as usize;
+    let side = side.min(width.max(height));
+    let centre_x = self.rng.gen_range(0, width);
+    let centre_y = self.rng.gen_range(0, height);
+
+    let left = centre_x.saturating_sub(side / 2);
+    let top = centre_y.saturating_sub(side / 2);
+
+    for y in top..(centre_y + side - side / 2).min(height) {
+      for x in left..(centre_x + side - side / 2).min(width) {
+        for channel in 0..channels {
+          pixels[(y * width + x) * channels + channel] = 0_f64;
+        }
+      }
+    }
+  }
+
+}
+
+impl Transform for Augmenter {
+  fn apply (&mut self, features: &[f64]) -> Vec<f64> {
+    self.augment(features)
+  }
+}
+
+/// @param rng Random generator
+/// @param maximum Largest magnitude
+/// @return Value drawn uniformly between -maximum and maximum
+fn symmetric (rng: &mut StdRng, maximum: f64) -> f64 {
+  if maximum > 0_f64 {
+    rng.gen_range(-maximum, maximum)
+  } else {
+    0_f64
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Augmentation, Augmenter};
+  use reader::sample::Shape;
+
+  #[test]
+  fn distorts_reproducibly_for_any_shape() {
+    let augmentation: Augmentation =
+      "translate=1,rotate=15,scale=0.1,shear=0.1,elastic=2,noise=0.05,cutout=2".parse().unwrap();
+    assert_eq!(
+      augmentation.to_string(),
+      "translate=1,rotate=15,scale=0.1,shear=0.1,elastic=2,noise=0.05,cutout=2",
+    );
+    assert!("cutout=0.5".parse::<Augmentation>().is_err());
+
+    // A wide image with two channels checks the indexing of the pixels.
+    let shape = Shape::new(6, 3, 2);
+    let image: Vec<f64> = (0..shape.len()).map(|i| (i % 7) as f64 / 7_f64).collect();
+
+    let mut first = Augmenter::seeded(augmentation, shape, 3);
+    let mut second = Augmenter::seeded(augmentation, shape, 3);
+    let distorted = first.augment(&image);
+    assert_eq!(distorted.len(), image.len());
+    assert_ne!(distorted, image);
+    assert_eq!(distorted, second.augment(&image));
+
+    let mut identity = Augmenter::seeded(Augmentation::default(), shape, 3);
+    assert_eq!(identity.augment(&image), image);
+  }
+}
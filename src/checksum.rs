@@ -0,0 +1,254 @@
+
+/// Round constants of SHA-256, the first 32 bits of the fractional parts of
+/// the cube roots of the first 64 primes.
+const K: [u32; 64] = [
+  0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+  0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+  0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+  0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+  0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+  0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+  0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+  0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Computes the SHA-256 digest of given bytes. Used to identify the exact
+/// content of data files a model was trained on.
+///
+/// @param bytes Content to hash
+/// @return 32 byte digest
+pub fn sha256 (bytes: &[u8]) -> [u8; 32] {
+  let mut hasher = Sha256::new();
+  hasher.update(bytes);
+
+  hasher.finish()
+}
+
+/// SHA-256 of content that arrives in parts, so that large files can be
+/// hashed without reading them into memory.
+pub struct Sha256 {
+
+  /// Intermediate hash value.
+  state: [u32; 8],
+
+  /// Bytes that do not fill a whole 64 byte block yet.
+  pending: Vec<u8>,
+
+  /// Number of bytes hashed so far.
+  length: u64,
+
+}
+
+impl Sha256 {
+
+  /// @return Hasher of no content
+  pub fn new () -> Self {
+    Sha256 {
+      state: [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+        0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+      ],
+      pending: Vec::with_capacity(64),
+      length: 0,
+    }
+  }
+
+  /// @param bytes Next part of the content
+  pub fn update (&mut self, mut bytes: &[u8]) {
+    self.length = self.length.wrapping_add(bytes.len() as u64);
+
+    if !self.pending.is_empty() {
+      let missing = (64 - self.pending.len()).min(bytes.len());
+      self.pending.extend_from_slice(&bytes[..missing]);
+      bytes = &bytes[missing..];
+
+      if self.pending.len() < 64 {
+        return;
+      }
+
+      let block = ::std::mem::take(&mut self.pending);
+      self.compress(&block);
+      self.pending = block;
+      self.pending.clear();
+    }
+
+    let whole = bytes.len() - bytes.len() % 64;
+    for block in bytes[..whole].chunks(64) {
+      self.compress(block);
+    }
+
+    self.pending.extend_from_slice(&bytes[whole..]);
+  }
+
+  /// @return Digest of the content
+  pub fn finish (mut self) -> [u8; 32] {
+    // The message is padded with a single 1 bit, zeros and its length in bits
+    // so that it spans a whole number of 64 byte blocks.
+    let bits = self.length.wrapping_mul(8);
+    let mut padding = vec!(0x80_u8);
+    while (self.pending.len() + padding.len()) % 64 != 56 {
+      padding.push(0);
+    }
+    padding.extend_from_slice(&bits.to_be_bytes());
+
+    let length = self.length;
+    self.update(&padding);
+    self.length = length;
+
+    let mut digest = [0_u8; 32];
+    for (i, value) in self.state.iter().enumerate() {
+      digest[i * 4..i * 4 + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    digest
+  }
+
+  /// Mixes one 64 byte block into the state.
+  fn compress (&mut self, block: &[u8]) {
+    let mut w = [0_u32; 64];
+    for (i, word) in block.chunks(4).enumerate() {
+      w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    for i in 16..64 {
+      let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+      let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+      w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+
+    for i in 0..64 {
+      let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+      let choice = (e & f) ^ (!e & g);
+      let temp1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(K[i]).wrapping_add(w[i]);
+      let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+      let majority = (a & b) ^ (a & c) ^ (b & c);
+      let temp2 = s0.wrapping_add(majority);
+
+      h = g;
+      g = f;
+      f = e;
+      e = d.wrapping_add(temp1);
+      d = c;
+      c = b;
+      b = a;
+      a = temp1.wrapping_add(temp2);
+    }
+
+    for (value, added) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+      *value = value.wrapping_add(*added);
+    }
+  }
+
+}
+
+impl Default for Sha256 {
+  fn default () -> Self {
+    Sha256::new()
+  }
+}
+
+/// Formats bytes as lowercase hexadecimal string.
+///
+/// @param bytes Bytes to format
+/// @return Two characters per byte
+pub fn hex (bytes: &[u8]) -> String {
+  bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Computes the CRC-32 of given bytes, as used by PNG chunks and gzip.
+///
+/// @param bytes Content to check
+/// @return Checksum
+pub fn crc32 (bytes: &[u8]) -> u32 {
+  let mut crc = Crc32::new();
+  crc.update(bytes);
+
+  crc.finish()
+}
+
+/// CRC-32 of content that arrives in parts, e.g. while streaming a file.
+pub struct Crc32 {
+  state: u32,
+}
+
+impl Crc32 {
+
+  /// @return Checksum of no content
+  pub fn new () -> Self {
+    Crc32 { state: !0_u32 }
+  }
+
+  /// @param bytes Next part of the content
+  pub fn update (&mut self, bytes: &[u8]) {
+    for &byte in bytes {
+      self.state ^= u32::from(byte);
+      for _ in 0..8 {
+        self.state = if self.state & 1 == 1 {
+          0xedb8_8320 ^ (self.state >> 1)
+        } else {
+          self.state >> 1
+        };
+      }
+    }
+  }
+
+  /// @return Checksum of the content so far
+  pub fn finish (&self) -> u32 {
+    !self.state
+  }
+
+}
+
+impl Default for Crc32 {
+  fn default () -> Self {
+    Crc32::new()
+  }
+}
+
+/// Computes the Adler-32 checksum that closes zlib streams.
+///
+/// @param bytes Content to check
+/// @return Checksum
+pub fn adler32 (bytes: &[u8]) -> u32 {
+  let (mut a, mut b) = (1_u32, 0_u32);
+
+  for &byte in bytes {
+    a = (a + u32::from(byte)) % 65521;
+    b = (b + a) % 65521;
+  }
+
+  b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{adler32, crc32, hex, sha256, Sha256};
+
+  #[test]
+  fn sha256_known_digests() {
+    assert_eq!(
+      hex(&sha256(b"")),
+      "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+    );
+    assert_eq!(
+      hex(&sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+      "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+    );
+
+    let mut parts = Sha256::new();
+    for part in b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".chunks(5) {
+      parts.update(part);
+    }
+    assert_eq!(
+      hex(&parts.finish()),
+      "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+    );
+  }
+
+  #[test]
+  fn crc32_and_adler32_known_values() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+  }
+}
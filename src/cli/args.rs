@@ -0,0 +1,123 @@
+use std::str::FromStr;
+use error::{Error, Result};
+
+/// Options of a subcommand parsed from "--name value" pairs and "--flag"
+/// switches.
+pub struct Args {
+
+  /// Options with a value in the order they were given.
+  options: Vec<(String, String)>,
+
+  /// Switches that were given.
+  flags: Vec<String>,
+
+}
+
+impl Args {
+
+  /// Parses arguments of a subcommand. Unknown options are rejected so that a
+  /// typo does not silently fall back to a default.
+  ///
+  /// @param arguments Arguments following the subcommand name
+  /// @param options Names of options that take a value
+  /// @param flags Names of options that take no value
+  /// @return Parsed arguments
+  pub fn parse (
+    arguments: &[String],
+    options: &[&str],
+    flags: &[&str],
+  ) -> Result<Args> {
+    let mut args = Args { options: Vec::new(), flags: Vec::new() };
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+      let name = argument.trim_start_matches("--");
+
+      if name.len() + 2 != argument.len() {
+        return Err(Error::Usage(format!("Unexpected argument '{}'.", argument)));
+      }
+
+      if flags.contains(&name) {
+        args.flags.push(name.to_string());
+      } else if options.contains(&name) {
+        let value = arguments.next()
+          .ok_or_else(|| Error::Usage(format!("Option --{} needs a value.", name)))?;
+
+        args.options.push((name.to_string(), value.to_string()));
+      } else {
+        return Err(Error::Usage(format!("Unknown option --{}.", name)));
+      }
+    }
+
+    Ok(args)
+  }
+
+  /// @param name Name of the option
+  /// @return Last value given to the option
+  pub fn optional (&self, name: &str) -> Option<&str> {
+    self.options.iter()
+      .rev()
+      .find(|(option, _)| option == name)
+      .map(|(_, value)| value.as_str())
+  }
+
+  /// @param name Name of the option
+  /// @return Last value given to the option or an error if it is missing
+  pub fn required (&self, name: &str) -> Result<&str> {
+    self.optional(name)
+      .ok_or_else(|| Error::Usage(format!("Option --{} is required.", name)))
+  }
+
+  /// @param name Name of the option
+  /// @return All values given to the option in order
+  pub fn all (&self, name: &str) -> Vec<&str> {
+    self.options.iter()
+      .filter(|(option, _)| option == name)
+      .map(|(_, value)| value.as_str())
+      .collect()
+  }
+
+  /// Parses the value of an option.
+  ///
+  /// @param name Name of the option
+  /// @return Parsed value if the option was given
+  pub fn parsed<T: FromStr> (&self, name: &str) -> Result<Option<T>> {
+    match self.optional(name) {
+      None => Ok(None),
+      Some(value) => value.parse::<T>()
+        .map(Some)
+        .map_err(|_| Error::Usage(format!("Invalid value '{}' for --{}.", value, name))),
+    }
+  }
+
+  /// Parses the value of an option or falls back to a default.
+  ///
+  /// @param name Name of the option
+  /// @param default Value to use if the option was not given
+  /// @return Parsed value
+  pub fn parsed_or<T: FromStr> (&self, name: &str, default: T) -> Result<T> {
+    Ok(self.parsed(name)?.unwrap_or(default))
+  }
+
+  /// Parses comma separated list of values, e.g. "64,128,10".
+  ///
+  /// @param name Name of the option
+  /// @return Parsed values if the option was given
+  pub fn list<T: FromStr> (&self, name: &str) -> Result<Option<Vec<T>>> {
+    match self.optional(name) {
+      None => Ok(None),
+      Some(value) => value.split(',')
+        .map(|item| item.trim().parse::<T>())
+        .collect::<::std::result::Result<Vec<T>, _>>()
+        .map(Some)
+        .map_err(|_| Error::Usage(format!("Invalid list '{}' for --{}.", value, name))),
+    }
+  }
+
+  /// @param name Name of the switch
+  /// @return Whether the switch was given
+  pub fn flag (&self, name: &str) -> bool {
+    self.flags.iter().any(|flag| flag == name)
+  }
+
+}
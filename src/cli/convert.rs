@@ -0,0 +1,36 @@
+use std::path::Path;
+use dataset::Dataset;
+use error::Result;
+use reader;
+use reader::cache::{self, Precision};
+use super::args::Args;
+
+/// Reads labelled data in any supported format and writes it into a binary
+/// cache, which loads much faster than parsing the original files.
+///
+/// @param arguments Options of the subcommand
+pub fn run (arguments: &[String]) -> Result<()> {
+  let mut options = super::DATA_OPTIONS.to_vec();
+  options.extend(&["format", "block", "data", "data-labels", "output", "precision"]);
+
+  let args = Args::parse(arguments, &options, &super::DATA_FLAGS)?;
+
+  let data = reader::load_dataset(
+    Path::new(args.required("data")?),
+    args.optional("data-labels").map(Path::new),
+    &super::data_format(&args)?,
+    None,
+  )?;
+
+  let output = args.required("output")?;
+  let precision: Precision = args.parsed_or("precision", Precision::F32)?;
+
+  cache::save_cache(&data, precision, output)?;
+
+  println!(
+    "Wrote {} samples with {} features and {} classes to {}.",
+    data.len(), data.features(), data.classes(), output,
+  );
+
+  Ok(())
+}
@@ -0,0 +1,125 @@
+use config::Config;
+use dataset::Dataset;
+use dataset::in_memory::InMemory;
+use dataset::split;
+use error::{Error, Result};
+use preprocess::Pipeline;
+use reader;
+use trainer::callback::Progress;
+use trainer::cross_validation::{self, FoldScore};
+use super::args::Args;
+use super::train;
+
+/// Options of train that make no sense when the held out folds take the place
+/// of the validation data. Validation data of a config file is ignored.
+const TRAIN_ONLY: [&str; 3] = ["validation", "validation-labels", "shuffle-buffer"];
+
+/// Estimates how well the configured network generalises with k-fold cross
+/// validation on the training data. Every fold trains a fresh network with
+/// the same config and seed.
+///
+/// @param arguments Options of the subcommand
+pub fn run (arguments: &[String]) -> Result<()> {
+  let mut options: Vec<&str> = train::HYPERPARAMETERS.iter()
+    .cloned()
+    .filter(|option| !TRAIN_ONLY.contains(option))
+    .collect();
+  options.extend(&["config", "folds"]);
+
+  let mut flags = super::DATA_FLAGS.to_vec();
+  flags.extend(&["stratify", "leave-one-out", "progress"]);
+
+  let args = Args::parse(arguments, &options, &flags)?;
+
+  let config = match args.optional("config") {
+    Some(path) => {
+      train::check_without_hyperparameters(&args)?;
+      Config::load(path)?
+    },
+    None => train::config_from_args(&args)?,
+  };
+
+  if config.stream {
+    return Err(Error::Usage("Cross validation cannot stream the training data.".to_string()));
+  }
+
+  let data = reader::load_dataset(
+    &config.train,
+    config.train_labels.as_deref(),
+    &config.format,
+    None,
+  )?;
+  super::check_samples(&train::network(&config, data.features()), &data)?;
+
+  // Every fold distorts and blends its training samples alike.
+  let augmenter = train::augmenter(&config, data.shape(), data.features())?;
+  let mixer = train::mixer(&config, data.shape(), data.features())?;
+
+  let k = match (args.flag("leave-one-out"), args.parsed::<usize>("folds")?) {
+    (true, None) => data.len(),
+    (false, folds) => folds.unwrap_or(5),
+    (true, Some(_)) => {
+      return Err(Error::Usage("Option --folds cannot be combined with --leave-one-out.".to_string()));
+    },
+  };
+
+  let folds = split::folds(&data, k, args.flag("stratify"), config.seed.unwrap_or(0))?;
+
+  let scores = cross_validation::cross_validate(&data, &folds, |fold, training_data| {
+    let mut trainer = train::trainer(&config);
+    trainer.mixer = mixer.clone();
+
+    // Balanced weights count the training folds only, like the preprocessing.
+    if let Some(ref weights) = config.class_weights {
+      trainer.class_weights = weights.resolve(training_data)?;
+    }
+
+    if args.flag("progress") {
+      println!("Fold {} of {}:", fold + 1, k);
+      trainer.add_callback(Box::new(Progress));
+    }
+
+    // Preprocessing is fitted on the training folds only, so that nothing
+    // about the held out fold leaks into the training.
+    let pipeline = Pipeline::fit(&config.preprocessing, training_data)?;
+    let mut network = train::network(&config, pipeline.outputs(training_data.features()));
+
+    if let Some(ref augmenter) = augmenter {
+      trainer.add_transform(Box::new(augmenter.clone()));
+      trainer.add_transform(Box::new(pipeline.clone()));
+      trainer.fit(&mut network, training_data);
+    } else if pipeline.is_empty() {
+      trainer.fit(&mut network, training_data);
+    } else {
+      trainer.fit(&mut network, &pipeline.transform(InMemory::collect(training_data)));
+    }
+
+    Ok((pipeline, network))
+  })?;
+
+  print_scores(&scores);
+
+  Ok(())
+}
+
+/// Prints a table of the folds followed by the mean and standard deviation.
+///
+/// @param scores Score of each fold
+fn print_scores (scores: &[FoldScore]) {
+  println!("{:>5} {:>8} {:>8} {:>9} {:>9}", "Fold", "Train", "Test", "Accuracy", "Loss");
+
+  for score in scores {
+    println!(
+      "{:>5} {:>8} {:>8} {:>9.4} {:>9.4}",
+      score.fold + 1, score.train_samples, score.test_samples, score.accuracy, score.loss,
+    );
+  }
+
+  let accuracies: Vec<f64> = scores.iter().map(|score| score.accuracy).collect();
+  let losses: Vec<f64> = scores.iter().map(|score| score.loss).collect();
+  let (accuracy, accuracy_std) = cross_validation::mean_std(&accuracies);
+  let (loss, loss_std) = cross_validation::mean_std(&losses);
+
+  println!("Accuracy {:.4} ± {:.4}.", accuracy, accuracy_std);
+  println!("Loss {:.4} ± {:.4}.", loss, loss_std);
+}
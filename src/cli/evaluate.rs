@@ -0,0 +1,115 @@
+use std::fs;
+use std::path::Path;
+use error::{Error, Result};
+use evaluation::{Coverage, Evaluation};
+use json::Json;
+use model::Model;
+use neural_network::network_classify::Criterion;
+use reader;
+use super::args::Args;
+
+/// Reports how well a model classifies labelled data: the confusion matrix,
+/// precision, recall and F1 of each class and their averages, followed by
+/// accuracy, top k accuracy and loss. With --coverage it also reports how
+/// many samples a reject threshold keeps and how accurate they are.
+///
+/// @param arguments Options of the subcommand
+pub fn run (arguments: &[String]) -> Result<()> {
+  let mut options = super::DATA_OPTIONS.to_vec();
+  options.extend(&[
+    "format", "block", "model", "data", "data-labels", "top-k", "csv", "json", "reject-by",
+    "thresholds", "coverage-csv",
+  ]);
+
+  let mut flags = super::DATA_FLAGS.to_vec();
+  flags.push("coverage");
+
+  let args = Args::parse(arguments, &options, &flags)?;
+
+  let coverage_options = ["reject-by", "thresholds", "coverage-csv"];
+  if !args.flag("coverage") && coverage_options.iter().any(|name| args.optional(name).is_some()) {
+    return Err(Error::Usage(
+      "Options --reject-by, --thresholds and --coverage-csv need --coverage.".to_string(),
+    ));
+  }
+
+  let model = Model::load(args.required("model")?)?;
+  let data = model.preprocessing.transform(reader::load_dataset(
+    Path::new(args.required("data")?),
+    args.optional("data-labels").map(Path::new),
+    &super::data_format(&args)?,
+    Some(&model.labels),
+  )?);
+  super::check_samples(&model.network, &data)?;
+
+  let top_k: usize = args.parsed_or("top-k", 5)?;
+  let evaluation = Evaluation::of(&model.network, &data);
+
+  println!("{}", evaluation);
+  println!("Correct {} out of {}.", evaluation.correct(), evaluation.samples());
+  println!("Accuracy {:.4}.", evaluation.accuracy());
+  println!("Top {} accuracy {:.4}.", top_k, evaluation.top_k_accuracy(top_k));
+  println!("Loss {:.4}.", evaluation.loss());
+
+  let coverage = if args.flag("coverage") {
+    let criterion = args.parsed_or("reject-by", Criterion::Probability)?;
+    let thresholds = args.list::<f64>("thresholds")?
+      .unwrap_or_else(|| (0..20).map(|step| step as f64 / 20_f64).collect());
+    let coverage = Coverage::of(&model.network, &data, criterion, &thresholds);
+
+    println!();
+    print_coverage(criterion, &coverage);
+
+    coverage
+  } else {
+    Vec::new()
+  };
+
+  if let Some(path) = args.optional("csv") {
+    fs::write(path, evaluation.to_csv())?;
+  }
+
+  if let Some(path) = args.optional("coverage-csv") {
+    let rows: Vec<String> = coverage.iter()
+      .map(|point| format!(
+        "{},{},{},{}", point.threshold, point.accepted, point.coverage, point.accuracy,
+      ))
+      .collect();
+
+    fs::write(path, format!("threshold,accepted,coverage,accuracy\n{}\n", rows.join("\n")))?;
+  }
+
+  if let Some(path) = args.optional("json") {
+    let mut json = evaluation.to_json(top_k);
+    if let Json::Object(ref mut fields) = json {
+      if !coverage.is_empty() {
+        fields.push((
+          "coverage".to_string(),
+          Json::Array(coverage.iter().map(Coverage::to_json).collect()),
+        ));
+      }
+    }
+
+    fs::write(path, format!("{}\n", json))?;
+  }
+
+  Ok(())
+}
+
+/// Prints coverage and accuracy of each threshold with a bar of the
+/// accuracy, so that the operating point can be picked at a glance.
+///
+/// @param criterion What the confidence is measured by
+/// @param coverage Coverage of each threshold
+fn print_coverage (criterion: Criterion, coverage: &[Coverage]) {
+  println!("Samples with {} below the threshold are rejected:", criterion);
+  println!("{:>9} {:>8} {:>9} {:>9}", "Threshold", "Accepted", "Coverage", "Accuracy");
+
+  for point in coverage {
+    println!(
+      "{:>9.4} {:>8} {:>9.4} {:>9.4} {}",
+      point.threshold, point.accepted, point.coverage, point.accuracy,
+      "#".repeat((point.accuracy * 20_f64).round() as usize),
+    );
+  }
+}
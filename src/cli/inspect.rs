@@ -0,0 +1,65 @@
+use error::Result;
+use model::Model;
+use manifest;
+use neural_network::layer::Layer;
+use super::args::Args;
+
+/// Prints summary of a model: activation, layers, parameter counts and
+/// weight norms.
+///
+/// @param arguments Options of the subcommand
+pub fn run (arguments: &[String]) -> Result<()> {
+  let args = Args::parse(arguments, &["model"], &[])?;
+
+  let model = Model::load(args.required("model")?)?;
+  let network = &model.network;
+  let schema = network.schema();
+
+  println!("Activation: {}", network.activation.name);
+  println!(
+    "Topology: {}",
+    schema.iter().map(|n| n.to_string()).collect::<Vec<String>>().join("-"),
+  );
+  println!("Inputs: {}", schema[0]);
+  if let Some(shape) = model.shape {
+    println!("Input shape: {}", shape);
+  }
+  for stage in model.preprocessing.stages() {
+    println!("Preprocessing: {}", stage);
+  }
+
+  let mut parameters = 0;
+  for (index, layer) in network.layers.iter().enumerate() {
+    let count: usize = layer.neurons.iter()
+      .map(|(_, weights)| weights.len() + 1)
+      .sum();
+
+    parameters += count;
+
+    println!(
+      "Layer {}: {} neurons, {} parameters, weight norm {:.4}",
+      index + 1, layer.neurons.len(), count, Layer::norm(&layer.neurons),
+    );
+  }
+
+  println!("Parameters: {}", parameters);
+
+  if let Some(ref manifest) = model.manifest {
+    println!("Commit: {}", manifest.commit.as_ref().map_or("unknown", String::as_str));
+    println!("Seed: {}", manifest.seed.map_or("none".to_string(), |seed| seed.to_string()));
+    println!(
+      "Trained: {} epochs from {} to {}",
+      manifest.epochs,
+      manifest::format_timestamp(manifest.started_at),
+      manifest::format_timestamp(manifest.finished_at),
+    );
+
+    for file in manifest.datasets.iter() {
+      println!("Data ({}): {} with {} rows, sha256 {}", file.role, file.path, file.rows, file.sha256);
+    }
+
+    println!("Metrics: {}", manifest.metrics);
+  }
+
+  Ok(())
+}
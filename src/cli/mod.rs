@@ -0,0 +1,345 @@
+
+/*
+ * Exports the argument parser shared by all subcommands.
+ */
+
+pub mod args;
+
+/*
+ * Exports one module per subcommand, each with a run function.
+ */
+
+pub mod train;
+pub mod evaluate;
+pub mod predict;
+pub mod inspect;
+pub mod convert;
+pub mod split;
+pub mod cross_validate;
+
+use reader::DataFormat;
+use reader::csv::CsvOptions;
+use reader::directory::ImageOptions;
+use self::args::Args;
+use error::{Error, Result};
+use dataset::Dataset;
+use neural_network::network::Network;
+
+/// Printed when the command line does not make sense.
+pub const USAGE: &str = "\
+Usage: classifier <command> [options]
+
+Commands:
+  train     Trains a new network and writes it into a model file.
+            --config PATH         Experiment config file, replaces the options
+                                  from --train to --seed
+            --train PATH          Training data (required without --config)
+            --train-labels PATH   Labels of the training data for idx format
+            --validation PATH     Data to report accuracy on after each epoch
+            --validation-labels PATH
+                                  Labels of the validation data for idx format
+            --stream              Read csv training data batch by batch instead
+                                  of loading it, for files larger than memory
+            --shuffle-buffer N    Samples shuffled together while streaming
+                                  with --seed [default: 10000]
+            --normalize NAME      Scale features fitted on the training data
+                                  and saved with the model, minmax for 0..1 or
+                                  standard for zero mean and unit variance
+            --normalize-scope NAME
+                                  feature to scale each feature on its own or
+                                  global to scale all alike [default: feature]
+            --pca N               Project the features onto their N principal
+                                  components after normalising, the input
+                                  layer takes one neuron per component
+            --pca-variance X      Keep as few components as explain this share
+                                  of the variance, between 0 and 1
+            --topology LIST       Neurons per layer [default: 64,128,128,10]
+            --activation NAME     sigmoid or leaky_relu [default: sigmoid]
+            --batch-size N        Samples per update [default: 10]
+            --epochs N            Passes over the data [default: 6.5 cycles of
+                                  the schedule plus one, 105 for step size 8]
+            --learning-rate X     Constant learning rate instead of the cycle
+            --min-lr X            Lower bound of the cycle [default: 0.1]
+            --max-lr X            Upper bound of the cycle [default: 0.8]
+            --step-size X         Epochs per half cycle [default: 8]
+            --momentum X          Use momentum instead of plain SGD
+            --augment LIST        Distort the training images anew each epoch,
+                                  NAME=X pairs of translate (pixels), rotate
+                                  (degrees), scale, shear, elastic, sigma
+                                  (pixels), noise and cutout (pixels)
+            --label-smoothing X   Spread this share of each label over all
+                                  classes [default: 0]
+            --mixup ALPHA         Blend pairs of samples of a batch and their
+                                  labels, weights drawn from Beta(ALPHA, ALPHA)
+            --cutmix ALPHA        Paste a box of one image of a batch into
+                                  another, labels weighted by area
+            --class-weights LIST  Loss weight of each class, or balanced for
+                                  the inverse of the class frequencies
+            --sampling NAME       Draw class balanced epochs, oversample to
+                                  repeat small classes or undersample to
+                                  leave out samples of large ones
+            --seed N              Seed the weights, the distortions and
+                                  shuffle the data before each epoch
+            --log PATH            Write JSON lines with epoch statistics
+            --output PATH         Model file to write [default: model.json]
+            --progress            Print statistics after each epoch
+  cross-validate
+            Trains a fresh network on all folds but one for each fold of the
+            training data and reports accuracy and loss on the held out folds.
+            Takes the options of train except --validation, --stream and the
+            outputs.
+            --folds N             Number of folds [default: 5]
+            --stratify            Keep the class proportions in every fold
+            --leave-one-out       One fold per sample, for small data sets
+  evaluate  Reports how well a model classifies labelled data: confusion
+            matrix, precision, recall, F1 and support of each class with
+            their macro, micro and weighted averages, accuracy and loss.
+            --model PATH          Model file (required)
+            --data PATH           Labelled data (required)
+            --data-labels PATH    Labels of the data for idx format
+            --top-k K             Also report how often the class is among
+                                  the K strongest outputs [default: 5]
+            --csv PATH            Write the scores and the confusion matrix
+                                  as csv
+            --json PATH           Write all metrics as JSON
+            --coverage            Report coverage and accuracy of the
+                                  predictions a reject threshold accepts
+            --reject-by NAME      probability or margin, see predict
+                                  [default: probability]
+            --thresholds LIST     Thresholds to report [default: 0 to 0.95
+                                  in steps of 0.05]
+            --coverage-csv PATH   Write the coverage of each threshold as csv
+  predict   Prints the label of each input row or image.
+            --model PATH          Model file (required)
+            --input PATH          Rows of features without labels
+            --image PATH          PNG, BMP, PBM, PGM or PPM image resized to
+                                  the model inputs, can be repeated, takes
+                                  --invert and --stretch
+            --top-k K             Print the K most likely labels, each
+                                  followed by its probability
+            --reject-below X      Print unknown when the confidence of the
+                                  prediction is below X
+            --reject-by NAME      probability of the most likely class or
+                                  margin to the second one [default:
+                                  probability]
+  inspect   Prints summary of a model.
+            --model PATH          Model file (required)
+  convert   Writes labelled data into a binary cache for --format cache.
+            --data PATH           Labelled data (required)
+            --data-labels PATH    Labels of the data for idx format
+            --output PATH         Cache file to write (required)
+            --precision NAME      f32 or f64 features [default: f32]
+  split     Splits labelled data into train, validation and test files. Csv
+            data is written as csv, other formats as caches.
+            --data PATH           Labelled data (required)
+            --data-labels PATH    Labels of the data for idx format
+            --output DIR          Directory to write the parts into (required)
+            --ratios LIST         Shares of train, validation and test, or of
+                                  train and test [default: 0.7,0.15,0.15]
+            --stratify            Keep the class proportions in every part
+            --seed N              Seed of the random split [default: 0]
+            --precision NAME      f32 or f64 features of caches [default: f32]
+
+Data options of train, cross-validate, evaluate, convert and split:
+            --format NAME         csv, idx for MNIST style image and label
+                                  files, bitmap for the original 32x32
+                                  optdigits bitmaps or images for a directory
+                                  with a directory of images per class or
+                                  cache for files written by convert
+                                  [default: csv]
+            --block N             Average bitmaps over blocks of NxN pixels,
+                                  4 gives the 8x8 optdigits grid [default: 1]
+            --shape WxH[xC]       Size images are resized to (required for
+                                  images format)
+            --invert              Swap dark and light, for dark ink on white
+            --stretch             Stretch intensities of images to 0..1
+
+Data options of csv files for train, cross-validate, evaluate, predict and
+split:
+            --delimiter CHAR      Field separator, tab for tabs [default: ,]
+            --header              First row holds column names
+            --label-column COL    first, last, position from 0 or column name
+                                  [default: last]
+            --comment CHAR        Skip lines starting with this character
+            --scale X             Divides each feature [default: 16]
+            --shape WxH[xC]       Image shape of the features
+";
+
+/// Runs the subcommand given on the command line.
+///
+/// @param arguments Command line arguments without the program name
+pub fn run (arguments: &[String]) -> Result<()> {
+  let (command, options) = match arguments.split_first() {
+    Some((command, options)) => (command.as_str(), options),
+    None => return Err(Error::Usage("Missing command.".to_string())),
+  };
+
+  match command {
+    "train" => train::run(options),
+    "cross-validate" => cross_validate::run(options),
+    "evaluate" => evaluate::run(options),
+    "predict" => predict::run(options),
+    "inspect" => inspect::run(options),
+    "convert" => convert::run(options),
+    "split" => split::run(options),
+    "help" | "--help" => {
+      print!("{}", USAGE);
+      Ok(())
+    },
+    _ => Err(Error::Usage(format!("Unknown command '{}'.", command))),
+  }
+}
+
+/// Options describing the layout of data files, shared by all commands that
+/// read them. The "header" switch is the only one without a value.
+pub const DATA_OPTIONS: [&str; 5] = ["delimiter", "label-column", "comment", "scale", "shape"];
+
+/// Reads the layout of data files from the command line. Without any options
+/// this is the optdigits layout.
+///
+/// @param args Options of the subcommand
+/// @return Layout of the data files
+pub fn csv_options (args: &Args) -> Result<CsvOptions> {
+  let mut options = CsvOptions::optdigits();
+  let usage = |error: Error| Error::Usage(error.to_string());
+
+  if let Some(delimiter) = args.optional("delimiter") {
+    options.delimiter = match delimiter {
+      "tab" | "\\t" => '\t',
+      _ if delimiter.chars().count() == 1 => delimiter.chars().next().unwrap(),
+      _ => return Err(Error::Usage("Delimiter must be a single character.".to_string())),
+    };
+  }
+
+  if let Some(column) = args.optional("label-column") {
+    options.label_column = column.parse().map_err(&usage)?;
+  }
+
+  if let Some(comment) = args.optional("comment") {
+    options.comment = comment.chars().next();
+  }
+
+  if let Some(shape) = args.optional("shape") {
+    options.shape = Some(shape.parse().map_err(&usage)?);
+  }
+
+  options.header = args.flag("header");
+  options.scale = args.parsed_or("scale", options.scale)?;
+
+  Ok(options)
+}
+
+/// Switches describing the data files, shared by all commands that read them.
+pub const DATA_FLAGS: [&str; 3] = ["header", "invert", "stretch"];
+
+/// Options and switches of the data files with the formats they apply to.
+const FORMAT_OPTIONS: [(&str, &[&str]); 9] = [
+  ("delimiter", &["csv"]),
+  ("header", &["csv"]),
+  ("label-column", &["csv"]),
+  ("comment", &["csv"]),
+  ("scale", &["csv"]),
+  ("shape", &["csv", "images"]),
+  ("block", &["bitmap"]),
+  ("invert", &["images"]),
+  ("stretch", &["images"]),
+];
+
+/// Reads kind of the data files from the command line. Options that do not
+/// apply to the chosen format are rejected.
+///
+/// @param args Options of the subcommand
+/// @return Kind and layout of the data files
+pub fn data_format (args: &Args) -> Result<DataFormat> {
+  let format = args.optional("format").unwrap_or("csv");
+
+  if !["csv", "idx", "bitmap", "images", "cache"].contains(&format) {
+    return Err(Error::Usage(format!(
+      "Unknown format '{}', use csv, idx, bitmap, images or cache.", format,
+    )));
+  }
+
+  for (option, formats) in FORMAT_OPTIONS.iter() {
+    if !formats.contains(&format) && (args.optional(option).is_some() || args.flag(option)) {
+      return Err(Error::Usage(format!(
+        "Option --{} only applies to {} format.", option, formats.join(" and "),
+      )));
+    }
+  }
+
+  match format {
+    "csv" => Ok(DataFormat::Csv(csv_options(args)?)),
+    "idx" => Ok(DataFormat::Idx),
+    "cache" => Ok(DataFormat::Cache),
+    "bitmap" => match args.parsed_or("block", 1)? {
+      0 => Err(Error::Usage("Block must be at least 1.".to_string())),
+      block => Ok(DataFormat::Bitmap { block }),
+    },
+    _ => {
+      let shape = args.optional("shape")
+        .ok_or_else(|| Error::Usage("Images format needs --shape.".to_string()))?
+        .parse()
+        .map_err(|error: Error| Error::Usage(error.to_string()))?;
+
+      Ok(DataFormat::Images(ImageOptions {
+        shape,
+        invert: args.flag("invert"),
+        stretch: args.flag("stretch"),
+      }))
+    },
+  }
+}
+
+/// Scores the network over a holdout data set.
+///
+/// @param network Trained network
+/// @param testing_data Labelled samples the network was not trained on
+/// @return Tuple in format (successful_classifications, samples)
+pub fn validate_network (
+  network: &Network,
+  testing_data: &dyn Dataset,
+) -> (usize, usize) {
+  // Calculate successful attemps over the holdout data set.
+  let success: usize = testing_data.iter()
+    .fold(0, |success, (target, inputs)| {
+      let succeded: bool = network.classify(inputs) == target;
+
+      if succeded { success + 1 } else { success }
+    });
+
+  (success, testing_data.len())
+}
+
+/// Checks that the samples fit the input and output layers of the network.
+///
+/// @param network Network the samples are going to be fed into
+/// @param data Samples to check
+pub fn check_samples (network: &Network, data: &dyn Dataset) -> Result<()> {
+  let schema = network.schema();
+  let (inputs, outputs) = (schema[0], schema[schema.len() - 1]);
+
+  if data.classes() > outputs {
+    return Err(Error::Invalid(format!(
+      "Data has {} classes but the network only has {} outputs.",
+      data.classes(), outputs,
+    )));
+  }
+
+  if data.features() != inputs {
+    return Err(Error::Invalid(format!(
+      "Samples have {} features but the network expects {}.",
+      data.features(), inputs,
+    )));
+  }
+
+  for (index, (class, _)) in data.iter().enumerate() {
+    if class >= outputs {
+      return Err(Error::Invalid(format!(
+        "Sample {} has class {} but the network only has {} outputs.",
+        index + 1, class, outputs,
+      )));
+    }
+  }
+
+  Ok(())
+}
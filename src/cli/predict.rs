@@ -0,0 +1,118 @@
+use std::fs;
+use error::{Error, Result};
+use image::Image;
+use model::Model;
+use neural_network::network_classify::{Criterion, Rejection};
+use reader::csv;
+use reader::directory::ImageOptions;
+use super::args::Args;
+
+/// Printed instead of a label when the prediction is rejected.
+const UNKNOWN: &str = "unknown";
+
+/// Prints the label of each input row or image. Rows hold delimited features
+/// without a label. With --top-k the labels of the most likely classes are
+/// printed, each followed by its probability. With --reject-below inputs the
+/// model is not confident about are labelled unknown.
+///
+/// @param arguments Options of the subcommand
+pub fn run (arguments: &[String]) -> Result<()> {
+  let mut options = super::DATA_OPTIONS.to_vec();
+  options.extend(&["model", "input", "image", "top-k", "reject-below", "reject-by"]);
+
+  let args = Args::parse(arguments, &options, &super::DATA_FLAGS)?;
+  let model = Model::load(args.required("model")?)?;
+
+  match (args.optional("input"), args.all("image")) {
+    (Some(path), ref images) if images.is_empty() => predict_rows(&model, path, &args),
+    (None, ref images) if !images.is_empty() => predict_images(&model, images, &args),
+    _ => Err(Error::Usage("Either --input or --image is required.".to_string())),
+  }
+}
+
+/// Prints the label of each row of a delimited text file.
+///
+/// @param model Model to classify with
+/// @param path Path to the rows
+/// @param args Options of the subcommand
+fn predict_rows (model: &Model, path: &str, args: &Args) -> Result<()> {
+  let inputs = model.inputs();
+
+  let source = fs::read_to_string(path)
+    .map_err(|error| Error::Invalid(format!("Cannot read {}: {}", path, error)))?;
+
+  let rows = csv::read_features(&source, &super::csv_options(args)?)
+    .map_err(|error| Error::Invalid(format!("Cannot read {}: {}", path, error)))?;
+
+  for (index, features) in rows.iter().enumerate() {
+    if features.len() != inputs {
+      return Err(Error::Invalid(format!(
+        "Row {} has {} features but the network expects {}.",
+        index + 1, features.len(), inputs,
+      )));
+    }
+
+    println!("{}", prediction(model, features, args)?);
+  }
+
+  Ok(())
+}
+
+/// Prints the path and label of each image. Images are resized to the input
+/// shape of the model.
+///
+/// @param model Model to classify with
+/// @param paths Paths to the images
+/// @param args Options of the subcommand
+fn predict_images (model: &Model, paths: &[&str], args: &Args) -> Result<()> {
+  let options = ImageOptions {
+    shape: model.input_shape()?,
+    invert: args.flag("invert"),
+    stretch: args.flag("stretch"),
+  };
+
+  for path in paths {
+    let features = options.features(&Image::load(path)?)?;
+
+    println!("{}\t{}", path, prediction(model, &features, args)?);
+  }
+
+  Ok(())
+}
+
+/// @param model Model to classify with
+/// @param features Raw features of one input
+/// @param args Options of the subcommand
+/// @return Label of the class, unknown if it is rejected, or the tab
+/// separated labels and probabilities of the top k classes
+fn prediction (model: &Model, features: &[f64], args: &Args) -> Result<String> {
+  let rejection = match args.parsed("reject-below")? {
+    Some(threshold) => Some(Rejection {
+      criterion: args.parsed_or("reject-by", Criterion::Probability)?,
+      threshold,
+    }),
+    None if args.optional("reject-by").is_some() => {
+      return Err(Error::Usage("Option --reject-by needs --reject-below.".to_string()));
+    },
+    None => None,
+  };
+
+  let k = match (args.parsed::<usize>("top-k")?, rejection) {
+    (Some(_), Some(_)) => {
+      return Err(Error::Usage("Option --top-k cannot be combined with --reject-below.".to_string()));
+    },
+    (Some(k), None) => k,
+    (None, Some(rejection)) => {
+      return Ok(model.classify_or_reject(features, &rejection)
+        .map_or_else(|| UNKNOWN.to_string(), |class| model.label(class)));
+    },
+    (None, None) => return Ok(model.label(model.classify(features))),
+  };
+
+  Ok(
+    model.top_k(features, k).into_iter()
+      .map(|(class, probability)| format!("{}\t{:.4}", model.label(class), probability))
+      .collect::<Vec<String>>()
+      .join("\t")
+  )
+}
@@ -0,0 +1,72 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use dataset::Dataset;
+use dataset::split::{self, Ratios};
+use error::Result;
+use reader::{self, DataFormat};
+use reader::cache::{self, Precision};
+use reader::csv;
+use super::args::Args;
+
+/// Splits labelled data into training, validation and test files. Delimited
+/// text is written in the layout it was read with, every other format into
+/// caches, which keep the image shape.
+///
+/// @param arguments Options of the subcommand
+pub fn run (arguments: &[String]) -> Result<()> {
+  let mut options = super::DATA_OPTIONS.to_vec();
+  options.extend(&[
+    "format", "block", "data", "data-labels", "ratios", "seed", "output", "precision",
+  ]);
+
+  let mut flags = super::DATA_FLAGS.to_vec();
+  flags.push("stratify");
+
+  let args = Args::parse(arguments, &options, &flags)?;
+  let format = super::data_format(&args)?;
+
+  let data = reader::load_dataset(
+    Path::new(args.required("data")?),
+    args.optional("data-labels").map(Path::new),
+    &format,
+    None,
+  )?;
+
+  let ratios: Ratios = args.parsed_or("ratios", Ratios::new(0.7_f64, 0.15_f64, 0.15_f64)?)?;
+  let seed: u64 = args.parsed_or("seed", 0)?;
+  let precision: Precision = args.parsed_or("precision", Precision::F32)?;
+
+  let directory = Path::new(args.required("output")?);
+  fs::create_dir_all(directory)?;
+
+  let splits = split::split(&data, &ratios, args.flag("stratify"), seed);
+  let partitions: [(&str, &dyn Dataset); 3] = [
+    ("train", &splits.train),
+    ("validation", &splits.validation),
+    ("test", &splits.test),
+  ];
+
+  for (name, partition) in partitions.iter() {
+    if partition.is_empty() {
+      continue;
+    }
+
+    let path = match format {
+      DataFormat::Csv(ref options) => {
+        let path = directory.join(format!("{}.csv", name));
+        csv::write_csv(*partition, options, BufWriter::new(File::create(&path)?))?;
+        path
+      },
+      _ => {
+        let path = directory.join(format!("{}.cache", name));
+        cache::save_cache(*partition, precision, &path)?;
+        path
+      },
+    };
+
+    println!("Wrote {} samples to {}.", partition.len(), path.display());
+  }
+
+  Ok(())
+}
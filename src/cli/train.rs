@@ -0,0 +1,533 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use augment::Augmenter;
+use config::Config;
+use dataset::Dataset;
+use dataset::in_memory::InMemory;
+use dataset::stream::Stream;
+use error::{Error, Result};
+use json::Json;
+use manifest::{self, DatasetFile, Manifest};
+use model::Model;
+use neural_network::activation::Activation;
+use neural_network::network::Network;
+use preprocess::{Pipeline, Step};
+use preprocess::normalize::Scope;
+use preprocess::pca::Components;
+use reader::{self, DataFormat};
+use reader::sample::Shape;
+use reader::stream::CsvStream;
+use trainer::{Ordering, Trainer};
+use trainer::balance::{ClassWeights, Sampler};
+use trainer::callback::Progress;
+use trainer::json_log::JsonLog;
+use trainer::mixing::{Mixer, Mixing};
+use trainer::optimizer::Momentum;
+use trainer::schedule::Schedule;
+use super::args::Args;
+
+/// Options that describe the run itself rather than where its results go.
+/// They cannot be combined with a config file.
+pub const HYPERPARAMETERS: [&str; 32] = [
+  "train", "train-labels", "validation", "validation-labels", "shuffle-buffer", "normalize",
+  "normalize-scope", "pca", "pca-variance", "topology", "activation", "batch-size", "epochs", "learning-rate", "min-lr",
+  "max-lr", "step-size", "momentum", "augment", "label-smoothing", "mixup", "cutmix",
+  "class-weights", "sampling", "seed", "format", "block", "delimiter", "label-column", "comment",
+  "scale", "shape",
+];
+
+/// Samples read at once when a stream is only evaluated.
+const BATCH: usize = 1024;
+
+/// Where the results of a training run are written.
+struct Outputs {
+
+  /// Model file.
+  model: PathBuf,
+
+  /// JSON lines log with statistics of each epoch.
+  log: Option<PathBuf>,
+
+}
+
+/// Trains a new network and writes it into a model file. The run is either
+/// described by a config file or by the command line options.
+///
+/// @param arguments Options of the subcommand
+pub fn run (arguments: &[String]) -> Result<()> {
+  let mut options = HYPERPARAMETERS.to_vec();
+  options.extend(&["config", "log", "output"]);
+
+  let mut flags = super::DATA_FLAGS.to_vec();
+  flags.extend(&["stream", "progress"]);
+
+  let args = Args::parse(arguments, &options, &flags)?;
+
+  let (config, outputs) = match args.optional("config") {
+    Some(path) => {
+      check_without_hyperparameters(&args)?;
+
+      let config = Config::load(path)?;
+      let outputs = prepare_output_directory(&config, Path::new(path), &args)?;
+
+      (config, outputs)
+    },
+    None => {
+      let config = config_from_args(&args)?;
+      let outputs = Outputs {
+        model: PathBuf::from(args.optional("output").unwrap_or("model.json")),
+        log: args.optional("log").map(PathBuf::from),
+      };
+
+      (config, outputs)
+    },
+  };
+
+  train(&config, &outputs, args.flag("progress"))
+}
+
+/// Makes sure that the run is not described twice, by a config file and by
+/// command line options.
+///
+/// @param args Options of the subcommand
+pub fn check_without_hyperparameters (args: &Args) -> Result<()> {
+  let given = HYPERPARAMETERS.iter()
+    .find(|option| args.optional(option).is_some())
+    .or_else(|| super::DATA_FLAGS.iter().find(|flag| args.flag(flag)))
+    .or_else(|| Some(&"stream").filter(|flag| args.flag(flag)));
+
+  match given {
+    Some(option) => Err(Error::Usage(format!(
+      "Option --{} cannot be combined with --config.", option,
+    ))),
+    None => Ok(()),
+  }
+}
+
+/// Builds the run config from the command line options.
+///
+/// @param args Options of the subcommand
+/// @return Validated Config instance
+pub fn config_from_args (args: &Args) -> Result<Config> {
+  let mut config = Config::new(PathBuf::from(args.required("train")?));
+
+  config.train_labels = args.optional("train-labels").map(PathBuf::from);
+  config.validation = args.optional("validation").map(PathBuf::from);
+  config.validation_labels = args.optional("validation-labels").map(PathBuf::from);
+  config.format = super::data_format(args)?;
+  config.stream = args.flag("stream");
+  config.shuffle_buffer = args.parsed_or("shuffle-buffer", config.shuffle_buffer)?;
+
+  if let Some(topology) = args.list("topology")? {
+    config.topology = topology;
+  }
+
+  if let Some(activation) = args.optional("activation") {
+    config.activation = activation.to_string();
+  }
+
+  let step_size: f64 = args.parsed_or("step-size", 8_f64)?;
+
+  config.schedule = match args.parsed("learning-rate")? {
+    Some(learning_rate) => Schedule::Constant(learning_rate),
+    None => Schedule::Cyclical {
+      min_lr: args.parsed_or("min-lr", 0.1_f64)?,
+      max_lr: args.parsed_or("max-lr", 0.8_f64)?,
+      step_size,
+    },
+  };
+
+  config.batch_size = args.parsed_or("batch-size", config.batch_size)?;
+  config.epochs = args.parsed_or("epochs", ::config::default_epochs(&config.schedule))?;
+  config.momentum = args.parsed("momentum")?;
+  config.augmentation = args.parsed("augment")?;
+  config.label_smoothing = args.parsed_or("label-smoothing", 0_f64)?;
+  config.mixup = args.parsed("mixup")?;
+  config.cutmix = args.parsed("cutmix")?;
+  config.class_weights = args.parsed("class-weights")?;
+  config.sampling = args.parsed("sampling")?;
+  config.seed = args.parsed("seed")?;
+
+  match (args.parsed("normalize")?, args.parsed("normalize-scope")?) {
+    (Some(method), scope) => config.preprocessing.push(Step::Normalize {
+      method,
+      scope: scope.unwrap_or(Scope::Feature),
+    }),
+    (None, Some(_)) => {
+      return Err(Error::Usage("Option --normalize-scope needs --normalize.".to_string()));
+    },
+    (None, None) => (),
+  }
+
+  match (args.parsed("pca")?, args.parsed("pca-variance")?) {
+    (Some(count), None) => config.preprocessing.push(Step::Pca {
+      components: Components::Count(count),
+    }),
+    (None, Some(variance)) => config.preprocessing.push(Step::Pca {
+      components: Components::Variance(variance),
+    }),
+    (Some(_), Some(_)) => {
+      return Err(Error::Usage("Option --pca cannot be combined with --pca-variance.".to_string()));
+    },
+    (None, None) => (),
+  }
+
+  config.validate().map_err(|error| Error::Usage(error.to_string()))?;
+
+  Ok(config)
+}
+
+/// Creates the output directory of a config driven run and copies the config
+/// file into it, so that the run can be reproduced from the directory alone.
+///
+/// @param config Loaded config
+/// @param path Path to the config file
+/// @param args Options of the subcommand, which can override the outputs
+/// @return Where to write the results
+fn prepare_output_directory (config: &Config, path: &Path, args: &Args) -> Result<Outputs> {
+  let directory = match config.output {
+    Some(ref directory) => directory.clone(),
+    None => {
+      return Ok(Outputs {
+        model: PathBuf::from(args.optional("output").unwrap_or("model.json")),
+        log: args.optional("log").map(PathBuf::from),
+      });
+    },
+  };
+
+  fs::create_dir_all(&directory)?;
+  fs::copy(path, directory.join("config.json"))?;
+
+  Ok(Outputs {
+    model: args.optional("output")
+      .map_or_else(|| directory.join("model.json"), PathBuf::from),
+    log: Some(args.optional("log")
+      .map_or_else(|| directory.join("training_log.jsonl"), PathBuf::from)),
+  })
+}
+
+/// Training data of a run, either loaded at once or read batch by batch.
+enum TrainingData {
+  Loaded(InMemory),
+  Streamed(Box<dyn Stream>),
+}
+
+/// Trains a network as described by the config.
+///
+/// @param config Validated config
+/// @param outputs Where to write the results
+/// @param progress Whether to print statistics after each epoch
+fn train (config: &Config, outputs: &Outputs, progress: bool) -> Result<()> {
+  let started_at = manifest::now();
+  let training_data = match config.format {
+    DataFormat::Csv(ref options) if config.stream => {
+      let stream = CsvStream::open(&config.train, options, None)?;
+
+      TrainingData::Streamed(Box::new(match config.seed {
+        Some(seed) => stream.shuffled(config.shuffle_buffer, seed),
+        None => stream,
+      }))
+    },
+    _ => TrainingData::Loaded(reader::load_dataset(
+      &config.train,
+      config.train_labels.as_deref(),
+      &config.format,
+      None,
+    )?),
+  };
+  let labels = match training_data {
+    TrainingData::Loaded(ref data) => data.labels().to_vec(),
+    TrainingData::Streamed(ref stream) => stream.labels().to_vec(),
+  };
+
+  // Validation labels must map onto the same classes as the training ones.
+  let validation_data = match config.validation {
+    Some(ref path) => Some(reader::load_dataset(
+      path,
+      config.validation_labels.as_deref(),
+      &config.format,
+      Some(&labels),
+    )?),
+    None => None,
+  };
+
+  // Images keep the shape of their raw pixels, whatever the preprocessing
+  // turns them into.
+  let shape = match training_data {
+    TrainingData::Loaded(ref data) => data.shape(),
+    TrainingData::Streamed(_) => match config.format {
+      DataFormat::Csv(ref options) => options.shape,
+      _ => None,
+    },
+  };
+
+  let features = match training_data {
+    TrainingData::Loaded(ref data) => data.features(),
+    TrainingData::Streamed(ref stream) => stream.features().unwrap_or(config.topology[0]),
+  };
+  let augmenter = augmenter(config, shape, features)?;
+  let mixer = mixer(config, shape, features)?;
+
+  // Preprocessing is fitted on the training data only and then applied to
+  // the validation data alike. Config makes sure that streams have none.
+  // Augmentation distorts the raw images, so they are kept next to the
+  // preprocessed ones, which are only used to check and score the network.
+  let (mut training_data, raw, pipeline) = match training_data {
+    TrainingData::Loaded(data) => {
+      let pipeline = Pipeline::fit(&config.preprocessing, &data)?;
+      let raw = Some(data.clone()).filter(|_| augmenter.is_some() && !pipeline.is_empty());
+
+      (TrainingData::Loaded(pipeline.transform(data)), raw, pipeline)
+    },
+    streamed => (streamed, None, Pipeline::new()),
+  };
+  let validation_data = validation_data.map(|data| pipeline.transform(data));
+
+  let inputs = match training_data {
+    TrainingData::Loaded(ref data) => data.features(),
+    TrainingData::Streamed(_) => config.topology[0],
+  };
+  let mut network = network(config, inputs);
+
+  match training_data {
+    TrainingData::Loaded(ref data) => super::check_samples(&network, data)?,
+    // Only the first sample is checked upfront, the parser makes sure that
+    // all other rows have as many features.
+    TrainingData::Streamed(ref mut stream) => {
+      if let Some(batch) = stream.next_batch(1)? {
+        super::check_samples(&network, &batch)?;
+      }
+    },
+  }
+  if let Some(ref data) = validation_data {
+    super::check_samples(&network, data)?;
+  }
+
+  let mut trainer = trainer(config);
+
+  if progress {
+    trainer.add_callback(Box::new(Progress));
+  }
+
+  if let Some(ref path) = outputs.log {
+    // Writes statistics of each epoch into a file for later analysis.
+    trainer.add_callback(Box::new(JsonLog::create(path)?));
+  }
+
+  if let Some(augmenter) = augmenter {
+    trainer.add_transform(Box::new(augmenter));
+  }
+  trainer.mixer = mixer;
+  trainer.class_weights = match (&config.class_weights, &training_data) {
+    (Some(ref weights), TrainingData::Loaded(ref data)) => weights.resolve(data)?,
+    // Config makes sure that streams come with one weight per class.
+    (Some(ClassWeights::Manual(ref weights)), TrainingData::Streamed(_))
+      if weights.len() == labels.len() => weights.clone(),
+    (Some(_), TrainingData::Streamed(_)) => return Err(Error::Invalid(format!(
+      "Class weights must be given for all {} classes of the data.", labels.len(),
+    ))),
+    (None, _) => Vec::new(),
+  };
+  if raw.is_some() {
+    trainer.add_transform(Box::new(pipeline.clone()));
+  }
+
+  // Trains the network on the training data.
+  let summary = match (&mut training_data, &validation_data) {
+    (TrainingData::Loaded(ref data), Some(ref validation)) => {
+      trainer.fit_with_validation(&mut network, raw.as_ref().unwrap_or(data), validation)
+    },
+    (TrainingData::Loaded(ref data), None) => {
+      trainer.fit(&mut network, raw.as_ref().unwrap_or(data))
+    },
+    (TrainingData::Streamed(ref mut stream), validation) => {
+      let validation = validation.as_ref().map(|data| data as &dyn Dataset);
+      trainer.fit_stream(&mut network, stream.as_mut(), validation)?
+    },
+  };
+
+  let (success, total) = match training_data {
+    TrainingData::Loaded(ref data) => super::validate_network(&network, data),
+    TrainingData::Streamed(ref mut stream) => validate_stream(&network, stream.as_mut())?,
+  };
+
+  println!(
+    "Done {} iterations on {} samples in {:?}.",
+    summary.epochs, total, summary.duration,
+  );
+
+  let mut datasets = vec!(DatasetFile::hash("train", &config.train, total)?);
+  if let Some(ref path) = config.train_labels {
+    datasets.push(DatasetFile::hash("train_labels", path, total)?);
+  }
+
+  let mut metrics = vec!(("train", accuracy((success, total))));
+
+  if let (Some(ref path), Some(ref data)) = (&config.validation, &validation_data) {
+    let (success, total) = super::validate_network(&network, data);
+
+    println!("Correct {} out of {}.", success, total);
+
+    datasets.push(DatasetFile::hash("validation", path, data.len())?);
+    if let Some(ref path) = config.validation_labels {
+      datasets.push(DatasetFile::hash("validation_labels", path, data.len())?);
+    }
+    metrics.push(("validation", accuracy((success, total))));
+  }
+
+  let mut model = Model::new(network);
+  model.labels = labels;
+  model.shape = shape;
+  model.preprocessing = pipeline;
+  model.manifest = Some(Manifest {
+    commit: manifest::git_commit(),
+    config: config.to_json(),
+    datasets,
+    seed: config.seed,
+    started_at,
+    finished_at: manifest::now(),
+    epochs: summary.epochs,
+    metrics: Json::object(metrics),
+  });
+
+  model.save(&outputs.model)?;
+  println!("Model written to {}.", outputs.model.display());
+
+  Ok(())
+}
+
+/// Bootstraps new network with randomly chosen weights, which are the same
+/// for every run with the same seed. When the preprocessing decides the
+/// number of features, e.g. PCA with a variance target, the input layer
+/// follows it instead of the topology.
+///
+/// @param config Validated config
+/// @param inputs Number of features after the preprocessing
+/// @return Untrained network
+pub fn network (config: &Config, inputs: usize) -> Network {
+  // Config is validated so the activation is known.
+  let activation = Activation::from_name(&config.activation).unwrap();
+
+  let mut topology = config.topology.clone();
+  if config.preprocessing.iter().any(Step::changes_features) {
+    topology[0] = inputs;
+  }
+
+  match config.seed {
+    Some(seed) => Network::seeded(activation, topology, seed),
+    None => Network::new(activation, topology),
+  }
+}
+
+/// Builds the trainer the config describes, without any callbacks.
+///
+/// @param config Validated config
+/// @return New Trainer instance
+pub fn trainer (config: &Config) -> Trainer {
+  let mut trainer = Trainer::new(config.schedule.clone());
+  trainer.batch_size = config.batch_size;
+  trainer.epochs = config.epochs;
+  trainer.label_smoothing = config.label_smoothing;
+
+  if let Some(momentum) = config.momentum {
+    trainer.optimizer = Box::new(Momentum::new(momentum));
+  }
+
+  if let Some(seed) = config.seed {
+    trainer.ordering = Ordering::Shuffled(seed);
+  }
+
+  trainer.sampler = config.sampling.map(|sampling| match config.seed {
+    Some(seed) => Sampler::seeded(sampling, seed),
+    None => Sampler::new(sampling),
+  });
+
+  trainer
+}
+
+/// Builds the augmenter of the config, seeded by the seed of the run.
+///
+/// @param config Validated config
+/// @param shape Shape of the raw training samples, if known
+/// @param features Number of raw features of a sample
+/// @return Augmenter unless the config distorts nothing
+pub fn augmenter (
+  config: &Config,
+  shape: Option<Shape>,
+  features: usize,
+) -> Result<Option<Augmenter>> {
+  let augmentation = match config.augmentation {
+    Some(augmentation) if augmentation.is_enabled() => augmentation,
+    _ => return Ok(None),
+  };
+
+  let shape = image_shape("Augmentation", shape, features)?;
+
+  Ok(Some(match config.seed {
+    Some(seed) => Augmenter::seeded(augmentation, shape, seed),
+    None => Augmenter::new(augmentation, shape),
+  }))
+}
+
+/// Builds the mixer of the config, seeded by the seed of the run.
+///
+/// @param config Validated config
+/// @param shape Shape of the raw training samples, if known
+/// @param features Number of raw features of a sample
+/// @return Mixer unless the config blends no samples
+pub fn mixer (config: &Config, shape: Option<Shape>, features: usize) -> Result<Option<Mixer>> {
+  let mixing = match (config.mixup, config.cutmix) {
+    (Some(alpha), _) => Mixing::Mixup { alpha },
+    (None, Some(alpha)) => Mixing::CutMix {
+      alpha,
+      shape: image_shape("CutMix", shape, features)?,
+    },
+    (None, None) => return Ok(None),
+  };
+
+  Ok(Some(match config.seed {
+    Some(seed) => Mixer::seeded(mixing, seed),
+    None => Mixer::new(mixing),
+  }))
+}
+
+/// Samples without a shape, such as rows of a delimited file, are taken for
+/// square grayscale images.
+///
+/// @param name What needs the shape, for the error
+/// @param shape Shape of the raw training samples, if known
+/// @param features Number of raw features of a sample
+/// @return Shape of the samples
+fn image_shape (name: &str, shape: Option<Shape>, features: usize) -> Result<Shape> {
+  shape.or_else(|| Shape::square(features)).ok_or_else(|| Error::Invalid(format!(
+    "{} needs the image shape of the {} features, set it with --shape.", name, features,
+  )))
+}
+
+/// Counts correct classifications over one more pass of a stream.
+///
+/// @param network Network to evaluate
+/// @param stream Labelled samples
+/// @return Tuple in format (successful_classifications, samples)
+fn validate_stream (network: &Network, stream: &mut dyn Stream) -> Result<(usize, usize)> {
+  let (mut success, mut total) = (0, 0);
+
+  stream.rewind()?;
+  while let Some(batch) = stream.next_batch(BATCH)? {
+    let (correct, samples) = super::validate_network(network, &batch);
+    success += correct;
+    total += samples;
+  }
+
+  Ok((success, total))
+}
+
+/// @param result Tuple in format (successful_classifications, samples)
+/// @return JSON object with the counts and the accuracy
+fn accuracy ((success, total): (usize, usize)) -> Json {
+  Json::object(vec!(
+    ("correct", success.into()),
+    ("total", total.into()),
+    ("accuracy", (success as f64 / total.max(1) as f64).into()),
+  ))
+}
@@ -0,0 +1,913 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use augment::{self, Augmentation};
+use json::Json;
+use error::{Error, Result};
+use neural_network::activation::Activation;
+use preprocess::Step;
+use preprocess::normalize::Scope;
+use preprocess::pca::Components;
+use trainer::balance::{ClassWeights, Sampling};
+use trainer::schedule::Schedule;
+use reader::DataFormat;
+use reader::csv::{CsvOptions, LabelColumn};
+use reader::directory::ImageOptions;
+use reader::sample::Shape;
+
+/// Declarative description of a training run. It can be loaded from a JSON
+/// file such as:
+///
+/// {
+///   "data": {
+///     "train": "input.txt",
+///     "validation": "cross_fold.txt",
+///     "format": { "delimiter": ",", "header": false, "label_column": "last", "scale": 16 }
+///   },
+///   "preprocessing": [{ "type": "normalize", "method": "standard", "scope": "feature" }],
+///   "network": { "topology": [64, 128, 128, 10], "activation": "sigmoid" },
+///   "training": {
+///     "batch_size": 10,
+///     "epochs": 105,
+///     "schedule": { "type": "cyclical", "min_lr": 0.1, "max_lr": 0.8, "step_size": 8 },
+///     "momentum": 0.9,
+///     "augmentation": { "translate": 1, "rotate": 10, "elastic": 2, "noise": 0.05 },
+///     "label_smoothing": 0.1,
+///     "mixup": 0.2,
+///     "class_weights": "balanced",
+///     "sampling": "oversample"
+///   },
+///   "seed": 42,
+///   "output": "runs/optdigits"
+/// }
+///
+/// Only "data.train" is required, everything else has the same defaults as
+/// the train command. Relative paths are resolved against the directory of
+/// the config file.
+///
+/// MNIST style IDX files keep labels in separate files:
+///
+/// "data": {
+///   "train": "train-images-idx3-ubyte",
+///   "train_labels": "train-labels-idx1-ubyte",
+///   "format": { "type": "idx" }
+/// }
+///
+/// Original optdigits bitmaps can be averaged over blocks of pixels:
+///
+/// "data": { "train": "optdigits-orig.tra", "format": { "type": "bitmap", "block": 4 } }
+///
+/// Directories with a directory of images per class are resized to a shape:
+///
+/// "data": { "train": "digits", "format": { "type": "images", "shape": "8x8", "invert": true } }
+///
+/// Delimited files larger than memory can be streamed, shuffling the
+/// training samples within a buffer of given size:
+///
+/// "data": { "train": "large.csv", "stream": true, "shuffle_buffer": 10000 }
+#[derive(Debug, Clone)]
+pub struct Config {
+
+  /// Training data.
+  pub train: PathBuf,
+
+  /// Labels of the training data for formats that keep them separately.
+  pub train_labels: Option<PathBuf>,
+
+  /// Data to report accuracy on after each epoch.
+  pub validation: Option<PathBuf>,
+
+  /// Labels of the validation data for formats that keep them separately.
+  pub validation_labels: Option<PathBuf>,
+
+  /// Kind and layout of the data files.
+  pub format: DataFormat,
+
+  /// Read the training data batch by batch instead of loading it.
+  pub stream: bool,
+
+  /// Number of samples shuffled together while streaming.
+  pub shuffle_buffer: usize,
+
+  /// Steps fitted on the training data and applied to every input.
+  pub preprocessing: Vec<Step>,
+
+  /// Number of inputs followed by number of neurons in each layer.
+  pub topology: Vec<usize>,
+
+  /// Name of the activation function.
+  pub activation: String,
+
+  /// How many samples are averaged into one update of the network.
+  pub batch_size: usize,
+
+  /// How many times should the training data be processed.
+  pub epochs: usize,
+
+  /// Learning rate for each epoch.
+  pub schedule: Schedule,
+
+  /// Use momentum optimizer with this coefficient instead of plain SGD.
+  pub momentum: Option<f64>,
+
+  /// Random distortions of the training images, applied anew each epoch.
+  pub augmentation: Option<Augmentation>,
+
+  /// Share of each label spread evenly over all classes.
+  pub label_smoothing: f64,
+
+  /// Blend pairs of samples and their labels with weights drawn from
+  /// Beta(alpha, alpha) with this alpha.
+  pub mixup: Option<f64>,
+
+  /// Paste boxes of one image into another, with the box area drawn from
+  /// Beta(alpha, alpha) with this alpha.
+  pub cutmix: Option<f64>,
+
+  /// Factor of the loss of each class, "balanced" or one weight per class.
+  pub class_weights: Option<ClassWeights>,
+
+  /// Draw class balanced epochs by over- or undersampling.
+  pub sampling: Option<Sampling>,
+
+  /// Seed for the initial weights and for shuffling the data. Without a seed
+  /// the weights are random and the data is not shuffled.
+  pub seed: Option<u64>,
+
+  /// Directory to write the model, the log and a copy of the config into.
+  pub output: Option<PathBuf>,
+
+}
+
+impl Config {
+
+  /// Builds config with default hyperparameters: the 64-128-128-10 sigmoid
+  /// network trained in batches of 10 with cyclical learning rate between
+  /// 0.1 and 0.8.
+  ///
+  /// @param train Training data
+  /// @return New Config instance
+  pub fn new (train: PathBuf) -> Self {
+    let schedule = Schedule::Cyclical { min_lr: 0.1_f64, max_lr: 0.8_f64, step_size: 8_f64 };
+
+    Config {
+      train,
+      train_labels: None,
+      validation: None,
+      validation_labels: None,
+      format: DataFormat::Csv(CsvOptions::optdigits()),
+      stream: false,
+      shuffle_buffer: 10_000,
+      preprocessing: Vec::new(),
+      topology: vec!(64, 128, 128, 10),
+      activation: "sigmoid".to_string(),
+      batch_size: 10,
+      epochs: default_epochs(&schedule),
+      schedule,
+      momentum: None,
+      augmentation: None,
+      label_smoothing: 0_f64,
+      mixup: None,
+      cutmix: None,
+      class_weights: None,
+      sampling: None,
+      seed: None,
+      output: None,
+    }
+  }
+
+  /// Reads and validates a config file.
+  ///
+  /// @param path Path to the config file
+  /// @return Validated Config instance
+  pub fn load<P: AsRef<Path>> (path: P) -> Result<Config> {
+    let path = path.as_ref();
+    let in_file = |error: Error| {
+      Error::Invalid(format!("Invalid config {}: {}", path.display(), error))
+    };
+
+    let source = fs::read_to_string(path).map_err(|error| in_file(error.into()))?;
+    let json = Json::parse(&source).map_err(&in_file)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    Config::from_json(&json, directory).map_err(&in_file)
+  }
+
+  /// Reads and validates config from its JSON form.
+  ///
+  /// @param json JSON representation of the config
+  /// @param directory Directory to resolve relative paths against
+  /// @return Validated Config instance
+  pub fn from_json (json: &Json, directory: &Path) -> Result<Config> {
+    let root = Section::new(
+      "",
+      json,
+      &["data", "preprocessing", "network", "training", "seed", "output"],
+    )?;
+
+    let data = Section::required(
+      &root,
+      "data",
+      &[
+        "train", "train_labels", "validation", "validation_labels", "format", "stream",
+        "shuffle_buffer",
+      ],
+    )?;
+    let mut config = Config::new(directory.join(data.string("train")?.ok_or_else(|| {
+      invalid("data.train", "is required")
+    })?));
+
+    config.train_labels = data.string("train_labels")?.map(|path| directory.join(path));
+    config.validation = data.string("validation")?.map(|path| directory.join(path));
+    config.validation_labels = data.string("validation_labels")?.map(|path| directory.join(path));
+
+    if let Some(format) = data.format("format")? {
+      config.format = format;
+    }
+
+    config.stream = data.bool("stream")?.unwrap_or(false);
+    if let Some(shuffle_buffer) = data.usize("shuffle_buffer")? {
+      config.shuffle_buffer = shuffle_buffer;
+    }
+
+    if let Some(preprocessing) = root.steps("preprocessing")? {
+      config.preprocessing = preprocessing;
+    }
+
+    if let Some(network) = Section::optional(&root, "network", &["topology", "activation"])? {
+      if let Some(topology) = network.usize_list("topology")? {
+        config.topology = topology;
+      }
+      if let Some(activation) = network.string("activation")? {
+        config.activation = activation;
+      }
+    }
+
+    let training = Section::optional(
+      &root,
+      "training",
+      &[
+        "batch_size", "epochs", "schedule", "momentum", "augmentation", "label_smoothing",
+        "mixup", "cutmix", "class_weights", "sampling",
+      ],
+    )?;
+
+    let mut epochs = None;
+    if let Some(training) = training {
+      if let Some(batch_size) = training.usize("batch_size")? {
+        config.batch_size = batch_size;
+      }
+      if let Some(schedule) = training.schedule("schedule")? {
+        config.schedule = schedule;
+      }
+      epochs = training.usize("epochs")?;
+      config.momentum = training.number("momentum")?;
+      config.augmentation = training.augmentation("augmentation")?;
+      config.label_smoothing = training.number("label_smoothing")?.unwrap_or(0_f64);
+      config.mixup = training.number("mixup")?;
+      config.cutmix = training.number("cutmix")?;
+      config.class_weights = training.class_weights("class_weights")?;
+      config.sampling = training.parsed("sampling")?;
+    }
+
+    // Default number of epochs follows the schedule unless given explicitly.
+    config.epochs = epochs.unwrap_or_else(|| default_epochs(&config.schedule));
+    config.seed = root.usize("seed")?.map(|seed| seed as u64);
+    config.output = root.string("output")?.map(|path| directory.join(path));
+
+    config.validate()?;
+
+    Ok(config)
+  }
+
+  /// Serializes the config into the same layout the config files use.
+  ///
+  /// @return JSON representation of the config
+  pub fn to_json (&self) -> Json {
+    let path = |path: &PathBuf| Json::from(path.display().to_string());
+
+    let schedule = match self.schedule {
+      Schedule::Constant(learning_rate) => Json::object(vec!(
+        ("type", "constant".into()),
+        ("learning_rate", learning_rate.into()),
+      )),
+      Schedule::Cyclical { min_lr, max_lr, step_size } => Json::object(vec!(
+        ("type", "cyclical".into()),
+        ("min_lr", min_lr.into()),
+        ("max_lr", max_lr.into()),
+        ("step_size", step_size.into()),
+      )),
+    };
+
+    let format = match self.format {
+      DataFormat::Csv(ref options) => Json::object(vec!(
+        ("type", "csv".into()),
+        ("delimiter", options.delimiter.to_string().into()),
+        ("header", options.header.into()),
+        ("label_column", match options.label_column {
+          LabelColumn::Index(index) => index.into(),
+          ref column => column.to_string().into(),
+        }),
+        ("comment", options.comment.map(|c| c.to_string()).into()),
+        ("scale", options.scale.into()),
+        ("shape", options.shape.map(|shape| shape.to_string()).into()),
+      )),
+      DataFormat::Idx => Json::object(vec!(("type", "idx".into()))),
+      DataFormat::Bitmap { block } => Json::object(vec!(
+        ("type", "bitmap".into()),
+        ("block", block.into()),
+      )),
+      DataFormat::Cache => Json::object(vec!(("type", "cache".into()))),
+      DataFormat::Images(ref options) => Json::object(vec!(
+        ("type", "images".into()),
+        ("shape", options.shape.to_string().into()),
+        ("invert", options.invert.into()),
+        ("stretch", options.stretch.into()),
+      )),
+    };
+
+    Json::object(vec!(
+      ("data", Json::object(vec!(
+        ("train", path(&self.train)),
+        ("train_labels", self.train_labels.as_ref().map(path).into()),
+        ("validation", self.validation.as_ref().map(path).into()),
+        ("validation_labels", self.validation_labels.as_ref().map(path).into()),
+        ("format", format),
+        ("stream", self.stream.into()),
+        ("shuffle_buffer", self.shuffle_buffer.into()),
+      ))),
+      ("preprocessing", Json::Array(self.preprocessing.iter().map(Step::to_json).collect())),
+      ("network", Json::object(vec!(
+        ("topology", self.topology.clone().into()),
+        ("activation", self.activation.as_str().into()),
+      ))),
+      ("training", Json::object(vec!(
+        ("batch_size", self.batch_size.into()),
+        ("epochs", self.epochs.into()),
+        ("schedule", schedule),
+        ("momentum", self.momentum.into()),
+        ("augmentation", self.augmentation.as_ref().map(Augmentation::to_json).into()),
+        ("label_smoothing", self.label_smoothing.into()),
+        ("mixup", self.mixup.into()),
+        ("cutmix", self.cutmix.into()),
+        ("class_weights", match self.class_weights {
+          Some(ClassWeights::Manual(ref weights)) => weights.clone().into(),
+          Some(ClassWeights::Balanced) => "balanced".into(),
+          None => Json::Null,
+        }),
+        ("sampling", self.sampling.map(|sampling| sampling.to_string()).into()),
+      ))),
+      ("seed", self.seed.map(|seed| seed as usize).into()),
+      ("output", self.output.as_ref().map(path).into()),
+    ))
+  }
+
+  /// Checks that the values make sense together and that the data exists.
+  pub fn validate (&self) -> Result<()> {
+    if self.topology.len() < 2 || self.topology.contains(&0) {
+      return Err(invalid(
+        "network.topology",
+        "needs at least two layers with at least one neuron each",
+      ));
+    }
+
+    if Activation::from_name(&self.activation).is_none() {
+      return Err(invalid(
+        "network.activation",
+        &format!("unknown activation '{}', use sigmoid or leaky_relu", self.activation),
+      ));
+    }
+
+    if let DataFormat::Csv(ref options) = self.format {
+      if options.scale == 0_f64 {
+        return Err(invalid("data.format.scale", "must not be 0"));
+      }
+    }
+
+    if let DataFormat::Bitmap { block: 0 } = self.format {
+      return Err(invalid("data.format.block", "must be at least 1"));
+    }
+
+    if self.format.needs_labels_file() {
+      if self.train_labels.is_none() {
+        return Err(invalid("data.train_labels", "is required by the data format"));
+      }
+      if self.validation.is_some() && self.validation_labels.is_none() {
+        return Err(invalid("data.validation_labels", "is required by the data format"));
+      }
+    }
+
+    if self.stream && !matches!(self.format, DataFormat::Csv(_)) {
+      return Err(invalid("data.stream", "is only supported for delimited text files"));
+    }
+
+    if self.stream && !self.preprocessing.is_empty() {
+      return Err(invalid("preprocessing", "cannot be fitted on streamed data"));
+    }
+
+    for step in self.preprocessing.iter() {
+      match *step {
+        Step::Pca { components: Components::Count(0) } => {
+          return Err(invalid("preprocessing.components", "must be at least 1"));
+        },
+        Step::Pca { components: Components::Variance(variance) }
+          if !(variance > 0_f64 && variance <= 1_f64) => {
+          return Err(invalid("preprocessing.variance", "must be in range (0, 1]"));
+        },
+        _ => (),
+      }
+    }
+
+    if self.shuffle_buffer == 0 {
+      return Err(invalid("data.shuffle_buffer", "must be at least 1"));
+    }
+
+    if self.batch_size == 0 {
+      return Err(invalid("training.batch_size", "must be at least 1"));
+    }
+
+    if self.epochs == 0 {
+      return Err(invalid("training.epochs", "must be at least 1"));
+    }
+
+    match self.schedule {
+      Schedule::Constant(learning_rate) if !(learning_rate.is_finite() && learning_rate > 0_f64) => {
+        return Err(invalid("training.schedule.learning_rate", "must be a positive number"));
+      },
+      Schedule::Cyclical { min_lr, max_lr, step_size } => {
+        if !(min_lr > 0_f64 && max_lr >= min_lr && max_lr.is_finite()) {
+          return Err(invalid(
+            "training.schedule",
+            "needs 0 < min_lr <= max_lr",
+          ));
+        }
+        if !(step_size.is_finite() && step_size > 0_f64) {
+          return Err(invalid("training.schedule.step_size", "must be a positive number"));
+        }
+      },
+      _ => (),
+    }
+
+    if let Some(momentum) = self.momentum {
+      if !(0_f64..1_f64).contains(&momentum) {
+        return Err(invalid("training.momentum", "must be in range [0, 1)"));
+      }
+    }
+
+    if !(0_f64..1_f64).contains(&self.label_smoothing) {
+      return Err(invalid("training.label_smoothing", "must be in range [0, 1)"));
+    }
+
+    for (field, alpha) in [("training.mixup", self.mixup), ("training.cutmix", self.cutmix)] {
+      if let Some(alpha) = alpha {
+        if !(alpha > 0_f64 && alpha.is_finite()) {
+          return Err(invalid(field, "must be a positive alpha"));
+        }
+      }
+    }
+
+    if self.stream && self.sampling.is_some() {
+      return Err(invalid("training.sampling", "needs the training data loaded, not streamed"));
+    }
+
+    if self.stream && self.class_weights == Some(ClassWeights::Balanced) {
+      return Err(invalid(
+        "training.class_weights",
+        "cannot count the classes of streamed data, give one weight per class",
+      ));
+    }
+
+    if (self.mixup.is_some() || self.cutmix.is_some()) && self.batch_size < 2 {
+      return Err(invalid("training.batch_size", "must be at least 2 to blend samples of a batch"));
+    }
+
+    if self.mixup.is_some() && self.cutmix.is_some() {
+      return Err(invalid("training.cutmix", "cannot be combined with training.mixup"));
+    }
+
+    if self.cutmix.is_some() && self.preprocessing.iter().any(Step::changes_features) {
+      return Err(invalid("training.cutmix", "needs image shaped inputs, not principal components"));
+    }
+
+    let files = [
+      ("data.train", Some(&self.train)),
+      ("data.train_labels", self.train_labels.as_ref()),
+      ("data.validation", self.validation.as_ref()),
+      ("data.validation_labels", self.validation_labels.as_ref()),
+    ];
+
+    // Images are read from directories, all other formats from files.
+    let directories = matches!(self.format, DataFormat::Images(_));
+
+    for (field, path) in files {
+      match path {
+        Some(path) if directories && !path.is_dir() => {
+          return Err(invalid(field, &format!("directory {} does not exist", path.display())));
+        },
+        Some(path) if !directories && !path.is_file() => {
+          return Err(invalid(field, &format!("file {} does not exist", path.display())));
+        },
+        _ => (),
+      }
+    }
+
+    Ok(())
+  }
+
+}
+
+/// Default number of epochs for a schedule: six and a half cycles plus one,
+/// so that the training ends at the lowest learning rate. Constant schedule
+/// uses the cycle of the default step size.
+///
+/// @param schedule Learning rate schedule
+/// @return Number of epochs
+pub fn default_epochs (schedule: &Schedule) -> usize {
+  let step_size = match *schedule {
+    Schedule::Cyclical { step_size, .. } => step_size,
+    Schedule::Constant(_) => 8_f64,
+  };
+
+  // Saturates for step sizes too large to count, which validate rejects.
+  let cycle = step_size.floor() as usize;
+  cycle.saturating_mul(13).saturating_add(1)
+}
+
+/// @param field Dotted path to the field in the config
+/// @param message What is wrong with the field
+/// @return Error describing the invalid field
+fn invalid (field: &str, message: &str) -> Error {
+  Error::Invalid(format!("{} {}", field, message))
+}
+
+/// Object in the config file that knows its dotted path for error messages.
+struct Section<'a> {
+
+  /// Dotted path to the object, empty for the root.
+  path: String,
+
+  /// Fields of the object.
+  fields: &'a [(String, Json)],
+
+}
+
+impl<'a> Section<'a> {
+
+  /// Wraps an object and rejects fields it does not know, since a typo would
+  /// otherwise silently fall back to a default.
+  ///
+  /// @param path Dotted path to the object
+  /// @param json The object
+  /// @param known Names of allowed fields
+  fn new (path: &str, json: &'a Json, known: &[&str]) -> Result<Section<'a>> {
+    let name = if path.is_empty() { "config" } else { path };
+    let fields = json.as_object()
+      .ok_or_else(|| invalid(name, "must be an object"))?;
+
+    if let Some((key, _)) = fields.iter().find(|(key, _)| !known.contains(&key.as_str())) {
+      return Err(invalid(name, &format!(
+        "has unknown field '{}', expected one of: {}", key, known.join(", "),
+      )));
+    }
+
+    Ok(Section { path: path.to_string(), fields })
+  }
+
+  /// @param parent Section the object is nested in
+  /// @param key Name of the object
+  /// @param known Names of allowed fields
+  /// @return Nested section if present
+  fn optional (parent: &Section<'a>, key: &str, known: &[&str]) -> Result<Option<Section<'a>>> {
+    match parent.get(key) {
+      None => Ok(None),
+      Some(json) => Section::new(&parent.field(key), json, known).map(Some),
+    }
+  }
+
+  /// @param parent Section the object is nested in
+  /// @param key Name of the object
+  /// @param known Names of allowed fields
+  /// @return Nested section or an error if it is missing
+  fn required (parent: &Section<'a>, key: &str, known: &[&str]) -> Result<Section<'a>> {
+    Section::optional(parent, key, known)?
+      .ok_or_else(|| invalid(&parent.field(key), "is required"))
+  }
+
+  /// @param key Name of the field
+  /// @return Dotted path to the field
+  fn field (&self, key: &str) -> String {
+    if self.path.is_empty() { key.to_string() } else { format!("{}.{}", self.path, key) }
+  }
+
+  /// @param key Name of the field
+  /// @return Value of the field unless it is missing or null
+  fn get (&self, key: &str) -> Option<&'a Json> {
+    self.fields.iter()
+      .find(|(name, _)| name == key)
+      .map(|(_, value)| value)
+      .filter(|value| **value != Json::Null)
+  }
+
+  /// Reads a field of given type.
+  ///
+  /// @param key Name of the field
+  /// @param expected Description of the expected type for the error
+  /// @param convert Conversion from JSON
+  fn typed<T, F> (&self, key: &str, expected: &str, convert: F) -> Result<Option<T>>
+    where F: Fn(&'a Json) -> Option<T>
+  {
+    match self.get(key) {
+      None => Ok(None),
+      Some(json) => convert(json)
+        .map(Some)
+        .ok_or_else(|| invalid(&self.field(key), &format!("must be {}, found {}", expected, json))),
+    }
+  }
+
+  fn string (&self, key: &str) -> Result<Option<String>> {
+    self.typed(key, "a string", |json| json.as_str().map(str::to_string))
+  }
+
+  fn number (&self, key: &str) -> Result<Option<f64>> {
+    self.typed(key, "a number", Json::as_f64)
+  }
+
+  fn usize (&self, key: &str) -> Result<Option<usize>> {
+    self.typed(key, "a non negative whole number", Json::as_usize)
+  }
+
+  fn bool (&self, key: &str) -> Result<Option<bool>> {
+    self.typed(key, "true or false", Json::as_bool)
+  }
+
+  /// Reads a string field and parses it, e.g. into an enum.
+  fn parsed<T: FromStr<Err = Error>> (&self, key: &str) -> Result<Option<T>> {
+    match self.string(key)? {
+      Some(text) => text.parse()
+        .map(Some)
+        .map_err(|error: Error| invalid(&self.field(key), &format!("is invalid: {}", error))),
+      None => Ok(None),
+    }
+  }
+
+  /// Reads a single character, e.g. a delimiter.
+  fn character (&self, key: &str) -> Result<Option<char>> {
+    self.typed(key, "a single character", |json| {
+      let text = json.as_str()?;
+      let mut chars = text.chars();
+
+      match (chars.next(), chars.next()) {
+        (Some(character), None) => Some(character),
+        _ => None,
+      }
+    })
+  }
+
+  /// Reads kind and layout of the data files in one of the forms:
+  /// { "type": "csv", "delimiter": ",", ... } where the type is optional
+  /// { "type": "idx" }
+  /// { "type": "bitmap", "block": 4 } where the block is optional
+  /// { "type": "images", "shape": "8x8", "invert": false, "stretch": false }
+  /// { "type": "cache" }
+  ///
+  /// @param key Name of the field
+  /// @return Format if present
+  fn format (&self, key: &str) -> Result<Option<DataFormat>> {
+    let json = match self.get(key) {
+      Some(json) => json,
+      None => return Ok(None),
+    };
+
+    let path = self.field(key);
+
+    let format = match json.get("type").map(|kind| (kind, kind.as_str())) {
+      None | Some((_, Some("csv"))) => {
+        let section = Section::new(
+          &path,
+          json,
+          &["type", "delimiter", "header", "label_column", "comment", "scale", "shape"],
+        )?;
+        let mut options = CsvOptions::optdigits();
+        section.read_csv_options(&mut options)?;
+
+        DataFormat::Csv(options)
+      },
+      Some((_, Some("idx"))) => {
+        Section::new(&path, json, &["type"])?;
+
+        DataFormat::Idx
+      },
+      Some((_, Some("bitmap"))) => {
+        let section = Section::new(&path, json, &["type", "block"])?;
+
+        DataFormat::Bitmap { block: section.usize("block")?.unwrap_or(1) }
+      },
+      Some((_, Some("cache"))) => {
+        Section::new(&path, json, &["type"])?;
+
+        DataFormat::Cache
+      },
+      Some((_, Some("images"))) => {
+        let section = Section::new(&path, json, &["type", "shape", "invert", "stretch"])?;
+        let shape = section.shape("shape")?
+          .ok_or_else(|| invalid(&section.field("shape"), "is required"))?;
+
+        DataFormat::Images(ImageOptions {
+          shape,
+          invert: section.bool("invert")?.unwrap_or(false),
+          stretch: section.bool("stretch")?.unwrap_or(false),
+        })
+      },
+      Some((kind, _)) => return Err(invalid(
+        &format!("{}.type", path),
+        &format!(
+          "must be one of \"csv\", \"idx\", \"bitmap\", \"images\" or \"cache\", found {}",
+          kind,
+        ),
+      )),
+    };
+
+    Ok(Some(format))
+  }
+
+  /// Reads layout of delimited text files into given options.
+  ///
+  /// @param options Options to override
+  fn read_csv_options (&self, options: &mut CsvOptions) -> Result<()> {
+    if let Some(delimiter) = self.character("delimiter")? {
+      options.delimiter = delimiter;
+    }
+    if let Some(header) = self.bool("header")? {
+      options.header = header;
+    }
+    if let Some(column) = self.get("label_column") {
+      options.label_column = match column.as_usize() {
+        Some(index) => LabelColumn::Index(index),
+        None => self.string("label_column")?.unwrap().parse()?,
+      };
+    }
+    if let Some(scale) = self.number("scale")? {
+      options.scale = scale;
+    }
+    options.comment = self.character("comment")?;
+    options.shape = self.shape("shape")?;
+
+    Ok(())
+  }
+
+  /// Reads image shape written as WIDTHxHEIGHT or WIDTHxHEIGHTxCHANNELS.
+  fn shape (&self, key: &str) -> Result<Option<Shape>> {
+    match self.string(key)? {
+      Some(shape) => shape.parse::<Shape>()
+        .map(Some)
+        .map_err(|error| invalid(&self.field(key), &error.to_string())),
+      None => Ok(None),
+    }
+  }
+
+  fn usize_list (&self, key: &str) -> Result<Option<Vec<usize>>> {
+    self.typed(key, "an array of non negative whole numbers", |json| {
+      json.as_array()?.iter().map(Json::as_usize).collect()
+    })
+  }
+
+  /// Reads learning rate schedule in one of the forms:
+  /// { "type": "constant", "learning_rate": 0.5 }
+  /// { "type": "cyclical", "min_lr": 0.1, "max_lr": 0.8, "step_size": 8 }
+  ///
+  /// @param key Name of the field
+  /// @return Schedule if present
+  fn schedule (&self, key: &str) -> Result<Option<Schedule>> {
+    let json = match self.get(key) {
+      Some(json) => json,
+      None => return Ok(None),
+    };
+
+    let path = self.field(key);
+    let kind = json.get("type").and_then(Json::as_str);
+
+    let schedule = match kind {
+      Some("constant") => {
+        let section = Section::new(&path, json, &["type", "learning_rate"])?;
+        let learning_rate = section.number("learning_rate")?
+          .ok_or_else(|| invalid(&section.field("learning_rate"), "is required"))?;
+
+        Schedule::Constant(learning_rate)
+      },
+      Some("cyclical") => {
+        let section = Section::new(&path, json, &["type", "min_lr", "max_lr", "step_size"])?;
+        let required = |key: &str| section.number(key)?
+          .ok_or_else(|| invalid(&section.field(key), "is required"));
+
+        Schedule::Cyclical {
+          min_lr: required("min_lr")?,
+          max_lr: required("max_lr")?,
+          step_size: required("step_size")?,
+        }
+      },
+      _ => return Err(invalid(
+        &format!("{}.type", path),
+        "must be either \"constant\" or \"cyclical\"",
+      )),
+    };
+
+    Ok(Some(schedule))
+  }
+
+  /// Reads class weights, either "balanced" or an array with a non negative
+  /// weight for each class.
+  ///
+  /// @param key Name of the field
+  /// @return Class weights if present
+  fn class_weights (&self, key: &str) -> Result<Option<ClassWeights>> {
+    self.typed(key, "\"balanced\" or an array of non negative numbers", |json| {
+      match json.as_str() {
+        Some("balanced") => Some(ClassWeights::Balanced),
+        Some(_) => None,
+        None => json.as_array()?.iter()
+          .map(|weight| weight.as_f64().filter(|weight| *weight >= 0_f64))
+          .collect::<Option<Vec<f64>>>()
+          .map(ClassWeights::Manual),
+      }
+    })
+  }
+
+  /// Reads random distortions of the training images such as
+  /// { "translate": 1, "rotate": 10, "elastic": 2, "sigma": 1, "noise": 0.05 }
+  /// where every field is optional and defaults to 0.
+  ///
+  /// @param key Name of the field
+  /// @return Augmentation if present
+  fn augmentation (&self, key: &str) -> Result<Option<Augmentation>> {
+    let section = match Section::optional(self, key, &augment::FIELDS)? {
+      Some(section) => section,
+      None => return Ok(None),
+    };
+
+    let mut augmentation = Augmentation::default();
+    for name in augment::FIELDS.iter() {
+      if let Some(value) = section.number(name)? {
+        augmentation.set(name, value)
+          .map_err(|error| invalid(&section.field(name), &format!("is invalid: {}", error)))?;
+      }
+    }
+
+    Ok(Some(augmentation))
+  }
+
+  /// Reads preprocessing steps, each in one of the forms:
+  /// { "type": "normalize", "method": "minmax", "scope": "feature" }
+  /// where the method is minmax or standard and the optional scope is
+  /// feature or global
+  /// { "type": "pca", "components": 30 }
+  /// { "type": "pca", "variance": 0.95 }
+  ///
+  /// @param key Name of the field
+  /// @return Steps if present
+  fn steps (&self, key: &str) -> Result<Option<Vec<Step>>> {
+    let json = match self.get(key) {
+      Some(json) => json,
+      None => return Ok(None),
+    };
+
+    let items = json.as_array()
+      .ok_or_else(|| invalid(&self.field(key), "must be an array"))?;
+
+    let mut steps = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+      let path = format!("{}[{}]", self.field(key), index);
+
+      let step = match item.get("type").and_then(Json::as_str) {
+        Some("normalize") => {
+          let section = Section::new(&path, item, &["type", "method", "scope"])?;
+
+          Step::Normalize {
+            method: section.parsed("method")?
+              .ok_or_else(|| invalid(&section.field("method"), "is required"))?,
+            scope: section.parsed("scope")?.unwrap_or(Scope::Feature),
+          }
+        },
+        Some("pca") => {
+          let section = Section::new(&path, item, &["type", "components", "variance"])?;
+
+          let components = match (section.usize("components")?, section.number("variance")?) {
+            (Some(count), None) => Components::Count(count),
+            (None, Some(variance)) => Components::Variance(variance),
+            _ => return Err(invalid(&path, "needs either components or variance")),
+          };
+
+          Step::Pca { components }
+        },
+        _ => return Err(invalid(
+          &format!("{}.type", path),
+          "must be either \"normalize\" or \"pca\"",
+        )),
+      };
+
+      steps.push(step);
+    }
+
+    Ok(Some(steps))
+  }
+
+}
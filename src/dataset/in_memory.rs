@@ -0,0 +1,184 @@
+use reader::digit::Digit;
+use reader::sample::{Format, Sample, Shape};
+use super::Dataset;
+
+/// Data set that keeps all features in one contiguous vector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InMemory {
+
+  /// Number of features of each sample.
+  features: usize,
+
+  /// Features of all samples, one sample after another.
+  data: Vec<f64>,
+
+  /// Class index of each sample.
+  targets: Vec<usize>,
+
+  /// Name of each class.
+  labels: Vec<String>,
+
+  /// Image shape of the features, if they are pixels.
+  shape: Option<Shape>,
+
+}
+
+impl InMemory {
+
+  /// Builds an empty data set.
+  ///
+  /// @param features Number of features of each sample
+  /// @param labels Name of each class
+  /// @return New InMemory instance
+  pub fn new (features: usize, labels: Vec<String>) -> Self {
+    InMemory { features, labels, data: Vec::new(), targets: Vec::new(), shape: None }
+  }
+
+  /// Builds an empty data set for samples of given format.
+  ///
+  /// @param format What the samples look like
+  /// @return New InMemory instance
+  pub fn with_format (format: &Format) -> Self {
+    let mut dataset = InMemory::new(format.features, format.labels.clone());
+    dataset.shape = format.shape;
+
+    dataset
+  }
+
+  /// Moves samples into a data set of given format. The samples are expected
+  /// to have been checked against the format.
+  ///
+  /// @param format What the samples look like
+  /// @param samples Samples to move
+  /// @return New InMemory instance
+  pub fn from_samples (format: &Format, samples: Vec<Sample>) -> Self {
+    let mut dataset = InMemory::with_format(format);
+    dataset.data.reserve(samples.len() * format.features);
+
+    for sample in samples {
+      dataset.push_sample(sample);
+    }
+
+    dataset
+  }
+
+  /// Builds a data set around features that are already in one contiguous
+  /// vector, so that loaders can fill it without copying.
+  ///
+  /// @param format What the samples look like
+  /// @param data Features of all samples, one sample after another
+  /// @param targets Class index of each sample
+  /// @return New InMemory instance
+  pub fn from_parts (format: &Format, data: Vec<f64>, targets: Vec<usize>) -> Self {
+    assert_eq!(data.len(), targets.len() * format.features, "Data has wrong number of features.");
+
+    InMemory {
+      features: format.features,
+      data,
+      targets,
+      labels: format.labels.clone(),
+      shape: format.shape,
+    }
+  }
+
+  /// Builds a data set with numbered classes "0", "1", ...
+  ///
+  /// @param features Number of features of each sample
+  /// @param classes Number of classes
+  /// @return New InMemory instance
+  pub fn numbered (features: usize, classes: usize) -> Self {
+    InMemory::new(features, (0..classes).map(|class| class.to_string()).collect())
+  }
+
+  /// Copies samples of any data set into memory.
+  ///
+  /// @param dataset Data set to copy
+  /// @return New InMemory instance
+  pub fn collect (dataset: &dyn Dataset) -> Self {
+    let mut copy = InMemory::new(dataset.features(), dataset.labels().to_vec());
+    copy.shape = dataset.shape();
+
+    for index in 0..dataset.len() {
+      let (class, features) = dataset.sample(index);
+      copy.push(class, features);
+    }
+
+    copy
+  }
+
+  /// Appends a sample.
+  ///
+  /// @param class Index of the class of the sample
+  /// @param features Features, there must be as many as the data set has
+  pub fn push (&mut self, class: usize, features: &[f64]) {
+    assert_eq!(features.len(), self.features, "Sample has wrong number of features.");
+
+    self.data.extend_from_slice(features);
+    self.targets.push(class);
+  }
+
+  /// Appends a sample, moving its features.
+  ///
+  /// @param sample Sample with as many features as the data set has
+  pub fn push_sample (&mut self, sample: Sample) {
+    assert_eq!(sample.features.len(), self.features, "Sample has wrong number of features.");
+
+    self.data.extend(sample.features);
+    self.targets.push(sample.class);
+  }
+
+  /// @return What the samples look like
+  pub fn format (&self) -> Format {
+    Format { features: self.features, labels: self.labels.clone(), shape: self.shape }
+  }
+
+  /// @param shape Image shape of the features
+  pub fn set_shape (&mut self, shape: Option<Shape>) {
+    self.shape = shape;
+  }
+
+  /// @param labels Name of each class
+  pub fn set_labels (&mut self, labels: Vec<String>) {
+    self.labels = labels;
+  }
+
+}
+
+impl Dataset for InMemory {
+
+  fn len (&self) -> usize {
+    self.targets.len()
+  }
+
+  fn sample (&self, index: usize) -> (usize, &[f64]) {
+    let start = index * self.features;
+
+    (self.targets[index], &self.data[start..start + self.features])
+  }
+
+  fn features (&self) -> usize {
+    self.features
+  }
+
+  fn labels (&self) -> &[String] {
+    &self.labels
+  }
+
+  fn shape (&self) -> Option<Shape> {
+    self.shape
+  }
+
+}
+
+impl From<Vec<Digit>> for InMemory {
+
+  /// Moves the grids of the digits into one contiguous data set of 8x8
+  /// images with classes "0" to "9".
+  fn from (digits: Vec<Digit>) -> InMemory {
+    InMemory::from_samples(
+      &Digit::format(),
+      digits.into_iter().map(Sample::from).collect(),
+    )
+  }
+
+}
@@ -0,0 +1,149 @@
+
+/*
+ * Exports the in-memory store that readers load data into.
+ */
+
+pub mod in_memory;
+
+/*
+ * Exports views that select, reorder and split samples of another data set
+ * without copying them.
+ */
+
+pub mod subset;
+
+/*
+ * Exports random splits of a data set into training, validation and test
+ * partitions.
+ */
+
+pub mod split;
+
+/*
+ * Exports the trait of data sets that are read batch by batch.
+ */
+
+pub mod stream;
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use self::subset::Subset;
+use reader::sample::Shape;
+
+/// Labelled samples of equal length. Samples are borrowed straight from the
+/// underlying storage, so that neither training nor evaluation has to clone
+/// them.
+pub trait Dataset {
+
+  /// @return Number of samples
+  fn len (&self) -> usize;
+
+  /// @return Whether there are no samples
+  fn is_empty (&self) -> bool {
+    self.len() == 0
+  }
+
+  /// @param index Position of the sample, must be less than len
+  /// @return Tuple in format (class_index, features)
+  fn sample (&self, index: usize) -> (usize, &[f64]);
+
+  /// @return Number of features of each sample
+  fn features (&self) -> usize;
+
+  /// Human readable names of the classes. The class index of a sample is a
+  /// position in this vector.
+  ///
+  /// @return Name of each class
+  fn labels (&self) -> &[String];
+
+  /// @return Number of classes
+  fn classes (&self) -> usize {
+    self.labels().len()
+  }
+
+  /// @return Image shape of the features, if they are pixels
+  fn shape (&self) -> Option<Shape> {
+    None
+  }
+
+  /// @return Iterator over all samples in order
+  fn iter<'a> (&'a self) -> Samples<'a> {
+    Box::new((0..self.len()).map(move |index| self.sample(index)))
+  }
+
+  /// Iterates over the samples in groups of given size. The last batch can be
+  /// smaller.
+  ///
+  /// @param size Number of samples in each batch
+  /// @return Iterator over the batches
+  fn batches<'a> (&'a self, size: usize) -> Batches<'a> {
+    let size = size.max(1);
+
+    Box::new(
+      (0..self.len()).step_by(size)
+        .map(move |start| {
+          (start..(start + size).min(self.len()))
+            .map(|index| self.sample(index))
+            .collect()
+        })
+    )
+  }
+
+  /// @param indices Positions of the samples to select, in order
+  /// @return View of the selected samples
+  fn select (&self, indices: Vec<usize>) -> Subset<'_> where Self: Sized {
+    Subset::new(self, indices)
+  }
+
+  /// @param start First sample of the slice
+  /// @param end Position after the last sample of the slice
+  /// @return View of the samples in range start..end
+  fn slice (&self, start: usize, end: usize) -> Subset<'_> where Self: Sized {
+    self.select((start..end.min(self.len())).collect())
+  }
+
+  /// Splits the samples in two at given ratio, keeping their order.
+  ///
+  /// @param ratio Share of samples in the first part, between 0 and 1
+  /// @return Tuple of views in format (first, second)
+  fn split (&self, ratio: f64) -> (Subset<'_>, Subset<'_>) where Self: Sized {
+    let at = (self.len() as f64 * ratio.clamp(0_f64, 1_f64)).round() as usize;
+
+    (self.slice(0, at), self.slice(at, self.len()))
+  }
+
+  /// @param seed Seed of the random number generator
+  /// @return View of all samples in random order
+  fn shuffled (&self, seed: u64) -> Subset<'_> where Self: Sized {
+    let mut indices: Vec<usize> = (0..self.len()).collect();
+    indices.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    self.select(indices)
+  }
+
+}
+
+/// Iterator over samples of a data set.
+pub type Samples<'a> = Box<dyn Iterator<Item = (usize, &'a [f64])> + 'a>;
+
+/// Iterator over batches of samples of a data set.
+pub type Batches<'a> = Box<dyn Iterator<Item = Vec<(usize, &'a [f64])>> + 'a>;
+
+/// Counts samples of each class.
+///
+/// @param dataset Data set to count
+/// @return Number of samples for each class index
+pub fn class_counts (dataset: &dyn Dataset) -> Vec<usize> {
+  let mut counts = vec!(0; dataset.classes());
+
+  for index in 0..dataset.len() {
+    let (class, _) = dataset.sample(index);
+
+    if class >= counts.len() {
+      counts.resize(class + 1, 0);
+    }
+    counts[class] += 1;
+  }
+
+  counts
+}
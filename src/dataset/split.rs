@@ -0,0 +1,217 @@
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::str::FromStr;
+use error::{Error, Result};
+use super::Dataset;
+use super::subset::Subset;
+
+/// Shares of the samples that go into the training, validation and test
+/// partitions. The shares are relative to their sum, so 0.7,0.15,0.15 and
+/// 70,15,15 split alike.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ratios {
+  pub train: f64,
+  pub validation: f64,
+  pub test: f64,
+}
+
+impl Ratios {
+
+  /// @param train Share of the training partition
+  /// @param validation Share of the validation partition
+  /// @param test Share of the test partition
+  /// @return New Ratios instance
+  pub fn new (train: f64, validation: f64, test: f64) -> Result<Self> {
+    let ratios = Ratios { train, validation, test };
+    let shares = [train, validation, test];
+
+    if shares.iter().any(|share| !share.is_finite() || *share < 0_f64) || ratios.sum() <= 0_f64 {
+      return Err(Error::Invalid(
+        "Split ratios must not be negative and at least one must be positive.".to_string(),
+      ));
+    }
+
+    Ok(ratios)
+  }
+
+  fn sum (&self) -> f64 {
+    self.train + self.validation + self.test
+  }
+
+  /// Distributes samples over the partitions. The boundaries are rounded from
+  /// the cumulative shares, so the sizes always add up.
+  ///
+  /// @param samples Number of samples to distribute
+  /// @return Number of samples in each partition
+  fn sizes (&self, samples: usize) -> [usize; 3] {
+    let boundary = |share: f64| (samples as f64 * share / self.sum()).round() as usize;
+    let train = boundary(self.train);
+    let validation = boundary(self.train + self.validation).max(train) - train;
+
+    [train, validation, samples - train - validation]
+  }
+
+}
+
+impl FromStr for Ratios {
+  type Err = Error;
+
+  /// Parses TRAIN,VALIDATION,TEST or TRAIN,TEST without a validation part.
+  fn from_str (text: &str) -> Result<Ratios> {
+    let shares = text.split(',')
+      .map(|share| share.trim().parse::<f64>())
+      .collect::<::std::result::Result<Vec<f64>, _>>();
+
+    match shares.as_ref().map(|shares| shares.as_slice()) {
+      Ok(&[train, test]) => Ratios::new(train, 0_f64, test),
+      Ok(&[train, validation, test]) => Ratios::new(train, validation, test),
+      _ => Err(Error::Invalid(format!(
+        "Invalid split '{}', expected TRAIN,VALIDATION,TEST or TRAIN,TEST.", text,
+      ))),
+    }
+  }
+}
+
+/// Training, validation and test partitions of one data set.
+pub struct Splits<'a> {
+  pub train: Subset<'a>,
+  pub validation: Subset<'a>,
+  pub test: Subset<'a>,
+}
+
+/// Splits the samples into training, validation and test partitions at
+/// random. Stratified splits divide each class separately, so that every
+/// partition keeps the class proportions of the whole data set. Samples keep
+/// their original order within a partition.
+///
+/// @param dataset Data set to split
+/// @param ratios Shares of the partitions
+/// @param stratify Whether to split each class separately
+/// @param seed Seed of the random number generator
+/// @return Views of the partitions
+pub fn split<'a> (dataset: &'a dyn Dataset, ratios: &Ratios, stratify: bool, seed: u64) -> Splits<'a> {
+  let mut rng = StdRng::seed_from_u64(seed);
+
+  let groups: Vec<Vec<usize>> = if stratify {
+    let mut classes = vec!(Vec::new(); dataset.classes());
+    for (index, (class, _)) in dataset.iter().enumerate() {
+      classes[class].push(index);
+    }
+    classes
+  } else {
+    vec!((0..dataset.len()).collect())
+  };
+
+  let mut partitions = [Vec::new(), Vec::new(), Vec::new()];
+
+  for mut group in groups {
+    group.shuffle(&mut rng);
+
+    let mut rest = &group[..];
+    for (partition, size) in partitions.iter_mut().zip(ratios.sizes(group.len()).iter()) {
+      let (taken, remaining) = rest.split_at(*size);
+      partition.extend_from_slice(taken);
+      rest = remaining;
+    }
+  }
+
+  let [mut train, mut validation, mut test] = partitions;
+  train.sort_unstable();
+  validation.sort_unstable();
+  test.sort_unstable();
+
+  Splits {
+    train: Subset::new(dataset, train),
+    validation: Subset::new(dataset, validation),
+    test: Subset::new(dataset, test),
+  }
+}
+
+/// Assigns every sample to one of k folds of nearly equal size for cross
+/// validation. Stratified folds deal the samples of each class in turn, so
+/// that every fold keeps the class proportions. With as many folds as
+/// samples this is leave-one-out.
+///
+/// @param dataset Data set to divide
+/// @param k Number of folds, from 2 up to the number of samples
+/// @param stratify Whether to deal each class separately
+/// @param seed Seed of the random number generator
+/// @return Positions of the samples in each fold, in order
+pub fn folds (dataset: &dyn Dataset, k: usize, stratify: bool, seed: u64) -> Result<Vec<Vec<usize>>> {
+  if k < 2 || k > dataset.len() {
+    return Err(Error::Invalid(format!(
+      "Cannot divide {} samples into {} folds, use 2 to {} folds.", dataset.len(), k, dataset.len(),
+    )));
+  }
+
+  let mut rng = StdRng::seed_from_u64(seed);
+  let mut order: Vec<usize> = (0..dataset.len()).collect();
+  order.shuffle(&mut rng);
+
+  // Sorting the shuffled samples by class keeps them shuffled within each
+  // class, and dealing them in turn spreads every class over all folds.
+  if stratify {
+    order.sort_by_key(|&index| dataset.sample(index).0);
+  }
+
+  let mut folds = vec!(Vec::new(); k);
+  for (position, index) in order.into_iter().enumerate() {
+    folds[position % k].push(index);
+  }
+
+  for fold in folds.iter_mut() {
+    fold.sort_unstable();
+  }
+
+  Ok(folds)
+}
+
+#[cfg(test)]
+mod tests {
+  use dataset::class_counts;
+  use dataset::in_memory::InMemory;
+  use super::*;
+
+  #[test]
+  fn stratified_split_keeps_class_proportions() {
+    let mut dataset = InMemory::new(1, vec!("a".to_string(), "b".to_string()));
+    for index in 0..100 {
+      dataset.push(if index < 80 { 0 } else { 1 }, &[index as f64]);
+    }
+
+    let ratios: Ratios = "70,10,20".parse().unwrap();
+    let splits = split(&dataset, &ratios, true, 5);
+
+    assert_eq!(class_counts(&splits.train), vec!(56, 14));
+    assert_eq!(class_counts(&splits.validation), vec!(8, 2));
+    assert_eq!(class_counts(&splits.test), vec!(16, 4));
+
+    let mut all: Vec<usize> = splits.train.indices().to_vec();
+    all.extend(splits.validation.indices());
+    all.extend(splits.test.indices());
+    all.sort();
+    assert_eq!(all, (0..100).collect::<Vec<usize>>());
+
+    let again = split(&dataset, &ratios, true, 5);
+    assert_eq!(again.test.indices(), splits.test.indices());
+  }
+
+  #[test]
+  fn stratified_folds_cover_every_sample_once() {
+    let mut dataset = InMemory::new(1, vec!("a".to_string(), "b".to_string()));
+    for index in 0..12 {
+      dataset.push(if index < 8 { 0 } else { 1 }, &[index as f64]);
+    }
+
+    let folds = folds(&dataset, 4, true, 9).unwrap();
+
+    for fold in &folds {
+      assert_eq!(class_counts(&dataset.select(fold.clone())), vec!(2, 1));
+    }
+
+    let mut all: Vec<usize> = folds.concat();
+    all.sort();
+    assert_eq!(all, (0..12).collect::<Vec<usize>>());
+    assert!(super::folds(&dataset, 13, false, 9).is_err());
+  }
+}
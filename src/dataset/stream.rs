@@ -0,0 +1,22 @@
+use dataset::in_memory::InMemory;
+use error::Result;
+
+/// Source of mini-batches for data sets that are read while training instead
+/// of being held in memory. Only one batch is kept at a time, so a pass over
+/// the data has to go through the whole source.
+pub trait Stream {
+
+  /// @return Name of each class
+  fn labels (&self) -> &[String];
+
+  /// @return Number of features of each sample, once known
+  fn features (&self) -> Option<usize>;
+
+  /// Starts another pass at the first sample.
+  fn rewind (&mut self) -> Result<()>;
+
+  /// @param size Most samples the batch may have
+  /// @return Next samples of the pass, None once the pass is over
+  fn next_batch (&mut self, size: usize) -> Result<Option<InMemory>>;
+
+}
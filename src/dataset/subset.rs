@@ -0,0 +1,54 @@
+use super::Dataset;
+use reader::sample::Shape;
+
+/// View of selected samples of another data set. Used for shuffling, slicing
+/// and splitting without copying any features.
+pub struct Subset<'a> {
+
+  /// Data set the samples come from.
+  dataset: &'a dyn Dataset,
+
+  /// Positions of the selected samples in the underlying data set.
+  indices: Vec<usize>,
+
+}
+
+impl<'a> Subset<'a> {
+
+  /// @param dataset Data set the samples come from
+  /// @param indices Positions of the samples to select, in order
+  /// @return New Subset instance
+  pub fn new (dataset: &'a dyn Dataset, indices: Vec<usize>) -> Self {
+    Subset { dataset, indices }
+  }
+
+  /// @return Positions of the selected samples in the underlying data set
+  pub fn indices (&self) -> &[usize] {
+    &self.indices
+  }
+
+}
+
+impl<'a> Dataset for Subset<'a> {
+
+  fn len (&self) -> usize {
+    self.indices.len()
+  }
+
+  fn sample (&self, index: usize) -> (usize, &[f64]) {
+    self.dataset.sample(self.indices[index])
+  }
+
+  fn features (&self) -> usize {
+    self.dataset.features()
+  }
+
+  fn labels (&self) -> &[String] {
+    self.dataset.labels()
+  }
+
+  fn shape (&self) -> Option<Shape> {
+    self.dataset.shape()
+  }
+
+}
@@ -0,0 +1,55 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::result;
+
+/// Everything that can go wrong while loading data, models and configuration
+/// or while parsing the command line.
+#[derive(Debug)]
+pub enum Error {
+
+  /// Reading or writing a file failed.
+  Io(io::Error),
+
+  /// Text input is malformed at given line and column, both starting at 1.
+  Parse {
+    line: usize,
+    column: usize,
+    message: String,
+  },
+
+  /// Input is well formed but its content does not make sense.
+  Invalid(String),
+
+  /// Command line arguments are wrong.
+  Usage(String),
+
+}
+
+/// Result with the crate error.
+pub type Result<T> = result::Result<T, Error>;
+
+impl fmt::Display for Error {
+
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Error::Io(ref error) => write!(f, "{}", error),
+      Error::Parse { line, column, ref message } => {
+        write!(f, "line {}, column {}: {}", line, column, message)
+      },
+      Error::Invalid(ref message) => write!(f, "{}", message),
+      Error::Usage(ref message) => write!(f, "{}", message),
+    }
+  }
+
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+
+  fn from (error: io::Error) -> Error {
+    Error::Io(error)
+  }
+
+}
@@ -0,0 +1,528 @@
+use std::fmt;
+use dataset::Dataset;
+use json::Json;
+use neural_network::network::Network;
+use neural_network::network_classify::Criterion;
+use reader::csv;
+use trainer;
+
+/// Precision, recall and F1 score of one class or an average over classes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Score {
+
+  /// Share of the samples predicted as the class that belong to it.
+  pub precision: f64,
+
+  /// Share of the samples of the class that are predicted as it.
+  pub recall: f64,
+
+  /// Harmonic mean of precision and recall.
+  pub f1: f64,
+
+  /// Number of samples that belong to the class or classes.
+  pub support: usize,
+
+}
+
+impl Score {
+
+  /// Ratios without any predictions or samples are 0.
+  ///
+  /// @param correct Samples of the class predicted as it
+  /// @param predicted Samples predicted as the class
+  /// @param support Samples of the class
+  /// @return New Score instance
+  fn new (correct: usize, predicted: usize, support: usize) -> Self {
+    let ratio = |part: usize, whole: usize| {
+      if whole == 0 { 0_f64 } else { part as f64 / whole as f64 }
+    };
+    let (precision, recall) = (ratio(correct, predicted), ratio(correct, support));
+
+    Score { precision, recall, f1: harmonic_mean(precision, recall), support }
+  }
+
+  /// @return JSON object with the score
+  pub fn to_json (&self) -> Json {
+    Json::object(vec!(
+      ("precision", self.precision.into()),
+      ("recall", self.recall.into()),
+      ("f1", self.f1.into()),
+      ("support", self.support.into()),
+    ))
+  }
+
+}
+
+/// How the scores of the classes are combined into one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Average {
+
+  /// Unweighted mean over the classes, rare classes count as much as common
+  /// ones.
+  Macro,
+
+  /// Scores of the counts summed over all classes, which for one label per
+  /// sample all equal the accuracy.
+  Micro,
+
+  /// Mean over the classes weighted by their support.
+  Weighted,
+
+}
+
+impl Average {
+
+  /// All averages in the order they are reported in.
+  pub const ALL: [Average; 3] = [Average::Macro, Average::Micro, Average::Weighted];
+
+}
+
+impl fmt::Display for Average {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Average::Macro => write!(f, "macro"),
+      Average::Micro => write!(f, "micro"),
+      Average::Weighted => write!(f, "weighted"),
+    }
+  }
+}
+
+/// Counts of how a network classifies labelled samples, from which all
+/// metrics are derived.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+
+  /// Name of each class.
+  labels: Vec<String>,
+
+  /// Number of samples of the class in the row predicted as the class in
+  /// the column.
+  confusion: Vec<Vec<usize>>,
+
+  /// Number of samples whose class got the n-th strongest output, from the
+  /// strongest one.
+  ranks: Vec<usize>,
+
+  /// Summed loss of all samples.
+  loss: f64,
+
+}
+
+impl Evaluation {
+
+  /// @param labels Name of each class, one per output neuron
+  /// @return Evaluation of no samples
+  pub fn new (labels: Vec<String>) -> Self {
+    let classes = labels.len();
+
+    Evaluation {
+      labels,
+      confusion: vec!(vec!(0; classes); classes),
+      ranks: vec!(0; classes),
+      loss: 0_f64,
+    }
+  }
+
+  /// Classifies every sample of a data set. Output neurons without a label
+  /// are named by their position.
+  ///
+  /// @param network Network to evaluate
+  /// @param data Labelled samples
+  /// @return Evaluation of the samples
+  pub fn of (network: &Network, data: &dyn Dataset) -> Self {
+    let outputs = network.schema().last().cloned().unwrap_or(0);
+    let labels = (0..outputs.max(data.classes()))
+      .map(|class| data.labels().get(class).cloned().unwrap_or_else(|| class.to_string()))
+      .collect();
+
+    let mut evaluation = Evaluation::new(labels);
+    for (target, inputs) in data.iter() {
+      evaluation.add(target, &network.compute(inputs));
+    }
+
+    evaluation
+  }
+
+  /// Counts one sample. Classes beyond the known labels are added and named
+  /// by their position.
+  ///
+  /// @param target The expected class
+  /// @param outputs Outputs from the network
+  pub fn add (&mut self, target: usize, outputs: &[f64]) {
+    self.grow((target + 1).max(outputs.len()));
+
+    let predicted = trainer::argmax(outputs);
+    self.confusion[target][predicted] += 1;
+
+    // Classes that fired stronger than the expected one, ties go to the
+    // expected class.
+    let expected = outputs.get(target).cloned().unwrap_or(f64::NEG_INFINITY);
+    let rank = outputs.iter().filter(|&&output| output > expected).count();
+    let last = self.ranks.len() - 1;
+    self.ranks[rank.min(last)] += 1;
+
+    self.loss += trainer::loss(target, outputs);
+  }
+
+  /// @param classes Number of classes the counts must have room for
+  fn grow (&mut self, classes: usize) {
+    for class in self.labels.len()..classes {
+      self.labels.push(class.to_string());
+    }
+
+    let classes = self.labels.len();
+    for row in self.confusion.iter_mut() {
+      row.resize(classes, 0);
+    }
+    self.confusion.resize(classes, vec!(0; classes));
+    self.ranks.resize(classes, 0);
+  }
+
+  /// @return Name of each class
+  pub fn labels (&self) -> &[String] {
+    &self.labels
+  }
+
+  /// @return Rows of samples of each class, columns of predicted classes
+  pub fn confusion (&self) -> &[Vec<usize>] {
+    &self.confusion
+  }
+
+  /// @return Number of evaluated samples
+  pub fn samples (&self) -> usize {
+    self.ranks.iter().sum()
+  }
+
+  /// @return Number of samples classified correctly
+  pub fn correct (&self) -> usize {
+    (0..self.labels.len()).map(|class| self.confusion[class][class]).sum()
+  }
+
+  /// @return Share of samples classified correctly
+  pub fn accuracy (&self) -> f64 {
+    self.correct() as f64 / self.samples().max(1) as f64
+  }
+
+  /// @param k How many of the strongest outputs may hold the class
+  /// @return Share of samples whose class is among the k strongest outputs
+  pub fn top_k_accuracy (&self, k: usize) -> f64 {
+    let hits: usize = self.ranks.iter().take(k).sum();
+
+    hits as f64 / self.samples().max(1) as f64
+  }
+
+  /// @return Mean loss of the samples
+  pub fn loss (&self) -> f64 {
+    self.loss / self.samples().max(1) as f64
+  }
+
+  /// @return Score of each class
+  pub fn scores (&self) -> Vec<Score> {
+    (0..self.labels.len())
+      .map(|class| Score::new(
+        self.confusion[class][class],
+        self.confusion.iter().map(|row| row[class]).sum(),
+        self.confusion[class].iter().sum(),
+      ))
+      .collect()
+  }
+
+  /// Combines the scores of the classes. Classes that neither occur in the
+  /// data nor are ever predicted are left out.
+  ///
+  /// @param average How the scores are combined
+  /// @return Average score with the support of all classes
+  pub fn average (&self, average: Average) -> Score {
+    let samples = self.samples();
+
+    if average == Average::Micro {
+      let correct = self.correct();
+      return Score::new(correct, samples, samples);
+    }
+
+    let scores: Vec<Score> = self.scores().into_iter()
+      .enumerate()
+      .filter(|&(class, score)| {
+        score.support > 0 || self.confusion.iter().any(|row| row[class] > 0)
+      })
+      .map(|(_, score)| score)
+      .collect();
+
+    let weight = |score: &Score| match average {
+      Average::Weighted => score.support as f64 / samples.max(1) as f64,
+      _ => 1_f64 / scores.len().max(1) as f64,
+    };
+
+    Score {
+      precision: scores.iter().map(|score| weight(score) * score.precision).sum(),
+      recall: scores.iter().map(|score| weight(score) * score.recall).sum(),
+      f1: scores.iter().map(|score| weight(score) * score.f1).sum(),
+      support: samples,
+    }
+  }
+
+  /// Writes one row per class with its scores followed by how its samples
+  /// were predicted, one column per class, and one row per average.
+  ///
+  /// @return Comma separated table with a header row
+  pub fn to_csv (&self) -> String {
+    let quote = |text: &str| csv::quote(text, ',');
+    let mut header = vec!(
+      "class".to_string(), "precision".to_string(), "recall".to_string(), "f1".to_string(),
+      "support".to_string(),
+    );
+    header.extend(self.labels.iter().map(|label| quote(&format!("predicted {}", label))));
+
+    let mut lines = vec!(header.join(","));
+    for (class, score) in self.scores().iter().enumerate() {
+      let mut row = vec!(quote(&self.labels[class]));
+      row.extend(score_fields(score));
+      row.extend(self.confusion[class].iter().map(usize::to_string));
+      lines.push(row.join(","));
+    }
+
+    for &average in Average::ALL.iter() {
+      let mut row = vec!(format!("{} avg", average));
+      row.extend(score_fields(&self.average(average)));
+      row.extend(self.labels.iter().map(|_| String::new()));
+      lines.push(row.join(","));
+    }
+
+    lines.join("\n") + "\n"
+  }
+
+  /// @param k Largest k of the reported top k accuracies
+  /// @return JSON object with every metric and the confusion matrix
+  pub fn to_json (&self, k: usize) -> Json {
+    let scores = self.scores();
+
+    Json::object(vec!(
+      ("samples", self.samples().into()),
+      ("correct", self.correct().into()),
+      ("accuracy", self.accuracy().into()),
+      ("loss", self.loss().into()),
+      ("top_k_accuracy", Json::Array(
+        (1..=k.min(self.labels.len())).map(|k| self.top_k_accuracy(k).into()).collect(),
+      )),
+      ("classes", Json::Array(
+        self.labels.iter().zip(scores.iter())
+          .map(|(label, score)| {
+            let mut json = score.to_json();
+            if let Json::Object(ref mut fields) = json {
+              fields.insert(0, ("label".to_string(), label.as_str().into()));
+            }
+            json
+          })
+          .collect(),
+      )),
+      ("averages", Json::Object(
+        Average::ALL.iter()
+          .map(|&average| (average.to_string(), self.average(average).to_json()))
+          .collect(),
+      )),
+      ("labels", Json::Array(self.labels.iter().map(|label| label.as_str().into()).collect())),
+      ("confusion", Json::Array(
+        self.confusion.iter().map(|row| row.clone().into()).collect(),
+      )),
+    ))
+  }
+
+}
+
+/// Prints the confusion matrix followed by the scores of each class and the
+/// averages, with aligned columns.
+impl fmt::Display for Evaluation {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let width = self.labels.iter()
+      .map(|label| label.chars().count())
+      .chain(self.confusion.iter().flatten().map(|count| count.to_string().len()))
+      .chain(Some("Actual".len()))
+      .max()
+      .unwrap_or(0);
+
+    writeln!(f, "Confusion matrix, rows are actual and columns predicted classes:")?;
+    write!(f, "{:>width$}", "Actual", width = width)?;
+    for label in self.labels.iter() {
+      write!(f, " {:>width$}", label, width = width)?;
+    }
+    writeln!(f)?;
+    for (label, row) in self.labels.iter().zip(self.confusion.iter()) {
+      write!(f, "{:>width$}", label, width = width)?;
+      for count in row {
+        write!(f, " {:>width$}", count, width = width)?;
+      }
+      writeln!(f)?;
+    }
+    writeln!(f)?;
+
+    let width = width.max("weighted avg".len());
+    writeln!(
+      f,
+      "{:>width$} {:>9} {:>9} {:>9} {:>9}",
+      "Class", "Precision", "Recall", "F1", "Support", width = width,
+    )?;
+    let row = |f: &mut fmt::Formatter, name: &str, score: &Score| writeln!(
+      f,
+      "{:>width$} {:>9.4} {:>9.4} {:>9.4} {:>9}",
+      name, score.precision, score.recall, score.f1, score.support, width = width,
+    );
+
+    for (label, score) in self.labels.iter().zip(self.scores().iter()) {
+      row(f, label, score)?;
+    }
+    writeln!(f)?;
+    for &average in Average::ALL.iter() {
+      row(f, &format!("{} avg", average), &self.average(average))?;
+    }
+
+    Ok(())
+  }
+}
+
+/// How many predictions a reject threshold keeps and how good they are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coverage {
+
+  /// Lowest confidence that is accepted.
+  pub threshold: f64,
+
+  /// Number of samples that are not rejected.
+  pub accepted: usize,
+
+  /// Share of samples that are not rejected.
+  pub coverage: f64,
+
+  /// Share of the accepted samples classified correctly, 1 when all are
+  /// rejected since no mistake is made.
+  pub accuracy: f64,
+
+}
+
+impl Coverage {
+
+  /// Classifies every sample once and finds the coverage and accuracy for
+  /// each threshold, to pick the threshold of the reject option.
+  ///
+  /// @param network Network to evaluate
+  /// @param data Labelled samples, usually the validation data
+  /// @param criterion What the confidence is measured by
+  /// @param thresholds Thresholds to try
+  /// @return Coverage of each threshold
+  pub fn of (
+    network: &Network,
+    data: &dyn Dataset,
+    criterion: Criterion,
+    thresholds: &[f64],
+  ) -> Vec<Coverage> {
+    // Tuples in format (confidence, correct).
+    let predictions: Vec<(f64, bool)> = data.iter()
+      .map(|(target, inputs)| {
+        let probabilities = network.probabilities(inputs);
+
+        (criterion.confidence(&probabilities), trainer::argmax(&probabilities) == target)
+      })
+      .collect();
+
+    thresholds.iter()
+      .map(|&threshold| {
+        let accepted: Vec<bool> = predictions.iter()
+          .filter(|&&(confidence, _)| confidence >= threshold)
+          .map(|&(_, correct)| correct)
+          .collect();
+        let correct = accepted.iter().filter(|&&correct| correct).count();
+
+        Coverage {
+          threshold,
+          accepted: accepted.len(),
+          coverage: accepted.len() as f64 / predictions.len().max(1) as f64,
+          accuracy: if accepted.is_empty() { 1_f64 } else { correct as f64 / accepted.len() as f64 },
+        }
+      })
+      .collect()
+  }
+
+  /// @return JSON object with the coverage
+  pub fn to_json (&self) -> Json {
+    Json::object(vec!(
+      ("threshold", self.threshold.into()),
+      ("accepted", self.accepted.into()),
+      ("coverage", self.coverage.into()),
+      ("accuracy", self.accuracy.into()),
+    ))
+  }
+
+}
+
+/// @param precision Share of predictions that are right
+/// @param recall Share of samples that are found
+/// @return F1 score, 0 when both are 0
+fn harmonic_mean (precision: f64, recall: f64) -> f64 {
+  if precision + recall == 0_f64 {
+    0_f64
+  } else {
+    2_f64 * precision * recall / (precision + recall)
+  }
+}
+
+/// @param score Score to write
+/// @return Precision, recall, F1 and support as csv fields
+fn score_fields (score: &Score) -> Vec<String> {
+  vec!(
+    score.precision.to_string(),
+    score.recall.to_string(),
+    score.f1.to_string(),
+    score.support.to_string(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Average, Evaluation};
+
+  #[test]
+  fn derives_scores_from_the_confusion_matrix() {
+    let labels = vec!("a".to_string(), "b".to_string(), "c".to_string());
+    let mut evaluation = Evaluation::new(labels);
+
+    // Two of a right, one a taken for b, b right, c taken for a with b
+    // second and a third.
+    for &(target, ref outputs) in [
+      (0, [0.9, 0.1, 0.0]), (0, [0.8, 0.2, 0.1]), (0, [0.3, 0.6, 0.1]),
+      (1, [0.1, 0.7, 0.2]), (2, [0.5, 0.4, 0.1]),
+    ].iter() {
+      evaluation.add(target, outputs);
+    }
+
+    assert_eq!(evaluation.confusion(), &[vec!(2, 1, 0), vec!(0, 1, 0), vec!(1, 0, 0)][..]);
+    assert_eq!(evaluation.accuracy(), 0.6);
+    assert_eq!(evaluation.top_k_accuracy(2), 0.8);
+    assert_eq!(evaluation.top_k_accuracy(3), 1.0);
+
+    let scores = evaluation.scores();
+    assert_eq!((scores[0].precision, scores[0].recall), (2_f64 / 3_f64, 2_f64 / 3_f64));
+    assert_eq!((scores[1].precision, scores[1].recall, scores[1].f1), (0.5, 1.0, 2_f64 / 3_f64));
+    assert_eq!((scores[2].f1, scores[2].support), (0_f64, 1));
+
+    let f1 = |average| evaluation.average(average).f1;
+    assert!((f1(Average::Micro) - 0.6).abs() < 1e-12);
+    assert!((f1(Average::Macro) - 4_f64 / 9_f64).abs() < 1e-12);
+    assert!((f1(Average::Weighted) - (2_f64 + 2_f64 / 3_f64) / 5_f64).abs() < 1e-12);
+
+    let csv = evaluation.to_csv();
+    assert_eq!(
+      csv.lines().next(),
+      Some("class,precision,recall,f1,support,predicted a,predicted b,predicted c"),
+    );
+    assert_eq!(csv.lines().count(), 7);
+  }
+
+  #[test]
+  fn names_classes_beyond_the_labels_by_position() {
+    let mut evaluation = Evaluation::new(Vec::new());
+    evaluation.add(2, &[0.1, 0.9]);
+
+    assert_eq!(evaluation.labels(), &["0".to_string(), "1".to_string(), "2".to_string()][..]);
+    assert_eq!(evaluation.confusion()[2], vec!(0, 1, 0));
+    assert_eq!(evaluation.samples(), 1);
+  }
+}
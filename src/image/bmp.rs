@@ -0,0 +1,143 @@
+use error::{Error, Result};
+use image::{self, Image};
+
+/// @param bytes Content of a file
+/// @return Whether the file starts with the BMP magic number
+pub fn is_bmp(bytes: &[u8]) -> bool {
+  bytes.starts_with(b"BM")
+}
+
+/// Decodes uncompressed BMP images with 1, 4 or 8 bits per pixel and a
+/// palette, or 16, 24 or 32 bits per pixel. Rows are stored bottom up unless
+/// the height is negative. The result is always RGB.
+///
+/// @param bytes Content of the file
+/// @return Decoded image
+pub fn decode(bytes: &[u8]) -> Result<Image> {
+  if !is_bmp(bytes) || bytes.len() < 26 {
+    return Err(Error::Invalid("Not a BMP image, bad magic number.".to_string()));
+  }
+
+  let offset = u32_at(bytes, 10)? as usize;
+  let header_size = u32_at(bytes, 14)? as usize;
+
+  // The old OS/2 header has 16 bit dimensions and no compression.
+  let (width, height, depth, compression, colors) = if header_size == 12 {
+    (
+      i64::from(u16_at(bytes, 18)?),
+      i64::from(u16_at(bytes, 20)?),
+      u16_at(bytes, 24)?,
+      0,
+      0,
+    )
+  } else if header_size >= 40 {
+    (
+      i64::from(u32_at(bytes, 18)? as i32),
+      i64::from(u32_at(bytes, 22)? as i32),
+      u16_at(bytes, 28)?,
+      u32_at(bytes, 30)?,
+      u32_at(bytes, 46)? as usize,
+    )
+  } else {
+    return Err(Error::Invalid(format!("Unknown BMP header of {} bytes.", header_size)));
+  };
+
+  let top_down = height < 0;
+  let (width, height) = (width.unsigned_abs() as usize, height.unsigned_abs() as usize);
+
+  let masks = match (compression, depth) {
+    (0, 16) => [0x7c00, 0x03e0, 0x001f],
+    (0, _) => [0xff_0000, 0xff00, 0xff],
+    // Bit fields follow the 40 byte header or are part of the newer ones.
+    (3, 16) | (3, 32) => [u32_at(bytes, 54)?, u32_at(bytes, 58)?, u32_at(bytes, 62)?],
+    _ => return Err(Error::Invalid("Compressed BMP images are not supported.".to_string())),
+  };
+
+  let palette = match depth {
+    1 | 4 | 8 => palette(bytes, 14 + header_size, header_size == 12, colors, depth)?,
+    16 | 24 | 32 => Vec::new(),
+    _ => return Err(Error::Invalid(format!("BMP images with {} bits per pixel are not supported.", depth))),
+  };
+
+  // The dimensions are checked against the file before anything is
+  // allocated for them.
+  let depth = usize::from(depth);
+  let stride = image::values(width, depth, 1)?.div_ceil(32) * 4;
+  let data = image::values(stride, height, 1).ok()
+    .and_then(|size| offset.checked_add(size))
+    .and_then(|end| bytes.get(offset..end))
+    .ok_or_else(|| Error::Invalid("BMP image data is truncated.".to_string()))?;
+
+  let mut pixels = Vec::with_capacity(image::values(width, height, 3)?);
+
+  for y in 0..height {
+    let row = if top_down { y } else { height - 1 - y };
+    let row = &data[row * stride..(row + 1) * stride];
+
+    for x in 0..width {
+      match depth {
+        1 | 4 | 8 => {
+          let bit = x * depth;
+          let index = usize::from(row[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1);
+          let color = palette.get(index)
+            .ok_or_else(|| Error::Invalid(format!("Color {} is not in the BMP palette.", index)))?;
+
+          pixels.extend_from_slice(color);
+        },
+        24 => pixels.extend(row[x * 3..x * 3 + 3].iter().rev().map(|&value| f64::from(value) / 255_f64)),
+        _ => {
+          let bytes = depth / 8;
+          let value = row[x * bytes..(x + 1) * bytes].iter().rev()
+            .fold(0_u32, |value, &byte| value << 8 | u32::from(byte));
+
+          pixels.extend(masks.iter().map(|&mask| channel(value, mask)));
+        },
+      }
+    }
+  }
+
+  Image::new(width, height, 3, pixels)
+}
+
+/// Reads the colour table of images with up to 8 bits per pixel. Entries are
+/// blue, green, red and, except for the old header, an unused byte.
+fn palette(bytes: &[u8], start: usize, old: bool, colors: usize, depth: u16) -> Result<Vec<[f64; 3]>> {
+  let count = if colors == 0 { 1 << depth } else { colors };
+  let size = if old { 3 } else { 4 };
+
+  let table = bytes.get(start..start + count * size)
+    .ok_or_else(|| Error::Invalid("BMP palette is truncated.".to_string()))?;
+
+  Ok(table.chunks(size)
+    .map(|entry| [
+      f64::from(entry[2]) / 255_f64,
+      f64::from(entry[1]) / 255_f64,
+      f64::from(entry[0]) / 255_f64,
+    ])
+    .collect())
+}
+
+/// @param value Pixel read as a little endian number
+/// @param mask Bits of the channel
+/// @return Intensity of the channel in range 0 to 1
+fn channel(value: u32, mask: u32) -> f64 {
+  if mask == 0 {
+    return 0_f64;
+  }
+
+  let shift = mask.trailing_zeros();
+
+  f64::from((value & mask) >> shift) / f64::from(mask >> shift)
+}
+
+fn u16_at(bytes: &[u8], position: usize) -> Result<u16> {
+  bytes.get(position..position + 2)
+    .map(|value| u16::from_le_bytes([value[0], value[1]]))
+    .ok_or_else(|| Error::Invalid("BMP header is truncated.".to_string()))
+}
+
+fn u32_at(bytes: &[u8], position: usize) -> Result<u32> {
+  bytes.get(position..position + 4)
+    .map(|value| u32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+    .ok_or_else(|| Error::Invalid("BMP header is truncated.".to_string()))
+}
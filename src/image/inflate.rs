@@ -0,0 +1,296 @@
+use checksum;
+use error::{Error, Result};
+
+/// Base lengths of length codes 257 to 285.
+const LENGTH_BASE: [usize; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+  35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+
+/// Extra bits following length codes 257 to 285.
+const LENGTH_EXTRA: [usize; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+  3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base distances of distance codes 0 to 29.
+const DISTANCE_BASE: [usize; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+  257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// Extra bits following distance codes 0 to 29.
+const DISTANCE_EXTRA: [usize; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+  7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+/// Order in which the lengths of the code length code are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+  16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompresses a zlib stream: a two byte header, DEFLATE compressed data
+/// and the Adler-32 checksum of the result.
+///
+/// @param data Compressed stream
+/// @return Decompressed bytes
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>> {
+  if data.len() < 2 || data[0] & 0x0f != 8 || (u16::from(data[0]) << 8 | u16::from(data[1])) % 31 != 0 {
+    return Err(invalid("bad zlib header"));
+  }
+
+  if data[1] & 0x20 != 0 {
+    return Err(invalid("zlib preset dictionaries are not supported"));
+  }
+
+  let mut bits = Bits { data: &data[2..], position: 0, bit: 0 };
+  let output = inflate(&mut bits)?;
+
+  let end = 2 + bits.position + usize::from(bits.bit > 0);
+  let expected = data.get(end..end + 4)
+    .map(|sum| u32::from_be_bytes([sum[0], sum[1], sum[2], sum[3]]))
+    .ok_or_else(|| invalid("zlib checksum is missing"))?;
+
+  if checksum::adler32(&output) != expected {
+    return Err(invalid("zlib checksum does not match"));
+  }
+
+  Ok(output)
+}
+
+/// Decompresses DEFLATE blocks until the last one.
+///
+/// @param bits Compressed data
+/// @return Decompressed bytes
+fn inflate(bits: &mut Bits) -> Result<Vec<u8>> {
+  let mut output = Vec::new();
+
+  loop {
+    let last = bits.read(1)? == 1;
+
+    match bits.read(2)? {
+      0 => stored(bits, &mut output)?,
+      1 => {
+        let (literals, distances) = fixed_codes();
+        codes(bits, &mut output, &literals, &distances)?;
+      },
+      2 => {
+        let (literals, distances) = dynamic_codes(bits)?;
+        codes(bits, &mut output, &literals, &distances)?;
+      },
+      _ => return Err(invalid("bad DEFLATE block type")),
+    }
+
+    if last {
+      return Ok(output);
+    }
+  }
+}
+
+/// Copies a block that is not compressed.
+fn stored(bits: &mut Bits, output: &mut Vec<u8>) -> Result<()> {
+  bits.align();
+
+  let header = bits.bytes(4)?;
+  let length = usize::from(u16::from_le_bytes([header[0], header[1]]));
+
+  if u16::from_le_bytes([header[0], header[1]]) != !u16::from_le_bytes([header[2], header[3]]) {
+    return Err(invalid("stored block length is corrupted"));
+  }
+
+  output.extend_from_slice(bits.bytes(length)?);
+
+  Ok(())
+}
+
+/// Decodes literals and back references of a compressed block.
+fn codes(
+  bits: &mut Bits,
+  output: &mut Vec<u8>,
+  literals: &Huffman,
+  distances: &Huffman,
+) -> Result<()> {
+  loop {
+    let symbol = literals.decode(bits)?;
+
+    match symbol {
+      0..=255 => output.push(symbol as u8),
+      256 => return Ok(()),
+      257..=285 => {
+        let code = symbol - 257;
+        let length = LENGTH_BASE[code] + bits.read(LENGTH_EXTRA[code])?;
+
+        let code = distances.decode(bits)?;
+        if code >= 30 {
+          return Err(invalid("bad distance code"));
+        }
+
+        let distance = DISTANCE_BASE[code] + bits.read(DISTANCE_EXTRA[code])?;
+        if distance > output.len() {
+          return Err(invalid("distance reaches before the start of the data"));
+        }
+
+        // The copy may overlap the bytes it produces, so it goes byte by byte.
+        let start = output.len() - distance;
+        for index in start..start + length {
+          let byte = output[index];
+          output.push(byte);
+        }
+      },
+      _ => return Err(invalid("bad literal or length code")),
+    }
+  }
+}
+
+/// @return Literal and distance codes of blocks with fixed Huffman codes
+fn fixed_codes() -> (Huffman, Huffman) {
+  let mut lengths = [0_usize; 288];
+  lengths[..144].iter_mut().for_each(|length| *length = 8);
+  lengths[144..256].iter_mut().for_each(|length| *length = 9);
+  lengths[256..280].iter_mut().for_each(|length| *length = 7);
+  lengths[280..].iter_mut().for_each(|length| *length = 8);
+
+  (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+/// Reads literal and distance codes of blocks with dynamic Huffman codes.
+/// Their lengths are themselves compressed by a code length code.
+fn dynamic_codes(bits: &mut Bits) -> Result<(Huffman, Huffman)> {
+  let literal_count = bits.read(5)? + 257;
+  let distance_count = bits.read(5)? + 1;
+  let length_count = bits.read(4)? + 4;
+
+  let mut code_lengths = [0_usize; 19];
+  for &index in CODE_LENGTH_ORDER.iter().take(length_count) {
+    code_lengths[index] = bits.read(3)?;
+  }
+  let code_lengths = Huffman::new(&code_lengths);
+
+  let mut lengths = Vec::with_capacity(literal_count + distance_count);
+  while lengths.len() < literal_count + distance_count {
+    let (length, repeat) = match code_lengths.decode(bits)? {
+      symbol @ 0..=15 => (symbol, 1),
+      16 => match lengths.last() {
+        Some(&previous) => (previous, 3 + bits.read(2)?),
+        None => return Err(invalid("repeated code length has no predecessor")),
+      },
+      17 => (0, 3 + bits.read(3)?),
+      _ => (0, 11 + bits.read(7)?),
+    };
+
+    lengths.extend((0..repeat).map(|_| length));
+  }
+
+  if lengths.len() > literal_count + distance_count {
+    return Err(invalid("code lengths overflow"));
+  }
+
+  if lengths[256] == 0 {
+    return Err(invalid("block has no end code"));
+  }
+
+  let (literals, distances) = lengths.split_at(literal_count);
+
+  Ok((Huffman::new(literals), Huffman::new(distances)))
+}
+
+/// Canonical Huffman code stored as number of codes of each length and the
+/// symbols ordered by their codes.
+struct Huffman {
+  counts: [usize; 16],
+  symbols: Vec<usize>,
+}
+
+impl Huffman {
+
+  /// @param lengths Code length of each symbol, 0 for unused symbols
+  fn new(lengths: &[usize]) -> Huffman {
+    let mut counts = [0_usize; 16];
+    for &length in lengths {
+      counts[length] += 1;
+    }
+    counts[0] = 0;
+
+    let mut symbols: Vec<usize> = (0..lengths.len()).filter(|&symbol| lengths[symbol] > 0).collect();
+    symbols.sort_by_key(|&symbol| lengths[symbol]);
+
+    Huffman { counts, symbols }
+  }
+
+  /// Reads bits until they form a code. Codes of each length are
+  /// consecutive numbers following the codes of shorter lengths.
+  fn decode(&self, bits: &mut Bits) -> Result<usize> {
+    let (mut code, mut first, mut index) = (0, 0, 0);
+
+    for &count in &self.counts[1..] {
+      code |= bits.read(1)?;
+
+      if code < first + count {
+        return Ok(self.symbols[index + code - first]);
+      }
+
+      index += count;
+      first = (first + count) << 1;
+      code <<= 1;
+    }
+
+    Err(invalid("bad Huffman code"))
+  }
+
+}
+
+/// Reader of the bits of a DEFLATE stream, least significant bit first.
+struct Bits<'a> {
+  data: &'a [u8],
+  position: usize,
+  bit: usize,
+}
+
+impl<'a> Bits<'a> {
+
+  /// @param count Number of bits, at most 16
+  /// @return Bits as a number, the first one being the least significant
+  fn read(&mut self, count: usize) -> Result<usize> {
+    let mut value = 0;
+
+    for index in 0..count {
+      let byte = *self.data.get(self.position).ok_or_else(|| invalid("data is truncated"))?;
+
+      value |= usize::from(byte >> self.bit & 1) << index;
+
+      self.bit += 1;
+      if self.bit == 8 {
+        self.bit = 0;
+        self.position += 1;
+      }
+    }
+
+    Ok(value)
+  }
+
+  /// Skips the rest of the current byte.
+  fn align(&mut self) {
+    if self.bit > 0 {
+      self.bit = 0;
+      self.position += 1;
+    }
+  }
+
+  /// @param count Number of whole bytes, the reader must be aligned
+  fn bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+    let bytes = self.data.get(self.position..self.position + count)
+      .ok_or_else(|| invalid("data is truncated"))?;
+
+    self.position += count;
+
+    Ok(bytes)
+  }
+
+}
+
+/// @param message What is wrong with the data
+/// @return Error describing corrupted compressed data
+fn invalid(message: &str) -> Error {
+  Error::Invalid(format!("Cannot decompress: {}.", message))
+}
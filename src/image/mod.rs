@@ -0,0 +1,241 @@
+
+/*
+ * Exports decoders of the supported image file formats.
+ */
+
+pub mod netpbm;
+pub mod png;
+pub mod bmp;
+
+/*
+ * Exports the zlib decompression PNG images are compressed with.
+ */
+
+pub mod inflate;
+
+use std::fs;
+use std::path::Path;
+use error::{Error, Result};
+use reader::sample::Shape;
+
+/// Decoded image with intensities in range 0 to 1, where 0 is black. Pixels
+/// are stored row by row with the channels of each pixel next to each other,
+/// the same layout image shaped samples use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+  pub width: usize,
+  pub height: usize,
+  pub channels: usize,
+  pub pixels: Vec<f64>,
+}
+
+impl Image {
+
+  /// @param width Number of pixels in a row
+  /// @param height Number of rows
+  /// @param channels Number of values per pixel, 1 for grayscale, 3 for RGB
+  /// @param pixels Intensities in range 0 to 1
+  /// @return New Image instance
+  pub fn new (width: usize, height: usize, channels: usize, pixels: Vec<f64>) -> Result<Self> {
+    let count = values(width, height, channels)?;
+    if pixels.len() != count {
+      return Err(Error::Invalid(format!(
+        "Image of {}x{}x{} needs {} values, found {}.",
+        width, height, channels, count, pixels.len(),
+      )));
+    }
+
+    Ok(Image { width, height, channels, pixels })
+  }
+
+  /// Reads an image file. The format is recognised by the content, not by the
+  /// extension.
+  ///
+  /// @param path Path to the image
+  /// @return Decoded image
+  pub fn load<P: AsRef<Path>> (path: P) -> Result<Image> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+
+    Image::decode(&bytes).map_err(|error| {
+      Error::Invalid(format!("Cannot read {}: {}", path.display(), error))
+    })
+  }
+
+  /// Decodes an image from the content of a file.
+  ///
+  /// @param bytes Content of the file
+  /// @return Decoded image
+  pub fn decode (bytes: &[u8]) -> Result<Image> {
+    if png::is_png(bytes) {
+      png::decode(bytes)
+    } else if bmp::is_bmp(bytes) {
+      bmp::decode(bytes)
+    } else if netpbm::is_netpbm(bytes) {
+      netpbm::decode(bytes)
+    } else {
+      Err(Error::Invalid("Unknown image format, expected PNG, BMP, PBM, PGM or PPM.".to_string()))
+    }
+  }
+
+  /// @return Dimensions of the image
+  pub fn shape (&self) -> Shape {
+    Shape::new(self.width, self.height, self.channels)
+  }
+
+  /// Converts colour images to one channel using the luma weights of ITU-R
+  /// BT.601. A fourth channel is taken for alpha and ignored.
+  ///
+  /// @return Grayscale image
+  pub fn grayscale (&self) -> Image {
+    let pixels = match self.channels {
+      1 => self.pixels.clone(),
+      2 => self.pixels.chunks(2).map(|pixel| pixel[0]).collect(),
+      _ => self.pixels.chunks(self.channels)
+        .map(|pixel| 0.299_f64 * pixel[0] + 0.587_f64 * pixel[1] + 0.114_f64 * pixel[2])
+        .collect(),
+    };
+
+    Image { width: self.width, height: self.height, channels: 1, pixels }
+  }
+
+  /// Converts the image to three channels. Gray values are copied into red,
+  /// green and blue, alpha is ignored.
+  ///
+  /// @return RGB image
+  pub fn rgb (&self) -> Image {
+    let pixels = match self.channels {
+      1 | 2 => self.pixels.chunks(self.channels)
+        .flat_map(|pixel| vec!(pixel[0]; 3))
+        .collect(),
+      _ => self.pixels.chunks(self.channels)
+        .flat_map(|pixel| pixel[..3].to_vec())
+        .collect(),
+    };
+
+    Image { width: self.width, height: self.height, channels: 3, pixels }
+  }
+
+  /// Resamples the image to given size. Each new pixel is the average of the
+  /// area of the old image it covers, so shrinking a scan keeps thin strokes
+  /// visible instead of skipping them.
+  ///
+  /// @param width Number of pixels in a row of the new image
+  /// @param height Number of rows of the new image
+  /// @return Resized image
+  pub fn resize (&self, width: usize, height: usize) -> Image {
+    let columns = area_weights(self.width, width);
+    let rows = area_weights(self.height, height);
+    let channels = self.channels;
+
+    // Resizes the rows first and then the columns of the result.
+    let mut narrow = vec!(0_f64; width * self.height * channels);
+    for y in 0..self.height {
+      for (x, weights) in columns.iter().enumerate() {
+        for &(source, weight) in weights {
+          for channel in 0..channels {
+            narrow[(y * width + x) * channels + channel] +=
+              weight * self.pixels[(y * self.width + source) * channels + channel];
+          }
+        }
+      }
+    }
+
+    let mut pixels = vec!(0_f64; width * height * channels);
+    for (y, weights) in rows.iter().enumerate() {
+      for &(source, weight) in weights {
+        for x in 0..width * channels {
+          pixels[y * width * channels + x] += weight * narrow[source * width * channels + x];
+        }
+      }
+    }
+
+    Image { width, height, channels, pixels }
+  }
+
+  /// Swaps dark and light, e.g. to turn dark ink on white paper into the
+  /// light strokes on black background the digits data uses.
+  ///
+  /// @return Inverted image
+  pub fn invert (&self) -> Image {
+    Image { pixels: self.pixels.iter().map(|pixel| 1_f64 - pixel).collect(), ..self.clone() }
+  }
+
+  /// Stretches the intensities so that the darkest pixel becomes 0 and the
+  /// brightest 1, which evens out scans of different exposure. Flat images
+  /// are left as they are.
+  ///
+  /// @return Image using the whole range of intensities
+  pub fn stretch (&self) -> Image {
+    let min = self.pixels.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = self.pixels.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    if max - min <= 0_f64 {
+      return self.clone();
+    }
+
+    Image {
+      pixels: self.pixels.iter().map(|pixel| (pixel - min) / (max - min)).collect(),
+      ..self.clone()
+    }
+  }
+
+  /// Converts the image into the features of a sample of given shape:
+  /// colours are dropped for one channel shapes, gray is spread and alpha
+  /// dropped for three channel shapes and the image is resized.
+  ///
+  /// @param shape Dimensions the network expects
+  /// @return Features of the image
+  pub fn features (&self, shape: &Shape) -> Result<Vec<f64>> {
+    let image = match (self.channels, shape.channels) {
+      (from, to) if from == to => self.clone(),
+      (_, 1) => self.grayscale(),
+      (_, 3) => self.rgb(),
+      (from, to) => return Err(Error::Invalid(format!(
+        "Cannot convert image with {} channels into {} channels, only into 1 or 3.", from, to,
+      ))),
+    };
+
+    Ok(image.resize(shape.width, shape.height).pixels)
+  }
+
+}
+
+/// Decoders call this before trusting the dimensions of a file header.
+///
+/// @param width Number of pixels in a row
+/// @param height Number of rows
+/// @param channels Number of values per pixel
+/// @return Number of values of an image of that size
+pub fn values (width: usize, height: usize, channels: usize) -> Result<usize> {
+  width.checked_mul(height)
+    .and_then(|pixels| pixels.checked_mul(channels))
+    .ok_or_else(|| Error::Invalid(format!(
+      "Image of {}x{}x{} is too large.", width, height, channels,
+    )))
+}
+
+/// Computes how much each old pixel contributes to each new one when a line
+/// of pixels is resampled by area averaging.
+///
+/// @param source Number of old pixels
+/// @param target Number of new pixels
+/// @return For each new pixel the old pixels it covers with their weights
+fn area_weights (source: usize, target: usize) -> Vec<Vec<(usize, f64)>> {
+  let ratio = source as f64 / target as f64;
+
+  (0..target)
+    .map(|index| {
+      let (start, end) = (index as f64 * ratio, (index + 1) as f64 * ratio);
+
+      (start.floor() as usize..(end.ceil() as usize).min(source))
+        .map(|pixel| {
+          let overlap = end.min(pixel as f64 + 1_f64) - start.max(pixel as f64);
+
+          (pixel, overlap / ratio)
+        })
+        .filter(|&(_, weight)| weight > 0_f64)
+        .collect()
+    })
+    .collect()
+}
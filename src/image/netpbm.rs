@@ -0,0 +1,179 @@
+use error::{Error, Result};
+use image::{self, Image};
+
+/// @param bytes Content of a file
+/// @return Whether the file starts with a Netpbm magic number P1 to P6
+pub fn is_netpbm(bytes: &[u8]) -> bool {
+  bytes.len() >= 2 && bytes[0] == b'P' && (b'1'..=b'6').contains(&bytes[1])
+}
+
+/// Decodes PBM, PGM and PPM images, both the plain text variants P1 to P3
+/// and the binary variants P4 to P6. Intensities are divided by the maximum
+/// value of the file. Set bits of PBM images are black, so they become 0.
+///
+/// @param bytes Content of the file
+/// @return Decoded image
+pub fn decode(bytes: &[u8]) -> Result<Image> {
+  if !is_netpbm(bytes) {
+    return Err(Error::Invalid("Not a Netpbm image, bad magic number.".to_string()));
+  }
+
+  let kind = bytes[1] - b'0';
+  let mut header = Header { bytes, position: 2 };
+
+  let width = header.number("width")?;
+  let height = header.number("height")?;
+  let max = if kind == 1 || kind == 4 { 1 } else { header.number("maximum value")? };
+  let channels = if kind == 3 || kind == 6 { 3 } else { 1 };
+
+  if max == 0 || max > 65535 {
+    return Err(Error::Invalid(format!("Maximum value {} is out of range 1 to 65535.", max)));
+  }
+
+  let count = image::values(width, height, channels)?;
+
+  let values: Vec<usize> = match kind {
+    1 => header.bits(count)?,
+    2 | 3 => (0..count).map(|_| header.number("pixel")).collect::<Result<_>>()?,
+    _ => {
+      // A single whitespace character separates the header from the data.
+      let data = bytes.get(header.position + 1..).unwrap_or(&[]);
+
+      match kind {
+        4 => unpack_bits(data, width, height)?,
+        _ if max < 256 => take(data, count, 1)?.iter().map(|&value| usize::from(value)).collect(),
+        _ => take(data, count, 2)?.chunks(2)
+          .map(|value| usize::from(value[0]) << 8 | usize::from(value[1]))
+          .collect(),
+      }
+    },
+  };
+
+  if let Some(value) = values.iter().find(|&&value| value > max) {
+    return Err(Error::Invalid(format!("Pixel value {} exceeds maximum {}.", value, max)));
+  }
+
+  let pixels = match kind {
+    1 | 4 => values.iter().map(|&bit| 1_f64 - bit as f64).collect(),
+    _ => values.iter().map(|&value| value as f64 / max as f64).collect(),
+  };
+
+  Image::new(width, height, channels, pixels)
+}
+
+/// Reader of the text part of a Netpbm file.
+struct Header<'a> {
+  bytes: &'a [u8],
+  position: usize,
+}
+
+impl<'a> Header<'a> {
+
+  /// Skips whitespace and comments, which run from "#" to the end of line.
+  fn skip_whitespace(&mut self) {
+    while let Some(&byte) = self.bytes.get(self.position) {
+      if byte == b'#' {
+        while self.bytes.get(self.position).is_some_and(|&byte| byte != b'\n') {
+          self.position += 1;
+        }
+      } else if byte.is_ascii_whitespace() {
+        self.position += 1;
+      } else {
+        break;
+      }
+    }
+  }
+
+  /// @param name What the number means, for the error message
+  /// @return Next decimal number
+  fn number(&mut self, name: &str) -> Result<usize> {
+    self.skip_whitespace();
+
+    let start = self.position;
+    while self.bytes.get(self.position).is_some_and(u8::is_ascii_digit) {
+      self.position += 1;
+    }
+
+    ::std::str::from_utf8(&self.bytes[start..self.position]).ok()
+      .and_then(|digits| digits.parse().ok())
+      .ok_or_else(|| Error::Invalid(format!("Expected {} at byte {}.", name, start)))
+  }
+
+  /// Reads pixels of plain PBM images, which may or may not be separated by
+  /// whitespace.
+  ///
+  /// @param count Number of pixels
+  /// @return Bits of the pixels
+  fn bits(&mut self, count: usize) -> Result<Vec<usize>> {
+    // Every pixel takes at least one byte, which bounds the allocation by
+    // the size of the file.
+    let remaining = self.bytes.len().saturating_sub(self.position);
+    if count > remaining {
+      return Err(Error::Invalid(format!(
+        "Image data is truncated, expected {} pixels, found {} bytes.", count, remaining,
+      )));
+    }
+
+    let mut bits = Vec::with_capacity(count);
+
+    while bits.len() < count {
+      self.skip_whitespace();
+
+      match self.bytes.get(self.position) {
+        Some(b'0') => bits.push(0),
+        Some(b'1') => bits.push(1),
+        _ => return Err(Error::Invalid(format!("Expected pixel at byte {}.", self.position))),
+      }
+
+      self.position += 1;
+    }
+
+    Ok(bits)
+  }
+
+}
+
+/// @param data Bytes following the header
+/// @param count Number of values
+/// @param size Bytes per value
+/// @return Bytes of the values
+fn take(data: &[u8], count: usize, size: usize) -> Result<&[u8]> {
+  let bytes = image::values(count, size, 1)?;
+
+  data.get(..bytes).ok_or_else(|| Error::Invalid(format!(
+    "Image data is truncated, expected {} bytes, found {}.", bytes, data.len(),
+  )))
+}
+
+/// Unpacks binary PBM rows, where each row starts on a new byte.
+///
+/// @param data Bytes following the header
+/// @param width Number of pixels in a row
+/// @param height Number of rows
+/// @return Bits of the pixels
+fn unpack_bits(data: &[u8], width: usize, height: usize) -> Result<Vec<usize>> {
+  let stride = width.div_ceil(8);
+  let data = take(data, stride * height, 1)?;
+
+  Ok(data.chunks(stride)
+    .flat_map(|row| (0..width).map(move |x| usize::from(row[x / 8] >> (7 - x % 8) & 1)))
+    .collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decodes_plain_and_binary_variants() {
+    let plain = decode(b"P2\n# comment\n2 2\n4\n0 1\n2 4\n").unwrap();
+    let binary = decode(b"P5 2 2 4\n\x00\x01\x02\x04").unwrap();
+
+    assert_eq!(plain, binary);
+    assert_eq!(plain.pixels, vec!(0_f64, 0.25_f64, 0.5_f64, 1_f64));
+
+    let bits = decode(b"P4 3 2\n\xa0\x40").unwrap();
+    assert_eq!(bits.pixels, vec!(0_f64, 1_f64, 0_f64, 1_f64, 0_f64, 1_f64));
+    assert_eq!(decode(b"P1 3 2 101010").unwrap(), bits);
+  }
+}
@@ -0,0 +1,208 @@
+use checksum;
+use error::{Error, Result};
+use image::{self, Image};
+use super::inflate;
+
+/// First eight bytes of every PNG file.
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// @param bytes Content of a file
+/// @return Whether the file starts with the PNG signature
+pub fn is_png(bytes: &[u8]) -> bool {
+  bytes.starts_with(&SIGNATURE)
+}
+
+/// Decodes PNG images with 8 bits per channel in grayscale, grayscale with
+/// alpha, RGB or RGBA. Palette, 16 bit and interlaced images are rejected.
+///
+/// @param bytes Content of the file
+/// @return Decoded image
+pub fn decode(bytes: &[u8]) -> Result<Image> {
+  if !is_png(bytes) {
+    return Err(Error::Invalid("Not a PNG image, bad signature.".to_string()));
+  }
+
+  let mut header = None;
+  let mut compressed = Vec::new();
+  let mut position = SIGNATURE.len();
+
+  loop {
+    let (kind, data) = chunk(bytes, &mut position)?;
+
+    match kind {
+      b"IHDR" => header = Some(Header::read(data)?),
+      b"IDAT" => compressed.extend_from_slice(data),
+      b"IEND" => break,
+      // Colour images may suggest a palette for displays that need one.
+      b"PLTE" if header.as_ref().is_some_and(|header| header.channels >= 3) => (),
+      // Chunks with uppercase first letter are critical and cannot be skipped.
+      _ if kind[0].is_ascii_uppercase() => return Err(Error::Invalid(format!(
+        "Unsupported PNG chunk {}.", String::from_utf8_lossy(kind),
+      ))),
+      _ => (),
+    }
+  }
+
+  let header = header.ok_or_else(|| Error::Invalid("PNG has no header.".to_string()))?;
+  let data = inflate::zlib_decompress(&compressed)?;
+  let pixels = unfilter(&data, &header)?;
+
+  Image::new(
+    header.width,
+    header.height,
+    header.channels,
+    pixels.iter().map(|&value| f64::from(value) / 255_f64).collect(),
+  )
+}
+
+/// Dimensions and layout of the image from the IHDR chunk.
+struct Header {
+  width: usize,
+  height: usize,
+  channels: usize,
+}
+
+impl Header {
+
+  fn read(data: &[u8]) -> Result<Header> {
+    if data.len() != 13 {
+      return Err(Error::Invalid("PNG header has wrong length.".to_string()));
+    }
+
+    let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let (depth, color, interlace) = (data[8], data[9], data[12]);
+
+    let channels = match color {
+      0 => 1,
+      2 => 3,
+      4 => 2,
+      6 => 4,
+      3 => return Err(Error::Invalid("Palette PNG images are not supported.".to_string())),
+      _ => return Err(Error::Invalid(format!("Unknown PNG color type {}.", color))),
+    };
+
+    if depth != 8 {
+      return Err(Error::Invalid(format!(
+        "PNG images with {} bits per channel are not supported, only 8.", depth,
+      )));
+    }
+
+    if interlace != 0 {
+      return Err(Error::Invalid("Interlaced PNG images are not supported.".to_string()));
+    }
+
+    Ok(Header { width, height, channels })
+  }
+
+}
+
+/// Reads the chunk at given position and moves past it. A chunk is its
+/// length, a four letter type, the data and a CRC of the type and the data.
+///
+/// @param bytes Content of the file
+/// @param position Where the chunk starts
+/// @return Type and data of the chunk
+fn chunk<'a>(bytes: &'a [u8], position: &mut usize) -> Result<(&'a [u8], &'a [u8])> {
+  let truncated = || Error::Invalid("PNG is truncated.".to_string());
+  let start = *position;
+
+  let length = bytes.get(start..start + 4)
+    .map(|length| u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize)
+    .ok_or_else(truncated)?;
+
+  let end = start + 8 + length;
+  let content = bytes.get(start + 4..end).ok_or_else(truncated)?;
+  let crc = bytes.get(end..end + 4)
+    .map(|crc| u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]))
+    .ok_or_else(truncated)?;
+
+  if checksum::crc32(content) != crc {
+    return Err(Error::Invalid(format!(
+      "PNG chunk {} is corrupted.", String::from_utf8_lossy(&content[..4]),
+    )));
+  }
+
+  *position = end + 4;
+
+  Ok((&content[..4], &content[4..]))
+}
+
+/// Reverses the filter each row was encoded with. Every row starts with the
+/// filter type and each byte is predicted from the byte of the pixel to the
+/// left, the byte above or both.
+///
+/// @param data Decompressed rows
+/// @param header Layout of the image
+/// @return Bytes of the pixels
+fn unfilter(data: &[u8], header: &Header) -> Result<Vec<u8>> {
+  let pixel = header.channels;
+  let stride = image::values(header.width, pixel, 1)?;
+
+  // Each row is one filter byte longer than its pixels.
+  let size = image::values(stride + 1, header.height, 1)?;
+  if data.len() < size {
+    return Err(Error::Invalid("PNG image data is truncated.".to_string()));
+  }
+
+  let mut pixels = vec!(0_u8; stride * header.height);
+
+  for (y, row) in data.chunks(stride + 1).take(header.height).enumerate() {
+    let (filter, row) = (row[0], &row[1..]);
+    let (done, rest) = pixels.split_at_mut(y * stride);
+    let above = if y > 0 { &done[(y - 1) * stride..] } else { &[][..] };
+    let current = &mut rest[..stride];
+
+    for x in 0..stride {
+      let a = if x >= pixel { current[x - pixel] } else { 0 };
+      let b = above.get(x).cloned().unwrap_or(0);
+      let c = if x >= pixel { above.get(x - pixel).cloned().unwrap_or(0) } else { 0 };
+
+      let prediction = match filter {
+        0 => 0,
+        1 => a,
+        2 => b,
+        3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+        4 => paeth(a, b, c),
+        _ => return Err(Error::Invalid(format!("Unknown PNG filter type {}.", filter))),
+      };
+
+      current[x] = row[x].wrapping_add(prediction);
+    }
+  }
+
+  Ok(pixels)
+}
+
+/// Picks whichever of left, above and upper left is closest to
+/// left + above - upper left.
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+  let estimate = i16::from(a) + i16::from(b) - i16::from(c);
+  let (pa, pb, pc) = (
+    (estimate - i16::from(a)).abs(),
+    (estimate - i16::from(b)).abs(),
+    (estimate - i16::from(c)).abs(),
+  );
+
+  if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reconstructs_filtered_rows() {
+    let bytes = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR\x00\x00\x00\x03\x00\x00\x00\x03\x08\x00\
+      \x00\x00\x00\x73\x43\xea\x63\x00\x00\x00\x12IDAT\x78\xda\x63\xe4\xe2\xe2\x62\x92\x93\x93\
+      \x63\x01\x62\x00\x04\x34\x00\xda\x8c\xd1\x19\x14\x00\x00\x00\x00IEND\xae\x42\x60\x82";
+
+    // Rows use the sub, up and Paeth filters.
+    let image = decode(bytes).unwrap();
+    let values: Vec<u8> = image.pixels.iter().map(|pixel| (pixel * 255_f64).round() as u8).collect();
+
+    assert_eq!(image.shape().to_string(), "3x3x1");
+    assert_eq!(values, vec!(10, 20, 30, 40, 50, 60, 70, 100, 130));
+    assert!(decode(&bytes[..60]).is_err());
+  }
+}
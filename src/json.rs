@@ -0,0 +1,437 @@
+use std::fmt;
+use std::str::Chars;
+use std::iter::Peekable;
+use error::{Error, Result};
+
+/// JSON value. Objects keep their fields in insertion order so that the
+/// output is stable and easy to read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+  Null,
+  Bool(bool),
+  Number(f64),
+  String(String),
+  Array(Vec<Json>),
+  Object(Vec<(String, Json)>),
+}
+
+impl Json {
+
+  /// Builds an object from pairs of keys and values.
+  ///
+  /// @param fields Vector of (key, value) pairs
+  /// @return New JSON object
+  pub fn object (fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(
+      fields.into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+    )
+  }
+
+  /// Parses a JSON document. Errors point to the line and column where the
+  /// document stopped making sense.
+  ///
+  /// @param source Text of the document
+  /// @return Parsed value
+  pub fn parse (source: &str) -> Result<Json> {
+    let mut parser = Parser { chars: source.chars().peekable(), line: 1, column: 1, depth: 0 };
+
+    let value = parser.value()?;
+    parser.whitespace();
+
+    match parser.chars.peek() {
+      None => Ok(value),
+      Some(_) => Err(parser.error("unexpected characters after the value")),
+    }
+  }
+
+  /// Looks up a field of an object.
+  ///
+  /// @param key Name of the field
+  /// @return The value if this is an object with such field
+  pub fn get (&self, key: &str) -> Option<&Json> {
+    match *self {
+      Json::Object(ref fields) => fields.iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value),
+      _ => None,
+    }
+  }
+
+  /// @return The number if this is a number
+  pub fn as_f64 (&self) -> Option<f64> {
+    match *self {
+      Json::Number(value) => Some(value),
+      _ => None,
+    }
+  }
+
+  /// @return The number if this is a non negative whole number
+  pub fn as_usize (&self) -> Option<usize> {
+    match *self {
+      Json::Number(value) if value >= 0_f64 && value.fract() == 0_f64 => {
+        Some(value as usize)
+      },
+      _ => None,
+    }
+  }
+
+  /// @return The value if this is a boolean
+  pub fn as_bool (&self) -> Option<bool> {
+    match *self {
+      Json::Bool(value) => Some(value),
+      _ => None,
+    }
+  }
+
+  /// @return The string if this is a string
+  pub fn as_str (&self) -> Option<&str> {
+    match *self {
+      Json::String(ref value) => Some(value),
+      _ => None,
+    }
+  }
+
+  /// @return The items if this is an array
+  pub fn as_array (&self) -> Option<&Vec<Json>> {
+    match *self {
+      Json::Array(ref values) => Some(values),
+      _ => None,
+    }
+  }
+
+  /// @return The fields if this is an object
+  pub fn as_object (&self) -> Option<&Vec<(String, Json)>> {
+    match *self {
+      Json::Object(ref fields) => Some(fields),
+      _ => None,
+    }
+  }
+
+}
+
+/// Deepest nesting of arrays and objects the parser accepts, which keeps the
+/// recursion off the end of the stack.
+const MAX_DEPTH: usize = 128;
+
+/// Recursive descent parser that keeps track of its position for errors.
+struct Parser<'a> {
+
+  /// Remaining characters of the document.
+  chars: Peekable<Chars<'a>>,
+
+  /// Line of the next character.
+  line: usize,
+
+  /// Column of the next character.
+  column: usize,
+
+  /// Number of arrays and objects the next value is nested in.
+  depth: usize,
+
+}
+
+impl<'a> Parser<'a> {
+
+  /// Builds an error pointing at the current position.
+  fn error (&self, message: &str) -> Error {
+    Error::Parse { line: self.line, column: self.column, message: message.to_string() }
+  }
+
+  /// Consumes the next character and moves the position.
+  fn next (&mut self) -> Option<char> {
+    let character = self.chars.next()?;
+
+    if character == '\n' {
+      self.line += 1;
+      self.column = 1;
+    } else {
+      self.column += 1;
+    }
+
+    Some(character)
+  }
+
+  /// Consumes the next character if it is the expected one.
+  fn expect (&mut self, expected: char) -> Result<()> {
+    self.whitespace();
+
+    match self.chars.peek() {
+      Some(&character) if character == expected => {
+        self.next();
+        Ok(())
+      },
+      _ => Err(self.error(&format!("expected '{}'", expected))),
+    }
+  }
+
+  /// Skips any whitespace.
+  fn whitespace (&mut self) {
+    while let Some(&character) = self.chars.peek() {
+      if !character.is_whitespace() {
+        break;
+      }
+      self.next();
+    }
+  }
+
+  /// Parses any value.
+  fn value (&mut self) -> Result<Json> {
+    self.whitespace();
+
+    match self.chars.peek().cloned() {
+      None => Err(self.error("unexpected end of document")),
+      Some('{') | Some('[') => self.nested(),
+      Some('"') => self.string().map(Json::String),
+      Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+      Some(c) if c.is_alphabetic() => self.literal(),
+      Some(c) => Err(self.error(&format!("unexpected character '{}'", c))),
+    }
+  }
+
+  /// Parses an array or object one level deeper.
+  fn nested (&mut self) -> Result<Json> {
+    if self.depth == MAX_DEPTH {
+      return Err(self.error(&format!("nested deeper than {} levels", MAX_DEPTH)));
+    }
+
+    self.depth += 1;
+    let value = if self.chars.peek() == Some(&'{') { self.object() } else { self.array() };
+    self.depth -= 1;
+
+    value
+  }
+
+  /// Parses true, false or null.
+  fn literal (&mut self) -> Result<Json> {
+    let (line, column) = (self.line, self.column);
+    let mut word = String::new();
+
+    while let Some(&character) = self.chars.peek() {
+      if !character.is_alphanumeric() {
+        break;
+      }
+      word.push(character);
+      self.next();
+    }
+
+    match word.as_str() {
+      "true" => Ok(Json::Bool(true)),
+      "false" => Ok(Json::Bool(false)),
+      "null" => Ok(Json::Null),
+      _ => Err(Error::Parse {
+        line,
+        column,
+        message: format!("unknown literal '{}'", word),
+      }),
+    }
+  }
+
+  /// Parses a number.
+  fn number (&mut self) -> Result<Json> {
+    let (line, column) = (self.line, self.column);
+    let mut text = String::new();
+
+    while let Some(&character) = self.chars.peek() {
+      if !(character.is_ascii_digit() || "+-.eE".contains(character)) {
+        break;
+      }
+      text.push(character);
+      self.next();
+    }
+
+    text.parse::<f64>()
+      .map(Json::Number)
+      .map_err(|_| Error::Parse {
+        line,
+        column,
+        message: format!("invalid number '{}'", text),
+      })
+  }
+
+  /// Parses a quoted string including escape sequences.
+  fn string (&mut self) -> Result<String> {
+    self.expect('"')?;
+    let mut value = String::new();
+
+    loop {
+      match self.next() {
+        None => return Err(self.error("unterminated string")),
+        Some('"') => return Ok(value),
+        Some('\\') => match self.next() {
+          Some('"') => value.push('"'),
+          Some('\\') => value.push('\\'),
+          Some('/') => value.push('/'),
+          Some('b') => value.push('\u{8}'),
+          Some('f') => value.push('\u{c}'),
+          Some('n') => value.push('\n'),
+          Some('r') => value.push('\r'),
+          Some('t') => value.push('\t'),
+          Some('u') => {
+            let code: String = (0..4).filter_map(|_| self.next()).collect();
+            let character = u32::from_str_radix(&code, 16).ok()
+              .and_then(::std::char::from_u32)
+              .ok_or_else(|| self.error("invalid unicode escape"))?;
+            value.push(character);
+          },
+          _ => return Err(self.error("invalid escape sequence")),
+        },
+        Some(character) => value.push(character),
+      }
+    }
+  }
+
+  /// Parses an array.
+  fn array (&mut self) -> Result<Json> {
+    self.expect('[')?;
+    let mut values = Vec::new();
+
+    self.whitespace();
+    if self.chars.peek() == Some(&']') {
+      self.next();
+      return Ok(Json::Array(values));
+    }
+
+    loop {
+      values.push(self.value()?);
+      self.whitespace();
+
+      match self.next() {
+        Some(',') => continue,
+        Some(']') => return Ok(Json::Array(values)),
+        _ => return Err(self.error("expected ',' or ']'")),
+      }
+    }
+  }
+
+  /// Parses an object.
+  fn object (&mut self) -> Result<Json> {
+    self.expect('{')?;
+    let mut fields = Vec::new();
+
+    self.whitespace();
+    if self.chars.peek() == Some(&'}') {
+      self.next();
+      return Ok(Json::Object(fields));
+    }
+
+    loop {
+      self.whitespace();
+      let key = self.string()?;
+      self.expect(':')?;
+      fields.push((key, self.value()?));
+      self.whitespace();
+
+      match self.next() {
+        Some(',') => continue,
+        Some('}') => return Ok(Json::Object(fields)),
+        _ => return Err(self.error("expected ',' or '}'")),
+      }
+    }
+  }
+
+}
+
+impl From<bool> for Json {
+  fn from (value: bool) -> Json { Json::Bool(value) }
+}
+
+impl From<f64> for Json {
+  fn from (value: f64) -> Json { Json::Number(value) }
+}
+
+impl From<usize> for Json {
+  fn from (value: usize) -> Json { Json::Number(value as f64) }
+}
+
+impl<'a> From<&'a str> for Json {
+  fn from (value: &'a str) -> Json { Json::String(value.to_string()) }
+}
+
+impl From<String> for Json {
+  fn from (value: String) -> Json { Json::String(value) }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+  fn from (value: Option<T>) -> Json {
+    value.map_or(Json::Null, Into::into)
+  }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+  fn from (values: Vec<T>) -> Json {
+    Json::Array(values.into_iter().map(Into::into).collect())
+  }
+}
+
+impl fmt::Display for Json {
+
+  /// Writes the value as compact JSON on a single line.
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Json::Null => write!(f, "null"),
+      Json::Bool(value) => write!(f, "{}", value),
+      // JSON has no representation for NaN and infinities.
+      Json::Number(value) if !value.is_finite() => write!(f, "null"),
+      Json::Number(value) => write!(f, "{}", value),
+      Json::String(ref value) => write_string(f, value),
+      Json::Array(ref values) => {
+        write!(f, "[")?;
+        for (index, value) in values.iter().enumerate() {
+          if index > 0 {
+            write!(f, ",")?;
+          }
+          write!(f, "{}", value)?;
+        }
+        write!(f, "]")
+      },
+      Json::Object(ref fields) => {
+        write!(f, "{{")?;
+        for (index, (key, value)) in fields.iter().enumerate() {
+          if index > 0 {
+            write!(f, ",")?;
+          }
+          write_string(f, key)?;
+          write!(f, ":{}", value)?;
+        }
+        write!(f, "}}")
+      },
+    }
+  }
+
+}
+
+/// Writes a quoted string with all characters JSON requires escaped.
+///
+/// @param f Formatter to write to
+/// @param value Raw string
+fn write_string (f: &mut fmt::Formatter, value: &str) -> fmt::Result {
+  write!(f, "\"")?;
+  for character in value.chars() {
+    match character {
+      '"' => write!(f, "\\\"")?,
+      '\\' => write!(f, "\\\\")?,
+      '\n' => write!(f, "\\n")?,
+      '\r' => write!(f, "\\r")?,
+      '\t' => write!(f, "\\t")?,
+      c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+      c => write!(f, "{}", c)?,
+    }
+  }
+  write!(f, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Json, MAX_DEPTH};
+
+  #[test]
+  fn limits_the_nesting_depth() {
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+    assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+    assert!(Json::parse(&nested(MAX_DEPTH + 1)).is_err());
+    assert!(Json::parse(&nested(100_000)).is_err());
+  }
+}
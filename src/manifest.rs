@@ -0,0 +1,244 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use checksum::{self, Sha256};
+use error::{Error, Result};
+use json::Json;
+
+/// Data file that took part in a training run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetFile {
+
+  /// What the file was used for, e.g. "train" or "validation".
+  pub role: String,
+
+  /// Path to the file as it was given.
+  pub path: String,
+
+  /// SHA-256 of the file content in hexadecimal.
+  pub sha256: String,
+
+  /// Number of samples read from the file.
+  pub rows: usize,
+
+}
+
+/// Record of how a model was made, stored inside the model file so that a
+/// good model can be reproduced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+
+  /// Commit of the code the model was trained with, if it was run from a git
+  /// checkout. A "-dirty" suffix marks uncommitted changes.
+  pub commit: Option<String>,
+
+  /// Config of the run.
+  pub config: Json,
+
+  /// Data files the model was trained and validated on.
+  pub datasets: Vec<DatasetFile>,
+
+  /// Seed of the weights and the data ordering.
+  pub seed: Option<u64>,
+
+  /// When the training started, in seconds since the Unix epoch.
+  pub started_at: u64,
+
+  /// When the training finished, in seconds since the Unix epoch.
+  pub finished_at: u64,
+
+  /// How many epochs were run.
+  pub epochs: usize,
+
+  /// Final metrics of the run, e.g. validation accuracy.
+  pub metrics: Json,
+
+}
+
+impl DatasetFile {
+
+  /// Hashes a data file. Directories, such as directories of images, are
+  /// hashed by the relative path and digest of every file in them.
+  ///
+  /// @param role What the file was used for
+  /// @param path Path to the file or directory
+  /// @param rows Number of samples read from the file
+  /// @return New DatasetFile instance
+  pub fn hash<P: AsRef<Path>> (role: &str, path: P, rows: usize) -> Result<Self> {
+    let path = path.as_ref();
+
+    Ok(DatasetFile {
+      role: role.to_string(),
+      path: path.display().to_string(),
+      sha256: checksum::hex(&digest(path, path)?),
+      rows,
+    })
+  }
+
+}
+
+/// @param root Directory the hash was asked for, paths are relative to it
+/// @param path File or directory to hash
+/// @return SHA-256 digest of the content
+fn digest (root: &Path, path: &Path) -> Result<[u8; 32]> {
+  if !path.is_dir() {
+    // Data files can be larger than memory, so they are hashed in parts.
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec!(0_u8; 1 << 16);
+
+    loop {
+      match file.read(&mut buffer)? {
+        0 => return Ok(hasher.finish()),
+        read => hasher.update(&buffer[..read]),
+      }
+    }
+  }
+
+  let mut entries = fs::read_dir(path)?
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<::std::result::Result<Vec<_>, _>>()?;
+  entries.sort();
+
+  let mut listing = String::new();
+  for entry in entries {
+    let name = entry.strip_prefix(root).unwrap_or(&entry).display().to_string();
+    listing.push_str(&format!("{} {}\n", checksum::hex(&digest(root, &entry)?), name));
+  }
+
+  Ok(checksum::sha256(listing.as_bytes()))
+}
+
+impl Manifest {
+
+  /// Serializes the manifest into a JSON object. Times are written both as
+  /// Unix seconds and in human readable form.
+  ///
+  /// @return JSON representation of the manifest
+  pub fn to_json (&self) -> Json {
+    let datasets: Vec<Json> = self.datasets.iter()
+      .map(|file| Json::object(vec!(
+        ("role", file.role.as_str().into()),
+        ("path", file.path.as_str().into()),
+        ("sha256", file.sha256.as_str().into()),
+        ("rows", file.rows.into()),
+      )))
+      .collect();
+
+    Json::object(vec!(
+      ("commit", self.commit.clone().into()),
+      ("config", self.config.clone()),
+      ("datasets", Json::Array(datasets)),
+      ("seed", self.seed.map(|seed| seed as usize).into()),
+      ("started_at", (self.started_at as usize).into()),
+      ("started", format_timestamp(self.started_at).into()),
+      ("finished_at", (self.finished_at as usize).into()),
+      ("finished", format_timestamp(self.finished_at).into()),
+      ("epochs", self.epochs.into()),
+      ("metrics", self.metrics.clone()),
+    ))
+  }
+
+  /// Deserializes the manifest from a JSON object.
+  ///
+  /// @param json JSON representation of the manifest
+  /// @return Manifest instance
+  pub fn from_json (json: &Json) -> Result<Manifest> {
+    let invalid = |field: &str| Error::Invalid(format!("Invalid manifest: bad {}.", field));
+    let number = |field: &str| json.get(field)
+      .and_then(Json::as_usize)
+      .ok_or_else(|| invalid(field));
+
+    let datasets = json.get("datasets")
+      .and_then(Json::as_array)
+      .ok_or_else(|| invalid("datasets"))?
+      .iter()
+      .map(|file| {
+        let text = |field: &str| file.get(field)
+          .and_then(Json::as_str)
+          .map(str::to_string)
+          .ok_or_else(|| invalid(field));
+
+        Ok(DatasetFile {
+          role: text("role")?,
+          path: text("path")?,
+          sha256: text("sha256")?,
+          rows: file.get("rows").and_then(Json::as_usize).ok_or_else(|| invalid("rows"))?,
+        })
+      })
+      .collect::<Result<Vec<DatasetFile>>>()?;
+
+    Ok(Manifest {
+      commit: json.get("commit").and_then(Json::as_str).map(str::to_string),
+      config: json.get("config").cloned().unwrap_or(Json::Null),
+      datasets,
+      seed: json.get("seed").and_then(Json::as_usize).map(|seed| seed as u64),
+      started_at: number("started_at")? as u64,
+      finished_at: number("finished_at")? as u64,
+      epochs: number("epochs")?,
+      metrics: json.get("metrics").cloned().unwrap_or(Json::Null),
+    })
+  }
+
+}
+
+/// Finds the commit of the source tree the binary was built from by asking
+/// git, wherever the binary runs from. Returns nothing when git is not
+/// installed or the sources are not a checkout.
+///
+/// @return Commit hash with "-dirty" suffix if there are uncommitted changes
+pub fn git_commit () -> Option<String> {
+  let git = |arguments: &[&str]| {
+    Command::new("git")
+      .arg("-C")
+      .arg(env!("CARGO_MANIFEST_DIR"))
+      .args(arguments)
+      .output()
+      .ok()
+      .filter(|output| output.status.success())
+      .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+  };
+
+  let commit = git(&["rev-parse", "HEAD"])?;
+
+  match git(&["status", "--porcelain", "--untracked-files=no"]) {
+    Some(ref changes) if !changes.is_empty() => Some(format!("{}-dirty", commit)),
+    _ => Some(commit),
+  }
+}
+
+/// @return Current time in seconds since the Unix epoch
+pub fn now () -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or(0)
+}
+
+/// Formats Unix seconds as an ISO 8601 UTC timestamp.
+///
+/// @param seconds Seconds since the Unix epoch
+/// @return Timestamp such as 2018-12-24T18:30:00Z
+pub fn format_timestamp (seconds: u64) -> String {
+  let days = (seconds / 86400) as i64;
+  let time = seconds % 86400;
+
+  // Converts days since the epoch to a civil date, see Howard Hinnant's
+  // chrono-compatible low-level date algorithms.
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let day_of_era = z - era * 146_097;
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+    year, month, day, time / 3600, time % 3600 / 60, time % 60,
+  )
+}
@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+use json::Json;
+use error::{Error, Result};
+use neural_network::network::Network;
+use neural_network::network_classify::Rejection;
+use manifest::Manifest;
+use preprocess::Pipeline;
+use reader::sample::Shape;
+
+/// Identifies model files written by this crate.
+const FORMAT: &str = "classifier-model";
+
+/// Version of the model file layout.
+const VERSION: usize = 1;
+
+/// Everything that is needed to classify inputs with a trained network. This
+/// is what gets written to and read from model files.
+pub struct Model {
+
+  /// Trained network.
+  pub network: Network,
+
+  /// Name of the class of each output neuron, so that labels read from the
+  /// data map onto the same outputs the network was trained with.
+  pub labels: Vec<String>,
+
+  /// Image shape of the inputs, so that images can be resized to fit.
+  pub shape: Option<Shape>,
+
+  /// Transforms raw inputs into what the network was trained on.
+  pub preprocessing: Pipeline,
+
+  /// Record of how the network was trained.
+  pub manifest: Option<Manifest>,
+
+}
+
+impl Model {
+
+  /// Wraps the network with classes numbered "0", "1", ... by its outputs.
+  ///
+  /// @param network Trained network
+  /// @return New Model instance
+  pub fn new (network: Network) -> Self {
+    let outputs = network.schema().last().cloned().unwrap_or(0);
+
+    Model {
+      network,
+      labels: (0..outputs).map(|class| class.to_string()).collect(),
+      shape: None,
+      preprocessing: Pipeline::new(),
+      manifest: None,
+    }
+  }
+
+  /// Serializes the model into a JSON object.
+  ///
+  /// @return JSON representation of the model
+  pub fn to_json (&self) -> Json {
+    Json::object(vec!(
+      ("format", FORMAT.into()),
+      ("version", VERSION.into()),
+      ("network", self.network.export()),
+      ("labels", self.labels.clone().into()),
+      ("shape", self.shape.map(|shape| shape.to_string()).into()),
+      ("preprocessing", self.preprocessing.to_json()),
+      ("manifest", self.manifest.as_ref().map(Manifest::to_json).into()),
+    ))
+  }
+
+  /// Deserializes the model from a JSON object.
+  ///
+  /// @param json JSON representation of the model
+  /// @return Model instance
+  pub fn from_json (json: &Json) -> Result<Model> {
+    if json.get("format").and_then(Json::as_str) != Some(FORMAT) {
+      return Err(Error::Invalid("Not a model file.".to_string()));
+    }
+
+    match json.get("version").and_then(Json::as_usize) {
+      Some(VERSION) => (),
+      version => return Err(Error::Invalid(
+        format!("Unsupported model version {:?}.", version),
+      )),
+    }
+
+    let network = json.get("network")
+      .ok_or_else(|| Error::Invalid("Model has no network.".to_string()))
+      .and_then(Network::import)?;
+
+    let mut model = Model::new(network);
+
+    if let Some(labels) = json.get("labels") {
+      model.labels = labels.as_array()
+        .and_then(|labels| labels.iter().map(|l| l.as_str().map(str::to_string)).collect())
+        .ok_or_else(|| Error::Invalid("Model labels must be strings.".to_string()))?;
+    }
+
+    if model.labels.len() > model.network.schema().last().cloned().unwrap_or(0) {
+      return Err(Error::Invalid("Model has more labels than outputs.".to_string()));
+    }
+
+    if let Some(preprocessing) = json.get("preprocessing") {
+      model.preprocessing = Pipeline::from_json(preprocessing)?;
+    }
+
+    let inputs = model.network.schema()[0];
+    if model.preprocessing.outputs(inputs) != inputs {
+      return Err(Error::Invalid("Model preprocessing does not match the inputs.".to_string()));
+    }
+
+    model.shape = match json.get("shape") {
+      None | Some(Json::Null) => None,
+      Some(shape) => Some(shape.as_str()
+        .ok_or_else(|| Error::Invalid("Model shape must be a string.".to_string()))?
+        .parse()?),
+    };
+
+    if model.shape.is_some_and(|shape| shape.len() != model.inputs()) {
+      return Err(Error::Invalid("Model shape does not match the inputs.".to_string()));
+    }
+
+    model.manifest = match json.get("manifest") {
+      None | Some(Json::Null) => None,
+      Some(manifest) => Some(Manifest::from_json(manifest)?),
+    };
+
+    Ok(model)
+  }
+
+  /// @param class Index of an output neuron
+  /// @return Name of the class, or the index if the class has no name
+  pub fn label (&self, class: usize) -> String {
+    self.labels.get(class).cloned().unwrap_or_else(|| class.to_string())
+  }
+
+  /// @return Number of raw features the model classifies
+  pub fn inputs (&self) -> usize {
+    self.preprocessing.inputs().unwrap_or_else(|| self.network.schema()[0])
+  }
+
+  /// Classifies raw features, passing them through the preprocessing first.
+  ///
+  /// @param features Raw features of one input
+  /// @return Index of the class
+  pub fn classify (&self, features: &[f64]) -> usize {
+    self.network.classify(&self.prepare(features))
+  }
+
+  /// @param features Raw features of one input
+  /// @return Activation of each output neuron
+  pub fn compute (&self, features: &[f64]) -> Vec<f64> {
+    self.network.compute(&self.prepare(features))
+  }
+
+  /// @param features Raw features of one input
+  /// @return Probability of each class, summing up to 1
+  pub fn probabilities (&self, features: &[f64]) -> Vec<f64> {
+    self.network.probabilities(&self.prepare(features))
+  }
+
+  /// @param features Raw features of one input
+  /// @param k How many classes to return
+  /// @return Tuples in format (class, probability), most likely first
+  pub fn top_k (&self, features: &[f64], k: usize) -> Vec<(usize, f64)> {
+    self.network.top_k(&self.prepare(features), k)
+  }
+
+  /// @param features Raw features of one input
+  /// @param rejection When to abstain
+  /// @return Index of the class, or None for unknown
+  pub fn classify_or_reject (&self, features: &[f64], rejection: &Rejection) -> Option<usize> {
+    self.network.classify_or_reject(&self.prepare(features), rejection)
+  }
+
+  /// Passes raw features through the preprocessing, without copying them
+  /// when there is none.
+  ///
+  /// @param features Raw features of one input
+  /// @return Features as the network expects them
+  fn prepare<'a> (&self, features: &'a [f64]) -> Cow<'a, [f64]> {
+    if self.preprocessing.is_empty() {
+      Cow::Borrowed(features)
+    } else {
+      Cow::Owned(self.preprocessing.apply(features))
+    }
+  }
+
+  /// Shape images are resized to before they are classified. Models trained
+  /// without image shaped data fall back to a square grayscale image, such as
+  /// 8x8 for the 64 optdigits inputs.
+  ///
+  /// @return Image shape of the inputs
+  pub fn input_shape (&self) -> Result<Shape> {
+    if let Some(shape) = self.shape {
+      return Ok(shape);
+    }
+
+    let inputs = self.inputs();
+
+    Shape::square(inputs).ok_or_else(|| Error::Invalid(format!(
+      "Model does not know the image shape of its {} inputs.", inputs,
+    )))
+  }
+
+  /// Writes the model into a file. Fails for a network whose training
+  /// diverged, since JSON has no numbers for infinity and NaN and the file
+  /// could not be loaded back.
+  ///
+  /// @param path Where to write the model
+  pub fn save<P: AsRef<Path>> (&self, path: P) -> Result<()> {
+    let finite = self.network.layers.iter()
+      .flat_map(|layer| layer.neurons.iter())
+      .all(|(bias, weights)| bias.is_finite() && weights.iter().all(|weight| weight.is_finite()));
+
+    if !finite {
+      return Err(Error::Invalid(
+        "Network has weights that are not finite, the training diverged.".to_string(),
+      ));
+    }
+
+    fs::write(path, format!("{}\n", self.to_json()))?;
+
+    Ok(())
+  }
+
+  /// Reads the model from a file.
+  ///
+  /// @param path Where to read the model from
+  /// @return Model instance
+  pub fn load<P: AsRef<Path>> (path: P) -> Result<Model> {
+    Model::from_json(&Json::parse(&fs::read_to_string(path)?)?)
+  }
+
+}
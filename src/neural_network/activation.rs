@@ -2,22 +2,39 @@ use std::f64::consts::E;
 
 pub struct Activation {
 
+  /// Name the activation is known by in exported models and on the command
+  /// line.
+  pub name: &'static str,
+
   /// Desired activation function to map onto all layer outputs.
-  pub function: Box<Fn(f64) -> f64>,
+  pub function: Box<dyn Fn(f64) -> f64>,
 
   /// Derivative of the activation function.
   /// TODO: Change description, it is actually not a derivative.
-  pub derivative: Box<Fn(f64) -> f64>,
+  pub derivative: Box<dyn Fn(f64) -> f64>,
 
 }
 
 impl Activation {
 
+  /// Looks up one of the default activation functions by its name.
+  ///
+  /// @param name Name of the activation, e.g. "sigmoid"
+  /// @return New Activation instance if the name is known
+  pub fn from_name (name: &str) -> Option<Self> {
+    match name {
+      "sigmoid" => Some(Activation::sigmoid()),
+      "leaky_relu" => Some(Activation::leaky_relu()),
+      _ => None,
+    }
+  }
+
   /// Sigmoid natural activation function that ranges the x to (-1;1).
   ///
   /// @return New Activation instance
   pub fn sigmoid () -> Self {
     Activation {
+      name: "sigmoid",
       function: Box::new(|x| 1_f64 / (1_f64 + E.powf(-x))),
       derivative: Box::new(|x| x * (1_f64 - x))
     }
@@ -28,6 +45,7 @@ impl Activation {
   /// @return New Activation instance
   pub fn leaky_relu () -> Self {
     Activation {
+      name: "leaky_relu",
       function: Box::new(|x| (0.01_f64 * x).max(x)),
       derivative: Box::new(|x| {
         if x > 0_f64 {
@@ -9,15 +9,8 @@ pub struct Layer {
   ///
   /// Following vector is therefore in following format:
   /// neurons: Vector<(Neuron) bias, weights>
-  ///
-  /// At the end of the training process, we change the floats.
   pub neurons: Vec<(f64, Vec<f64>)>,
 
-  /// Shadow vector we can store weights updates to. The first number states how
-  /// many updates there has been since last commit. This is used to divide each
-  /// of the weight update before addding it to the neurons weights.
-  updates: (f64, Vec<(f64, Vec<f64>)>),
-
 }
 
 impl Layer {
@@ -27,10 +20,7 @@ impl Layer {
   /// @param neurons Vector of neurons in the layer
   /// @return New layer instance
   pub fn from (neurons: Vec<(f64, Vec<f64>)>) -> Layer {
-    Layer {
-      updates: Layer::new_updates(&neurons),
-      neurons,
-    }
+    Layer { neurons }
   }
 
   /// Calculates the activations for each neuron against inputs. The network
@@ -41,8 +31,8 @@ impl Layer {
   /// @return Activations for this layer
   pub fn activations (
     &self,
-    inputs: &Vec<f64>,
-    activation_function: &Fn(f64) -> f64,
+    inputs: &[f64],
+    activation_function: &dyn Fn(f64) -> f64,
   ) -> Vec<f64> {
     self.neurons.iter()
       .map(|neuron| {
@@ -63,40 +53,27 @@ impl Layer {
       .collect()
   }
 
-  /// Adds new weights to the cached update vector of neurons.
+  /// Adds scaled nudges to the neurons of this layer. This is how optimizers
+  /// write their updates into the model.
   ///
-  /// @param update Vector that mimics neurons with nudges to the weights
-  pub fn add_update (&mut self, update: Vec<(f64, Vec<f64>)>) {
-    // Add weights to the update vector.
-    Layer::add_weights(&mut self.updates.1, &update, 1_f64);
-
-    // Updates the number of commits.
-    self.updates.0 = self.updates.0 + 1_f64;
+  /// @param nudges Vector that mimics neurons with nudges to the weights
+  /// @param scale Number to multiply each nudge by before adding it
+  pub fn nudge (&mut self, nudges: &[(f64, Vec<f64>)], scale: f64) {
+    Layer::add_weights(&mut self.neurons, nudges, scale);
   }
 
-  /// Commits the cached update vector into the main neurons vector.
-  pub fn commit_updates (&mut self) {
-    {
-      let (divider, ref mut neurons) = self.updates;
-
-      // Adds weights to the neurons.
-      Layer::add_weights(&mut self.neurons, neurons, -divider);
-    }
-
-    self.updates = Layer::new_updates(&self.neurons);
-  }
-
-  /// Adds weights to a vector. This is used by commit and add methods.
+  /// Adds weights to a vector. This is used by the nudge method as well as by
+  /// anyone who accumulates nudges in the neurons format.
   ///
   /// @param targets Vector of target neurons
   /// @param source Vector of nudges to weights and biases
-  /// @param divider Number to divide each source weight and bias by
-  fn add_weights (
-    targets: &mut Vec<(f64, Vec<f64>)>,
-    source: &Vec<(f64, Vec<f64>)>,
-    divider: f64,
+  /// @param scale Number to multiply each source weight and bias by
+  pub fn add_weights (
+    targets: &mut [(f64, Vec<f64>)],
+    source: &[(f64, Vec<f64>)],
+    scale: f64,
   ) {
-    if divider == 0_f64 {
+    if scale == 0_f64 {
       return
     }
 
@@ -105,34 +82,37 @@ impl Layer {
       let (bias, ref weights) = source[index];
 
       // Add bias from source to the target.
-      target.0 = target.0 + (bias / divider);
+      target.0 += bias * scale;
 
       for (weight_index, weight) in weights.iter().enumerate() {
         // Add weight from source to the target.
-        let new_weight = target.1[weight_index] + (weight / divider);
-
-        // Change the weight to the summed one.
-        target.1[weight_index] = new_weight;
+        target.1[weight_index] += weight * scale;
       }
     }
   }
 
-  /// Generates new vector of neurons with weights and biases 0 and a counter
-  /// starting at 0 for the update vector.
+  /// Euclidean norm of all biases and weights in the neurons format. Used to
+  /// monitor both the layer weights and the gradients.
   ///
-  /// @param neurons Scheme of neurons
-  /// @return Counter and copy of neurons
-  fn new_updates (neurons: &Vec<(f64, Vec<f64>)>) -> (f64, Vec<(f64, Vec<f64>)>) {
-    (
-      0_f64,
-      // Sets bias and all weights to 0 for each neuron.
-      (0..neurons.len())
-        .map(|neuron_index| {
-          let (_, ref weights) = neurons[neuron_index];
+  /// @param neurons Vector of neurons or nudges
+  /// @return Square root of the sum of squares
+  pub fn norm (neurons: &[(f64, Vec<f64>)]) -> f64 {
+    neurons.iter()
+      .map(|(bias, weights)| {
+        bias * bias + weights.iter().map(|weight| weight * weight).sum::<f64>()
+      })
+      .sum::<f64>()
+      .sqrt()
+  }
 
-          (0_f64, (0..weights.len()).map(|_| 0_f64).collect())
-        })
-        .collect()
-    )
+  /// Generates new vector of neurons with weights and biases 0 in the shape of
+  /// this layer.
+  ///
+  /// @return Copy of neurons with all values set to 0
+  pub fn zeros (&self) -> Vec<(f64, Vec<f64>)> {
+    // Sets bias and all weights to 0 for each neuron.
+    self.neurons.iter()
+      .map(|(_, weights)| (0_f64, vec!(0_f64; weights.len())))
+      .collect()
   }
 }
@@ -1,21 +1,23 @@
 
-/**
- * Exports the network struct.
+/*
+ * Exports the network struct, the back prop gradients and the reject option
+ * of the classification.
  */
 
 pub mod network;
-mod network_train;
-mod network_classify;
+pub mod network_train;
+pub mod network_classify;
+mod network_export;
 
-/**
+/*
  * Exports type of activation function and its derivative plus provides some
  * default ones.
  */
 
 pub mod activation;
 
-/**
+/*
  * Exports the layer struct
  */
 
-mod layer;
+pub mod layer;
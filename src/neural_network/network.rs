@@ -1,4 +1,5 @@
 use rand::prelude::*;
+use rand::rngs::StdRng;
 use super::layer::Layer;
 use super::activation::Activation;
 
@@ -13,19 +14,6 @@ pub struct Network {
   /// Activation function and its derivative.
   pub activation: Activation,
 
-  /// Learning rate of the network.
-  pub learning_rate: f64,
-
-  ///
-  pub batch_size: usize,
-
-  ///
-  pub step_size: f64,
-
-  pub min_lr: f64,
-
-  pub max_lr: f64,
-
 }
 
 impl Network {
@@ -41,13 +29,8 @@ impl Network {
   ) -> Self {
     Network {
       activation,
-      batch_size: 1,
-      learning_rate: 1_f64,
-      min_lr: 1_f64,
-      max_lr: 1_f64,
-      step_size: 1_f64,
       layers: layers.into_iter()
-        .map(|neurons| Layer::from(neurons))
+        .map(Layer::from)
         .collect()
     }
   }
@@ -61,11 +44,39 @@ impl Network {
   /// @return New Network instance
   pub fn new (
     activation: Activation,
-    schema: Vec<u8>,
+    schema: Vec<usize>,
   ) -> Self {
-    // Random number generator.
-    let mut rng = rand::thread_rng();
+    Network::with_rng(activation, schema, &mut rand::thread_rng())
+  }
 
+  /// Generates new network from given schema with weights drawn from a
+  /// generator seeded by given number, so that the same seed always yields
+  /// the same network.
+  ///
+  /// @param activation Pointer to the activation function and its derivative
+  /// @param schema Vector representing layers and their neurons
+  /// @param seed Seed of the random number generator
+  /// @return New Network instance
+  pub fn seeded (
+    activation: Activation,
+    schema: Vec<usize>,
+    seed: u64,
+  ) -> Self {
+    Network::with_rng(activation, schema, &mut StdRng::seed_from_u64(seed))
+  }
+
+  /// Generates new network from given schema with weights drawn from given
+  /// random number generator.
+  ///
+  /// @param activation Pointer to the activation function and its derivative
+  /// @param schema Vector representing layers and their neurons
+  /// @param rng Random number generator
+  /// @return New Network instance
+  pub fn with_rng<R: Rng> (
+    activation: Activation,
+    schema: Vec<usize>,
+    rng: &mut R,
+  ) -> Self {
     // We prepare an empty layers shell.
     let mut layers: Vec<Vec<(f64, Vec<f64>)>> = Vec::new();
 
@@ -95,4 +106,19 @@ impl Network {
     Network::from(activation, layers)
   }
 
+  /// Reconstructs the schema the network was built from: number of inputs
+  /// followed by number of neurons in each layer.
+  ///
+  /// @return Vector representing layers and their neurons
+  pub fn schema (&self) -> Vec<usize> {
+    let inputs = self.layers.first()
+      .and_then(|layer| layer.neurons.first())
+      .map_or(0, |(_, weights)| weights.len());
+
+    let mut schema = vec!(inputs);
+    schema.extend(self.layers.iter().map(|layer| layer.neurons.len()));
+
+    schema
+  }
+
 }
@@ -1,23 +1,103 @@
+use std::fmt;
 use std::ops::Deref;
+use std::str::FromStr;
+use error::{Error, Result};
 use super::network::Network;
 
+/// What the confidence of a prediction is measured by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Criterion {
+
+  /// Probability of the most likely class.
+  Probability,
+
+  /// Difference between the probabilities of the two most likely classes,
+  /// low when the network hesitates between two classes.
+  Margin,
+
+}
+
+impl Criterion {
+
+  /// @param probabilities Probability of each class
+  /// @return Confidence of the most likely class
+  pub fn confidence (&self, probabilities: &[f64]) -> f64 {
+    let best = top_k(probabilities, 2);
+    let first = best.first().map_or(0_f64, |&(_, probability)| probability);
+
+    match *self {
+      Criterion::Probability => first,
+      Criterion::Margin => first - best.get(1).map_or(0_f64, |&(_, probability)| probability),
+    }
+  }
+
+}
+
+impl FromStr for Criterion {
+  type Err = Error;
+
+  fn from_str (text: &str) -> Result<Criterion> {
+    match text {
+      "probability" => Ok(Criterion::Probability),
+      "margin" => Ok(Criterion::Margin),
+      _ => Err(Error::Invalid(format!(
+        "Unknown confidence '{}', use probability or margin.", text,
+      ))),
+    }
+  }
+}
+
+impl fmt::Display for Criterion {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Criterion::Probability => write!(f, "probability"),
+      Criterion::Margin => write!(f, "margin"),
+    }
+  }
+}
+
+/// Abstains from predictions whose confidence is below a threshold, so that
+/// ambiguous inputs can be handed to a human instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rejection {
+
+  /// What the confidence is measured by.
+  pub criterion: Criterion,
+
+  /// Lowest confidence that is still accepted.
+  pub threshold: f64,
+
+}
+
+impl Rejection {
+
+  /// @param probabilities Probability of each class
+  /// @return Most likely class, unless it is not confident enough
+  pub fn decide (&self, probabilities: &[f64]) -> Option<usize> {
+    if self.criterion.confidence(probabilities) < self.threshold {
+      return None;
+    }
+
+    top_k(probabilities, 1).first().map(|&(class, _)| class)
+  }
+
+}
+
 impl Network {
 
   /// Classifies input (e.g. image) into one of the output layer categories.
   ///
   /// @param inputs Vector of same length as input layer
   /// @return Position of output neuron that fired the most
-  pub fn classify (&self, inputs: Vec<f64>) -> u8 {
-    // Serves as an iterator.
-    let mut current_neuron: u8 = 0;
+  pub fn classify (&self, inputs: &[f64]) -> usize {
     // The neuron that fired the most.
-    let mut strongest_neuron: Option<u8> = Some(current_neuron);
+    let mut strongest_neuron: Option<usize> = Some(0);
     // The intensity that the neuron fired with.
     let mut intensity: Option<f64> = None;
 
     // For each output neuron activation we compare the intensity.
     // This is basically a max function.
-    for &probability in self.compute(inputs).iter() {
+    for (current_neuron, &probability) in self.compute(inputs).iter().enumerate() {
       match intensity {
         None => intensity = Some(probability),
         Some(x) => if x < probability {
@@ -25,8 +105,6 @@ impl Network {
           strongest_neuron = Some(current_neuron);
         }
       }
-
-      current_neuron += 1;
     }
 
     // We use option here only to make the first strongest_neuron assigment
@@ -34,18 +112,120 @@ impl Network {
     strongest_neuron.unwrap()
   }
 
+  /// Computes how likely each class is, see probabilities.
+  ///
+  /// @param inputs Vector of same length as input layer
+  /// @return Probability of each output neuron, summing up to 1
+  pub fn probabilities (&self, inputs: &[f64]) -> Vec<f64> {
+    probabilities(&self.compute(inputs))
+  }
+
+  /// Finds the classes the network considers most likely.
+  ///
+  /// @param inputs Vector of same length as input layer
+  /// @param k How many classes to return
+  /// @return Tuples in format (class, probability), most likely first
+  pub fn top_k (&self, inputs: &[f64], k: usize) -> Vec<(usize, f64)> {
+    top_k(&self.probabilities(inputs), k)
+  }
+
+  /// Classifies input unless the network is not confident enough.
+  ///
+  /// @param inputs Vector of same length as input layer
+  /// @param rejection When to abstain
+  /// @return Most likely class, or None for unknown
+  pub fn classify_or_reject (&self, inputs: &[f64], rejection: &Rejection) -> Option<usize> {
+    rejection.decide(&self.probabilities(inputs))
+  }
+
   /// Computes the activation of the network over given inputs.
   ///
   /// @param inputs Vector of same length as input layer
   /// @return Activation intensity of each neuron in output layer
-  fn compute (&self, inputs: Vec<f64>) -> Vec<f64> {
+  pub fn compute (&self, inputs: &[f64]) -> Vec<f64> {
     // We deference the pointer to the activation function.
     let activation_fn = self.activation.function.deref();
 
+    // The first layer reads the borrowed inputs, so that they don't have to
+    // be copied into the signal.
+    let (first, rest) = self.layers.split_first()
+      .expect("Network has at least one layer.");
+
     // The prettiest line in the entire algorithm. We start with given inputs
     // and propagate the signal from layer to layer.
-    self.layers.iter()
-      .fold(inputs, |signal, layer| layer.activations(&signal, activation_fn))
+    rest.iter()
+      .fold(
+        first.activations(inputs, activation_fn),
+        |signal, layer| layer.activations(&signal, activation_fn),
+      )
+  }
+
+}
+
+/// Normalises output activations into class probabilities. The network is
+/// trained towards one hot targets rather than through a softmax, so the
+/// activations are taken as scores of the classes: negative ones count as 0
+/// and the rest are divided by their sum. Outputs that are all 0 make every
+/// class equally likely.
+///
+/// @param outputs Activation of each output neuron
+/// @return Probability of each output neuron, summing up to 1
+pub fn probabilities (outputs: &[f64]) -> Vec<f64> {
+  let total: f64 = outputs.iter().map(|output| output.max(0_f64)).sum();
+
+  if total > 0_f64 && total.is_finite() {
+    outputs.iter().map(|output| output.max(0_f64) / total).collect()
+  } else {
+    vec!(1_f64 / outputs.len().max(1) as f64; outputs.len())
+  }
+}
+
+/// Sorts classes by their score, ties keep the lower class first.
+///
+/// @param scores Score of each class, e.g. its probability
+/// @param k How many classes to return
+/// @return Tuples in format (class, score), highest score first
+pub fn top_k (scores: &[f64], k: usize) -> Vec<(usize, f64)> {
+  let mut ranked: Vec<(usize, f64)> = scores.iter().cloned().enumerate().collect();
+  ranked.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+  ranked.truncate(k);
+
+  ranked
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{probabilities, top_k, Criterion, Rejection};
+
+  #[test]
+  fn normalises_outputs_and_ranks_classes() {
+    let probs = probabilities(&[0.2, -0.5, 0.6]);
+    assert!((probs.iter().sum::<f64>() - 1_f64).abs() < 1e-12);
+    assert_eq!(probs[1], 0_f64);
+    assert!((probs[2] - 0.75).abs() < 1e-12);
+
+    assert_eq!(probabilities(&[0_f64, -1_f64, 0_f64, 0_f64]), vec!(0.25; 4));
+
+    // Ties keep the lower class first.
+    let ranked = top_k(&[0.3, 0.1, 0.3, 0.3], 3);
+    assert_eq!(ranked, vec!((0, 0.3), (2, 0.3), (3, 0.3)));
+    assert_eq!(top_k(&[0.2, 0.8], 5), vec!((1, 0.8), (0, 0.2)));
   }
 
+  #[test]
+  fn rejects_unconfident_predictions() {
+    let confident = [0.1, 0.7, 0.2];
+    let torn = [0.45, 0.1, 0.45];
+
+    assert!((Criterion::Margin.confidence(&confident) - 0.5).abs() < 1e-12);
+
+    let by_probability = Rejection { criterion: Criterion::Probability, threshold: 0.5 };
+    assert_eq!(by_probability.decide(&confident), Some(1));
+    assert_eq!(by_probability.decide(&torn), None);
+
+    // Ties are as unconfident as it gets by margin.
+    let by_margin = Rejection { criterion: Criterion::Margin, threshold: 0.01 };
+    assert_eq!(by_margin.decide(&torn), None);
+    assert_eq!(by_margin.decide(&[0.46, 0.09, 0.45]), Some(0));
+  }
 }
@@ -1,2 +1,102 @@
+use json::Json;
+use error::{Error, Result};
+use super::activation::Activation;
+use super::network::Network;
 
-// TODO: Export the layer schema and weights to file.
+impl Network {
+
+  /// Exports the activation name and the weights of each layer. Layers are
+  /// written in the same format they are stored in:
+  /// Vector<(Layer): Vector<(Neuron) bias, weights>>
+  ///
+  /// @return JSON representation of the network
+  pub fn export (&self) -> Json {
+    let layers: Vec<Json> = self.layers.iter()
+      .map(|layer| {
+        Json::Array(
+          layer.neurons.iter()
+            .map(|(bias, weights)| {
+              Json::Array(vec!(Json::from(*bias), Json::from(weights.clone())))
+            })
+            .collect()
+        )
+      })
+      .collect();
+
+    Json::object(vec!(
+      ("activation", self.activation.name.into()),
+      ("layers", Json::Array(layers)),
+    ))
+  }
+
+  /// Builds the network back from its exported form. Checks that each neuron
+  /// has as many weights as there are neurons in the previous layer.
+  ///
+  /// @param json JSON representation of the network
+  /// @return Imported Network instance
+  pub fn import (json: &Json) -> Result<Network> {
+    let name = json.get("activation")
+      .and_then(Json::as_str)
+      .ok_or_else(|| invalid("missing activation"))?;
+
+    let activation = Activation::from_name(name)
+      .ok_or_else(|| invalid(&format!("unknown activation '{}'", name)))?;
+
+    let layers = json.get("layers")
+      .and_then(Json::as_array)
+      .ok_or_else(|| invalid("missing layers"))?
+      .iter()
+      .map(import_layer)
+      .collect::<Result<Vec<Vec<(f64, Vec<f64>)>>>>()?;
+
+    if layers.is_empty() || layers.iter().any(|neurons| neurons.is_empty()) {
+      return Err(invalid("every layer must have at least one neuron"));
+    }
+
+    for index in 1..layers.len() {
+      let inputs = layers[index - 1].len();
+
+      if layers[index].iter().any(|(_, weights)| weights.len() != inputs) {
+        return Err(invalid(&format!(
+          "neurons in layer {} must have {} weights", index + 1, inputs,
+        )));
+      }
+    }
+
+    Ok(Network::from(activation, layers))
+  }
+
+}
+
+/// Reads one layer in format Vector<(Neuron) bias, weights>.
+///
+/// @param json Exported layer
+/// @return Neurons of the layer
+fn import_layer (json: &Json) -> Result<Vec<(f64, Vec<f64>)>> {
+  json.as_array()
+    .ok_or_else(|| invalid("layer must be an array of neurons"))?
+    .iter()
+    .map(|neuron| {
+      let neuron = neuron.as_array()
+        .filter(|neuron| neuron.len() == 2)
+        .ok_or_else(|| invalid("neuron must be a pair of bias and weights"))?;
+
+      let bias = neuron[0].as_f64()
+        .ok_or_else(|| invalid("bias must be a number"))?;
+
+      let weights = neuron[1].as_array()
+        .ok_or_else(|| invalid("weights must be an array"))?
+        .iter()
+        .map(|weight| weight.as_f64().ok_or_else(|| invalid("weight must be a number")))
+        .collect::<Result<Vec<f64>>>()?;
+
+      Ok((bias, weights))
+    })
+    .collect()
+}
+
+/// @param message What is wrong with the exported network
+/// @return Error describing an invalid network
+fn invalid (message: &str) -> Error {
+  Error::Invalid(format!("Invalid network: {}", message))
+}
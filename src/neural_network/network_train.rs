@@ -2,72 +2,134 @@ use std::ops::Deref;
 use super::layer::Layer;
 use super::network::Network;
 
+/// Nudges to every bias and weight of the network in the same format as the
+/// layers are stored: Vector<(Layer): Vector<(Neuron) bias, weights>>
+pub type Gradients = Vec<Vec<(f64, Vec<f64>)>>;
+
 impl Network {
 
-  /// Trains the network with back prop algorithm.
+  /// Runs the back prop algorithm for a single sample. The network itself is
+  /// not changed, it is up to the caller (usually the trainer and its
+  /// optimizer) to decide what to do with the gradients.
   ///
-  /// @param network Network instance we want to train
-  /// @param training_data Training data
-  pub fn train (&mut self, training_data: &Vec<(u8, Vec<f64>)>, epoch: usize) {
-    self.learning_rate = self.calculate_learning_rate(epoch as f64);
+  /// @param target The expected result for given inputs
+  /// @param inputs Vector of same length as input layer
+  /// @return Tuple in format (output_activations, gradients)
+  pub fn backpropagate (
+    &self,
+    target: usize,
+    inputs: &[f64],
+  ) -> (Vec<f64>, Gradients) {
+    // One hot vector: the expected output neuron should fire fully, the
+    // others not at all.
+    let mut targets = vec!(0_f64; self.layers.last().map_or(0, |layer| layer.neurons.len()));
+    if let Some(expected) = targets.get_mut(target) {
+      *expected = 1_f64;
+    }
 
-    for (i, digit) in training_data.iter().enumerate() {
-      let (target, inputs) = digit;
+    self.backpropagate_soft(&targets, inputs)
+  }
 
-      // Converts target into usize so that it can be compared with enumerate.
-      let target = usize::from(*target);
+  /// Runs the back prop algorithm for a single sample whose label is spread
+  /// over several classes, such as a smoothed label or a blend of two
+  /// samples.
+  ///
+  /// @param targets Expected activation of each output neuron
+  /// @param inputs Vector of same length as input layer
+  /// @return Tuple in format (output_activations, gradients)
+  pub fn backpropagate_soft (
+    &self,
+    targets: &[f64],
+    inputs: &[f64],
+  ) -> (Vec<f64>, Gradients) {
+    self.backpropagate_weighted(targets, 1_f64, inputs)
+  }
 
-      // Gets the activations for each layer.
-      let activations: Vec<Vec<f64>> = self.calculate_activations(inputs);
+  /// Runs the back prop algorithm for a single sample whose loss counts
+  /// more or less than others, e.g. a sample of a rare class.
+  ///
+  /// @param targets Expected activation of each output neuron
+  /// @param weight Factor of the loss of the sample
+  /// @param inputs Vector of same length as input layer
+  /// @return Tuple in format (output_activations, gradients)
+  pub fn backpropagate_weighted (
+    &self,
+    targets: &[f64],
+    weight: f64,
+    inputs: &[f64],
+  ) -> (Vec<f64>, Gradients) {
+    // Gets the activations for each layer.
+    let activations: Vec<Vec<f64>> = self.calculate_activations(inputs);
+
+    let layers_count: usize = self.layers.len();
+
+    // Partial weight change without the previous activations.
+    let output_partial_deltas: Vec<f64> = self.calculate_deltas(
+      targets,
+      weight,
+      &activations[layers_count],
+    );
 
-      let layers_count: usize = self.layers.len();
+    let mut gradients: Gradients = Vec::with_capacity(layers_count);
 
-      // Partial weight change without the learning rate and previous
-      // activations.
-      let output_partial_deltas: Vec<f64> = self.calculate_deltas(
-        target,
-        &activations[layers_count],
-      );
+    // Propagates the error deltas from one layer to another.
+    (0..layers_count).rev().fold(
+      output_partial_deltas,
+      |deltas, layer| {
+        let mut new_partial_deltas: Vec<f64> = Vec::new();
 
-      let mut layers_iterator: Vec<usize> = (0..layers_count).collect();
-      layers_iterator.reverse();
+        // Calculates nudges for each weight and bias in this layer.
+        gradients.push(self.calculate_nudges(
+          layer,
+          deltas,
+          &activations,
+          &mut new_partial_deltas,
+        ));
 
-      // Propagates the error deltas from one layer to another.
-      layers_iterator.iter().fold(
-        output_partial_deltas,
-        |deltas, layer| self.update_layer_weights(*layer, deltas, &activations),
-      );
+        new_partial_deltas
+      },
+    );
 
-      let batch = i % self.batch_size;
+    // Nudges were collected from the output layer backwards.
+    gradients.reverse();
 
-      if batch % self.batch_size == 0 {
-        for layer in self.layers.iter_mut() {
-          layer.commit_updates();
-        }
-      }
-    }
+    (activations[layers_count].clone(), gradients)
+  }
 
-    // Commits all updates into each layer.
-    for layer in self.layers.iter_mut() {
-      layer.commit_updates();
+  /// Adds gradients multiplied by given scale to the weights and biases of
+  /// each layer.
+  ///
+  /// @param gradients Nudges in the same format as the layers
+  /// @param scale Number to multiply each nudge by, negative to descend
+  pub fn apply_gradients (&mut self, gradients: &[Vec<(f64, Vec<f64>)>], scale: f64) {
+    for (layer, nudges) in self.layers.iter_mut().zip(gradients.iter()) {
+      layer.nudge(nudges, scale);
     }
   }
 
+  /// Generates gradients with every nudge set to 0 in the shape of the network.
+  /// Useful as an accumulator.
+  ///
+  /// @return Zeroed gradients
+  pub fn zero_gradients (&self) -> Gradients {
+    self.layers.iter().map(|layer| layer.zeros()).collect()
+  }
+
   /// Computes the activation of the network over given inputs and stores them
   /// along the way in a vector. In contrast with network_classify compute fn,
   /// we have activations from all layers, not only the last one.
   ///
   /// @param inputs Vector of same length as input layer
   /// @return Activation intensity of each neuron in each layer
-  fn calculate_activations (&self, inputs: &Vec<f64>) -> Vec<Vec<f64>> {
+  fn calculate_activations (&self, inputs: &[f64]) -> Vec<Vec<f64>> {
     // We deference the pointer to the activation function.
     let activation_fn = self.activation.function.deref();
 
     // We return vector of vectors holding the activation values.
-    self.layers.iter().fold(vec!(inputs.clone()), |mut activations, layer| {
+    self.layers.iter().fold(vec!(inputs.to_vec()), |mut activations, layer| {
       // Computing an activation vector of a layer.
       let output = layer.activations(
-        &activations.last().unwrap(),
+        activations.last().unwrap(),
         activation_fn,
       );
 
@@ -78,36 +140,6 @@ impl Network {
     })
   }
 
-  /// Calculates output layer weights. This process is slightly different for
-  /// this layer, therefore it has its own logic separated from the hidden layers.
-  ///
-  /// @param layer_index Index of the output layer is layers.length - 1
-  /// @param target The expected result for given inputs
-  /// @param activations Activation values of each layer
-  /// @return Vector of changes to each neurons bias and weights
-  fn update_layer_weights (
-    &mut self,
-    layer_index: usize,
-    partial_deltas: Vec<f64>,
-    activations: &Vec<Vec<f64>>,
-  ) -> Vec<f64> {
-    // Stores delta errors for neurons in this layer.
-    let mut new_partial_deltas: Vec<f64> = Vec::new();
-
-    // Calculates nudges for each weight and bias in this layer.
-    let nudges = self.calculate_nudges(
-      layer_index,
-      partial_deltas,
-      activations,
-      &mut new_partial_deltas,
-    );
-
-    // Caches the nudges.
-    self.layers[layer_index].add_update(nudges);
-
-    new_partial_deltas
-  }
-
   /// Calculates the nudges to bias and neurons. This is the core of the whole
   /// algorithm. It also pushes the calculated new partial deltas to a
   /// collector that sends it to the next layer.
@@ -117,10 +149,10 @@ impl Network {
   /// @param activations All network activations from the feed forward process
   /// @param new_partial_deltas Delta error collector
   fn calculate_nudges (
-    &mut self,
+    &self,
     layer_index: usize,
     partial_deltas: Vec<f64>,
-    activations: &Vec<Vec<f64>>,
+    activations: &[Vec<f64>],
     new_partial_deltas: &mut Vec<f64>,
   ) -> Vec<(f64, Vec<f64>)> {
     let layer: &Layer = &self.layers[layer_index];
@@ -160,12 +192,12 @@ impl Network {
         // input layer. Therefore activations[layer_index] gives us activations
         // from the next layer (in direction to the output).
         let weight_deltas = activations[layer_index].iter()
-          .map(|activation| activation * new_partial_delta * self.learning_rate)
+          .map(|activation| activation * new_partial_delta)
           .collect();
 
         // Export changes to the neuron in the same format as each neuron is
         // defined: (bias, weights).
-        (new_partial_delta * self.learning_rate, weight_deltas)
+        (new_partial_delta, weight_deltas)
       })
       .collect()
   }
@@ -173,35 +205,23 @@ impl Network {
   /// Calculates the the partial weight change for each output neuron. This
   /// result is to be mapped over the outputs in previous hidden layer.
   ///
-  /// @param target The expected outcome
+  /// @param targets The expected activation of each output neuron
+  /// @param weight Factor of the loss of the sample
   /// @param outputs Outputs from the network
   /// @return Vector of partial delta for each output neuron
-  fn calculate_deltas (&self, target: usize, outputs: &Vec<f64>) -> Vec<f64> {
+  fn calculate_deltas (&self, targets: &[f64], weight: f64, outputs: &[f64]) -> Vec<f64> {
     let derivative = self.activation.derivative.deref();
 
     outputs.iter()
-      .enumerate()
-      .map(|(neuron, output)| {
-        // If this was the expected answer, compute error to 1, otherwise to 0.
-        let total_to_output = if target == neuron {
-          -(1_f64 - output)
-        } else {
-          -(0_f64 - output)
-        };
+      .zip(targets.iter())
+      .map(|(output, target)| {
+        // Error of the output to the expected activation.
+        let total_to_output = -(target - output);
 
-        derivative(*output) * total_to_output
+        // Weighted loss scales the derivative of the loss alike.
+        weight * derivative(*output) * total_to_output
       })
       .collect()
   }
 
-  pub fn calculate_learning_rate (&mut self, epoch: f64) -> f64 {
-    let step = 1_f64 + epoch / (2_f64 * self.step_size as f64);
-    let cycle = step.floor();
-    let progress = (0.5_f64 - (step - cycle)).abs();
-
-    self.max_lr - (
-      2_f64 * (self.max_lr - self.min_lr) * (0.5_f64 - progress).abs()
-    )
-  }
-
 }
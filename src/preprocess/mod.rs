@@ -0,0 +1,284 @@
+/*
+ * Exports min-max scaling and standardisation of features.
+ */
+
+pub mod normalize;
+
+/*
+ * Exports principal component analysis.
+ */
+
+pub mod pca;
+
+use std::fmt;
+use dataset::Dataset;
+use dataset::in_memory::InMemory;
+use error::{Error, Result};
+use json::Json;
+use reader::sample::Format;
+use trainer::Transform;
+use self::normalize::{Method, Normalizer, Scope};
+use self::pca::{Components, Pca};
+
+/// Preprocessing step as configured, before it has seen any data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+
+  /// Normalises the features with statistics of the training data.
+  Normalize { method: Method, scope: Scope },
+
+  /// Projects the features onto their principal components.
+  Pca { components: Components },
+
+}
+
+impl Step {
+
+  /// @param dataset Training data, already passed through earlier stages
+  /// @return Stage fitted on the data
+  pub fn fit (&self, dataset: &dyn Dataset) -> Result<Stage> {
+    Ok(match *self {
+      Step::Normalize { method, scope } => Stage::Normalize(Normalizer::fit(dataset, method, scope)),
+      Step::Pca { components } => Stage::Pca(Pca::fit(dataset, components)?),
+    })
+  }
+
+  /// @return Whether the step can change the number of features
+  pub fn changes_features (&self) -> bool {
+    matches!(*self, Step::Pca { .. })
+  }
+
+  /// @return JSON representation of the step, as written in config files
+  pub fn to_json (&self) -> Json {
+    match *self {
+      Step::Normalize { method, scope } => Json::object(vec!(
+        ("type", "normalize".into()),
+        ("method", method.to_string().into()),
+        ("scope", scope.to_string().into()),
+      )),
+      Step::Pca { components: Components::Count(count) } => Json::object(vec!(
+        ("type", "pca".into()),
+        ("components", count.into()),
+      )),
+      Step::Pca { components: Components::Variance(variance) } => Json::object(vec!(
+        ("type", "pca".into()),
+        ("variance", variance.into()),
+      )),
+    }
+  }
+
+}
+
+/// Preprocessing step fitted on training data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stage {
+  Normalize(Normalizer),
+  Pca(Pca),
+}
+
+impl Stage {
+
+  /// @return Number of features the stage expects
+  pub fn inputs (&self) -> usize {
+    match *self {
+      Stage::Normalize(ref normalizer) => normalizer.features(),
+      Stage::Pca(ref pca) => pca.features(),
+    }
+  }
+
+  /// @return Number of features the stage produces
+  pub fn outputs (&self) -> usize {
+    match *self {
+      Stage::Normalize(ref normalizer) => normalizer.features(),
+      Stage::Pca(ref pca) => pca.components(),
+    }
+  }
+
+  /// @param features Features of one input
+  /// @return Transformed features
+  pub fn apply (&self, mut features: Vec<f64>) -> Vec<f64> {
+    match *self {
+      Stage::Normalize(ref normalizer) => {
+        normalizer.apply(&mut features);
+        features
+      },
+      Stage::Pca(ref pca) => pca.apply(&features),
+    }
+  }
+
+  /// @return JSON representation of the stage
+  pub fn to_json (&self) -> Json {
+    match *self {
+      Stage::Normalize(ref normalizer) => normalizer.to_json(),
+      Stage::Pca(ref pca) => pca.to_json(),
+    }
+  }
+
+  /// @param json JSON representation of the stage
+  /// @return Stage instance
+  pub fn from_json (json: &Json) -> Result<Stage> {
+    match json.get("type").and_then(Json::as_str) {
+      Some("normalize") => Normalizer::from_json(json).map(Stage::Normalize),
+      Some("pca") => Pca::from_json(json).map(Stage::Pca),
+      kind => Err(Error::Invalid(format!("Unknown preprocessing stage {:?}.", kind))),
+    }
+  }
+
+}
+
+impl fmt::Display for Stage {
+
+  /// Describes the stage in a few words, e.g. "standard normalisation per
+  /// feature".
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Stage::Normalize(ref normalizer) => write!(
+        f, "{} normalisation per {} of {} features",
+        normalizer.method, normalizer.scope, normalizer.features(),
+      ),
+      Stage::Pca(ref pca) => write!(
+        f, "pca of {} features to {} components explaining {:.1}% of the variance",
+        pca.features(), pca.components(), pca.explained_variance() * 100_f64,
+      ),
+    }
+  }
+
+}
+
+/// Stages applied one after another to every input, turning raw features
+/// into the inputs of the network. The pipeline is fitted on the training
+/// data only and saved with the model, so that validation data and inputs
+/// classified later are transformed exactly alike.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Pipeline {
+  stages: Vec<Stage>,
+}
+
+impl Pipeline {
+
+  /// @return Pipeline that passes the features through unchanged
+  pub fn new () -> Self {
+    Pipeline { stages: Vec::new() }
+  }
+
+  /// Fits the steps in order, each on the output of the stages before it.
+  ///
+  /// @param steps Configured preprocessing steps
+  /// @param dataset Training data
+  /// @return Fitted Pipeline instance
+  pub fn fit (steps: &[Step], dataset: &dyn Dataset) -> Result<Self> {
+    let mut pipeline = Pipeline::new();
+    let mut transformed: Option<InMemory> = None;
+
+    for (index, step) in steps.iter().enumerate() {
+      let stage = match transformed {
+        Some(ref data) => step.fit(data)?,
+        None => step.fit(dataset)?,
+      };
+      pipeline.stages.push(stage);
+
+      // The last stage is fitted on data the pipeline does not need to keep.
+      if index + 1 < steps.len() {
+        let last = pipeline.stages.last().unwrap();
+        transformed = Some(match transformed {
+          Some(data) => Pipeline::apply_stage(last, &data),
+          None => Pipeline::apply_stage(last, dataset),
+        });
+      }
+    }
+
+    Ok(pipeline)
+  }
+
+  /// @return Stages in the order they are applied
+  pub fn stages (&self) -> &[Stage] {
+    &self.stages
+  }
+
+  /// @return Whether the pipeline passes the features through unchanged
+  pub fn is_empty (&self) -> bool {
+    self.stages.is_empty()
+  }
+
+  /// @return Number of raw features the pipeline expects, if it has stages
+  pub fn inputs (&self) -> Option<usize> {
+    self.stages.first().map(Stage::inputs)
+  }
+
+  /// @param inputs Number of raw features
+  /// @return Number of features the pipeline produces from them
+  pub fn outputs (&self, inputs: usize) -> usize {
+    self.stages.last().map_or(inputs, Stage::outputs)
+  }
+
+  /// @param features Raw features of one input
+  /// @return Features as the network expects them
+  pub fn apply (&self, features: &[f64]) -> Vec<f64> {
+    self.stages.iter().fold(features.to_vec(), |features, stage| stage.apply(features))
+  }
+
+  /// Transforms every sample of a data set. Without any stages the data set
+  /// is returned as it is, without copying.
+  ///
+  /// @param dataset Data set with raw features
+  /// @return Data set with the features as the network expects them
+  pub fn transform (&self, dataset: InMemory) -> InMemory {
+    self.stages.iter().fold(dataset, |data, stage| Pipeline::apply_stage(stage, &data))
+  }
+
+  /// @param stage Stage to apply
+  /// @param dataset Data set to transform
+  /// @return Transformed copy of the data set
+  fn apply_stage (stage: &Stage, dataset: &dyn Dataset) -> InMemory {
+    let format = Format {
+      features: stage.outputs(),
+      labels: dataset.labels().to_vec(),
+      // Pixels stay pixels only as long as there are as many of them.
+      shape: dataset.shape().filter(|shape| shape.len() == stage.outputs()),
+    };
+
+    let mut transformed = InMemory::with_format(&format);
+    for (class, features) in dataset.iter() {
+      transformed.push(class, &stage.apply(features.to_vec()));
+    }
+
+    transformed
+  }
+
+  /// @return JSON array of the stages
+  pub fn to_json (&self) -> Json {
+    Json::Array(self.stages.iter().map(Stage::to_json).collect())
+  }
+
+  /// Reads the stages and checks that each one takes what the previous one
+  /// produces.
+  ///
+  /// @param json JSON array of the stages
+  /// @return Pipeline instance
+  pub fn from_json (json: &Json) -> Result<Pipeline> {
+    let stages = json.as_array()
+      .ok_or_else(|| Error::Invalid("Preprocessing must be an array of stages.".to_string()))?
+      .iter()
+      .map(Stage::from_json)
+      .collect::<Result<Vec<Stage>>>()?;
+
+    for pair in stages.windows(2) {
+      if pair[0].outputs() != pair[1].inputs() {
+        return Err(Error::Invalid(format!(
+          "Preprocessing stage produces {} features but the next one expects {}.",
+          pair[0].outputs(), pair[1].inputs(),
+        )));
+      }
+    }
+
+    Ok(Pipeline { stages })
+  }
+
+}
+
+/// Lets the trainer preprocess samples that were augmented on the fly.
+impl Transform for Pipeline {
+  fn apply (&mut self, features: &[f64]) -> Vec<f64> {
+    Pipeline::apply(self, features)
+  }
+}
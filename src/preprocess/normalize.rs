@@ -0,0 +1,257 @@
+use std::fmt;
+use std::str::FromStr;
+use dataset::Dataset;
+use error::{Error, Result};
+use json::Json;
+
+/// How features are brought into a common range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+
+  /// Shifts and scales the values into the range 0 to 1.
+  MinMax,
+
+  /// Shifts and scales the values to zero mean and unit variance.
+  Standard,
+
+}
+
+impl FromStr for Method {
+  type Err = Error;
+
+  fn from_str (text: &str) -> Result<Method> {
+    match text {
+      "minmax" => Ok(Method::MinMax),
+      "standard" => Ok(Method::Standard),
+      _ => Err(Error::Invalid(format!(
+        "Unknown normalisation '{}', use minmax or standard.", text,
+      ))),
+    }
+  }
+}
+
+impl fmt::Display for Method {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Method::MinMax => write!(f, "minmax"),
+      Method::Standard => write!(f, "standard"),
+    }
+  }
+}
+
+/// Which values share the statistics of the normalisation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+
+  /// Every feature is scaled by its own statistics.
+  Feature,
+
+  /// All features are scaled alike, which keeps the relative intensities of
+  /// pixels intact.
+  Global,
+
+}
+
+impl FromStr for Scope {
+  type Err = Error;
+
+  fn from_str (text: &str) -> Result<Scope> {
+    match text {
+      "feature" => Ok(Scope::Feature),
+      "global" => Ok(Scope::Global),
+      _ => Err(Error::Invalid(format!(
+        "Unknown normalisation scope '{}', use feature or global.", text,
+      ))),
+    }
+  }
+}
+
+impl fmt::Display for Scope {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Scope::Feature => write!(f, "feature"),
+      Scope::Global => write!(f, "global"),
+    }
+  }
+}
+
+/// Normalisation fitted on training data. Each value becomes
+/// (value - offset) / scale with the offset and scale of its feature, or the
+/// single pair of a global normalisation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Normalizer {
+
+  /// How the statistics were chosen.
+  pub method: Method,
+
+  /// Which values share the statistics.
+  pub scope: Scope,
+
+  /// Number of features of the inputs.
+  features: usize,
+
+  /// Subtracted from the values, the minimum or the mean.
+  offsets: Vec<f64>,
+
+  /// Divides the shifted values, the range or the standard deviation.
+  scales: Vec<f64>,
+
+}
+
+impl Normalizer {
+
+  /// Computes the statistics of the data. Constant features would be
+  /// divided by zero, so their scale is 1 instead.
+  ///
+  /// @param dataset Training data
+  /// @param method How features are brought into a common range
+  /// @param scope Which values share the statistics
+  /// @return Fitted Normalizer instance
+  pub fn fit (dataset: &dyn Dataset, method: Method, scope: Scope) -> Self {
+    let features = dataset.features();
+    let groups = match scope {
+      Scope::Feature => features,
+      Scope::Global => 1,
+    };
+    let group = |feature: usize| if groups == 1 { 0 } else { feature };
+
+    let (offsets, scales) = match method {
+      Method::MinMax => {
+        let mut minimums = vec!(f64::INFINITY; groups);
+        let mut maximums = vec!(f64::NEG_INFINITY; groups);
+
+        for (_, values) in dataset.iter() {
+          for (feature, &value) in values.iter().enumerate() {
+            minimums[group(feature)] = minimums[group(feature)].min(value);
+            maximums[group(feature)] = maximums[group(feature)].max(value);
+          }
+        }
+
+        let ranges = minimums.iter().zip(maximums.iter())
+          .map(|(minimum, maximum)| maximum - minimum)
+          .collect();
+
+        (minimums, ranges)
+      },
+      Method::Standard => {
+        let mut means = vec!(0_f64; groups);
+        let mut counts = vec!(0_f64; groups);
+        for (_, values) in dataset.iter() {
+          for (feature, &value) in values.iter().enumerate() {
+            means[group(feature)] += value;
+            counts[group(feature)] += 1_f64;
+          }
+        }
+        for (mean, count) in means.iter_mut().zip(counts.iter()) {
+          *mean /= count.max(1_f64);
+        }
+
+        let mut deviations = vec!(0_f64; groups);
+        for (_, values) in dataset.iter() {
+          for (feature, &value) in values.iter().enumerate() {
+            deviations[group(feature)] += (value - means[group(feature)]).powi(2);
+          }
+        }
+        for (deviation, count) in deviations.iter_mut().zip(counts.iter()) {
+          *deviation = (*deviation / count.max(1_f64)).sqrt();
+        }
+
+        (means, deviations)
+      },
+    };
+
+    // Features without any samples have infinite bounds.
+    let offsets = offsets.into_iter()
+      .map(|offset| if offset.is_finite() { offset } else { 0_f64 })
+      .collect();
+    let scales = scales.into_iter()
+      .map(|scale| if scale.is_finite() && scale > 0_f64 { scale } else { 1_f64 })
+      .collect();
+
+    Normalizer { method, scope, features, offsets, scales }
+  }
+
+  /// @return Number of features of the inputs
+  pub fn features (&self) -> usize {
+    self.features
+  }
+
+  /// @param values Features of one input, normalised in place
+  pub fn apply (&self, values: &mut [f64]) {
+    let global = self.scope == Scope::Global;
+
+    for (feature, value) in values.iter_mut().enumerate() {
+      let group = if global { 0 } else { feature };
+      *value = (*value - self.offsets[group]) / self.scales[group];
+    }
+  }
+
+  /// @return JSON representation of the normaliser
+  pub fn to_json (&self) -> Json {
+    Json::object(vec!(
+      ("type", "normalize".into()),
+      ("method", self.method.to_string().into()),
+      ("scope", self.scope.to_string().into()),
+      ("features", self.features.into()),
+      ("offsets", self.offsets.clone().into()),
+      ("scales", self.scales.clone().into()),
+    ))
+  }
+
+  /// @param json JSON representation of the normaliser
+  /// @return Normalizer instance
+  pub fn from_json (json: &Json) -> Result<Normalizer> {
+    let invalid = |message: &str| Error::Invalid(format!("Invalid normalisation: {}.", message));
+    let text = |key: &str| json.get(key).and_then(Json::as_str)
+      .ok_or_else(|| invalid(&format!("missing {}", key)));
+    let numbers = |key: &str| json.get(key)
+      .and_then(Json::as_array)
+      .and_then(|values| values.iter().map(Json::as_f64).collect::<Option<Vec<f64>>>())
+      .ok_or_else(|| invalid(&format!("{} must be an array of numbers", key)));
+
+    let method: Method = text("method")?.parse()?;
+    let scope: Scope = text("scope")?.parse()?;
+    let features = json.get("features").and_then(Json::as_usize)
+      .ok_or_else(|| invalid("missing features"))?;
+    let (offsets, scales) = (numbers("offsets")?, numbers("scales")?);
+
+    let groups = if scope == Scope::Global { 1 } else { features };
+    if offsets.len() != groups || scales.len() != groups {
+      return Err(invalid(&format!("expected {} offsets and scales", groups)));
+    }
+
+    if scales.contains(&0_f64) {
+      return Err(invalid("scales must not be 0"));
+    }
+
+    Ok(Normalizer { method, scope, features, offsets, scales })
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use dataset::in_memory::InMemory;
+  use super::*;
+
+  #[test]
+  fn scales_by_training_statistics() {
+    let mut dataset = InMemory::new(2, vec!("a".to_string()));
+    dataset.push(0, &[0_f64, 10_f64]);
+    dataset.push(0, &[2_f64, 10_f64]);
+    dataset.push(0, &[4_f64, 10_f64]);
+
+    let minmax = Normalizer::fit(&dataset, Method::MinMax, Scope::Feature);
+    let mut values = [3_f64, 12_f64];
+    minmax.apply(&mut values);
+    // The constant feature is only shifted.
+    assert_eq!(values, [0.75_f64, 2_f64]);
+
+    let standard = Normalizer::fit(&dataset, Method::Standard, Scope::Global);
+    let mut values = [0_f64, 10_f64];
+    standard.apply(&mut values);
+    assert!((values[0] + 1.4412_f64).abs() < 1e-4 && (values[1] - 0.9608_f64).abs() < 1e-4);
+
+    assert_eq!(Normalizer::from_json(&standard.to_json()).unwrap(), standard);
+  }
+}
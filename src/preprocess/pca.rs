@@ -0,0 +1,426 @@
+use dataset::Dataset;
+use error::{Error, Result};
+use json::Json;
+
+/// How many principal components to keep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Components {
+
+  /// Given number of components, at most one per feature.
+  Count(usize),
+
+  /// As few components as explain at least this share of the variance,
+  /// between 0 and 1.
+  Variance(f64),
+
+}
+
+/// Projection of the features onto the directions of largest variance in the
+/// training data, which shrinks large inputs such as 28x28 images to a few
+/// dozen features.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pca {
+
+  /// Mean of each feature, subtracted before the projection.
+  mean: Vec<f64>,
+
+  /// Unit length direction of each kept component, largest variance first.
+  components: Vec<Vec<f64>>,
+
+  /// Share of the total variance each kept component explains.
+  explained: Vec<f64>,
+
+}
+
+impl Pca {
+
+  /// Computes the covariance matrix of the features and its eigenvectors.
+  /// Fails for features that are not finite numbers.
+  ///
+  /// @param dataset Training data
+  /// @param components How many components to keep
+  /// @return Fitted Pca instance
+  pub fn fit (dataset: &dyn Dataset, components: Components) -> Result<Self> {
+    let features = dataset.features();
+    let samples = dataset.len().max(1) as f64;
+
+    let mut mean = vec!(0_f64; features);
+    for (_, values) in dataset.iter() {
+      for (sum, value) in mean.iter_mut().zip(values.iter()) {
+        *sum += value;
+      }
+    }
+    mean.iter_mut().for_each(|sum| *sum /= samples);
+
+    // Only the upper triangle is accumulated, the matrix is symmetric.
+    let mut covariance = vec!(0_f64; features * features);
+    let mut centered = vec!(0_f64; features);
+    for (_, values) in dataset.iter() {
+      for ((center, value), mean) in centered.iter_mut().zip(values.iter()).zip(mean.iter()) {
+        *center = value - mean;
+      }
+
+      for (i, &x) in centered.iter().enumerate() {
+        if x == 0_f64 {
+          continue;
+        }
+
+        let row = &mut covariance[i * features..(i + 1) * features];
+        for (sum, &y) in row[i..].iter_mut().zip(centered[i..].iter()) {
+          *sum += x * y;
+        }
+      }
+    }
+
+    let divisor = (dataset.len().max(2) - 1) as f64;
+    for i in 0..features {
+      for j in i..features {
+        let value = covariance[i * features + j] / divisor;
+        covariance[i * features + j] = value;
+        covariance[j * features + i] = value;
+      }
+    }
+
+    if covariance.iter().any(|value| !value.is_finite()) {
+      return Err(Error::Invalid(
+        "Cannot fit PCA, the covariance of the features is not finite.".to_string(),
+      ));
+    }
+
+    let (values, vectors) = eigen(covariance, features)?;
+
+    let mut order: Vec<usize> = (0..features).collect();
+    order.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+
+    let total: f64 = values.iter().map(|value| value.max(0_f64)).sum();
+    let ratios: Vec<f64> = order.iter()
+      .map(|&index| if total > 0_f64 { values[index].max(0_f64) / total } else { 0_f64 })
+      .collect();
+
+    let count = match components {
+      Components::Count(count) => count.clamp(1, features.max(1)),
+      Components::Variance(target) => {
+        let mut cumulative = 0_f64;
+        ratios.iter()
+          .position(|ratio| {
+            cumulative += ratio;
+            cumulative >= target - 1e-12
+          })
+          .map_or(features, |position| position + 1)
+      },
+    }.min(features);
+
+    let components = order.iter()
+      .take(count)
+      .map(|&index| {
+        let mut direction: Vec<f64> = (0..features).map(|row| vectors[row * features + index]).collect();
+
+        // Eigenvectors have no inherent sign. Pointing the largest entry up
+        // makes the projection the same on every run.
+        let largest = direction.iter().cloned().fold(0_f64, |largest, value| {
+          if value.abs() > largest.abs() { value } else { largest }
+        });
+        if largest < 0_f64 {
+          direction.iter_mut().for_each(|value| *value = -*value);
+        }
+
+        direction
+      })
+      .collect();
+
+    Ok(Pca { mean, components, explained: ratios[..count].to_vec() })
+  }
+
+  /// @return Number of features of the inputs
+  pub fn features (&self) -> usize {
+    self.mean.len()
+  }
+
+  /// @return Number of kept components, which is the number of outputs
+  pub fn components (&self) -> usize {
+    self.components.len()
+  }
+
+  /// @return Share of the total variance the kept components explain
+  pub fn explained_variance (&self) -> f64 {
+    self.explained.iter().sum()
+  }
+
+  /// @param values Features of one input
+  /// @return Coordinates of the input along each component
+  pub fn apply (&self, values: &[f64]) -> Vec<f64> {
+    self.components.iter()
+      .map(|direction| {
+        direction.iter()
+          .zip(values.iter().zip(self.mean.iter()))
+          .map(|(weight, (value, mean))| weight * (value - mean))
+          .sum()
+      })
+      .collect()
+  }
+
+  /// @return JSON representation of the projection
+  pub fn to_json (&self) -> Json {
+    Json::object(vec!(
+      ("type", "pca".into()),
+      ("mean", self.mean.clone().into()),
+      ("components", self.components.clone().into()),
+      ("explained", self.explained.clone().into()),
+    ))
+  }
+
+  /// @param json JSON representation of the projection
+  /// @return Pca instance
+  pub fn from_json (json: &Json) -> Result<Pca> {
+    let invalid = |message: &str| Error::Invalid(format!("Invalid PCA: {}.", message));
+    let numbers = |json: &Json| json.as_array()
+      .and_then(|values| values.iter().map(Json::as_f64).collect::<Option<Vec<f64>>>());
+
+    let mean = json.get("mean").and_then(numbers)
+      .ok_or_else(|| invalid("mean must be an array of numbers"))?;
+    let explained = json.get("explained").and_then(numbers)
+      .ok_or_else(|| invalid("explained must be an array of numbers"))?;
+    let components = json.get("components")
+      .and_then(Json::as_array)
+      .and_then(|components| components.iter().map(numbers).collect::<Option<Vec<Vec<f64>>>>())
+      .ok_or_else(|| invalid("components must be arrays of numbers"))?;
+
+    if components.is_empty() || components.iter().any(|direction| direction.len() != mean.len()) {
+      return Err(invalid(&format!("expected components of {} values", mean.len())));
+    }
+
+    if explained.len() != components.len() {
+      return Err(invalid("expected a share of the variance for each component"));
+    }
+
+    Ok(Pca { mean, components, explained })
+  }
+
+}
+
+/// Eigen decomposition of a symmetric matrix. The matrix is first reduced to
+/// tridiagonal form by Householder reflections, whose eigenvalues the
+/// implicit QL method then finds. This follows tred2 and tql2 of EISPACK.
+///
+/// @param v Symmetric matrix, row by row
+/// @param n Number of rows and columns
+/// @return Tuple in format (eigenvalues, eigenvectors) where eigenvector i is
+///         column i of the row by row matrix
+fn eigen (mut v: Vec<f64>, n: usize) -> Result<(Vec<f64>, Vec<f64>)> {
+  let mut d = vec!(0_f64; n);
+  let mut e = vec!(0_f64; n);
+
+  if n == 0 {
+    return Ok((d, v));
+  }
+
+  tridiagonalize(&mut v, &mut d, &mut e, n);
+  diagonalize(&mut v, &mut d, &mut e, n)?;
+
+  Ok((d, v))
+}
+
+/// Householder reduction to tridiagonal form. Leaves the diagonal in d, the
+/// subdiagonal in e and the accumulated transformation in v.
+fn tridiagonalize (v: &mut [f64], d: &mut [f64], e: &mut [f64], n: usize) {
+  d.copy_from_slice(&v[(n - 1) * n..]);
+
+  for i in (1..n).rev() {
+    let scale: f64 = d[..i].iter().map(|value| value.abs()).sum();
+    let mut h = 0_f64;
+
+    if scale == 0_f64 {
+      e[i] = d[i - 1];
+      for j in 0..i {
+        d[j] = v[(i - 1) * n + j];
+        v[i * n + j] = 0_f64;
+        v[j * n + i] = 0_f64;
+      }
+    } else {
+      // Generates the Householder vector.
+      for value in d[..i].iter_mut() {
+        *value /= scale;
+        h += *value * *value;
+      }
+
+      let mut f = d[i - 1];
+      let mut g = if f > 0_f64 { -h.sqrt() } else { h.sqrt() };
+      e[i] = scale * g;
+      h -= f * g;
+      d[i - 1] = f - g;
+      e[..i].iter_mut().for_each(|value| *value = 0_f64);
+
+      // Applies the similarity transformation to the remaining columns.
+      for j in 0..i {
+        f = d[j];
+        v[j * n + i] = f;
+        g = e[j] + v[j * n + j] * f;
+        for k in j + 1..i {
+          g += v[k * n + j] * d[k];
+          e[k] += v[k * n + j] * f;
+        }
+        e[j] = g;
+      }
+
+      f = 0_f64;
+      for j in 0..i {
+        e[j] /= h;
+        f += e[j] * d[j];
+      }
+
+      let hh = f / (h + h);
+      for j in 0..i {
+        e[j] -= hh * d[j];
+      }
+
+      for j in 0..i {
+        f = d[j];
+        g = e[j];
+        for k in j..i {
+          v[k * n + j] -= f * e[k] + g * d[k];
+        }
+        d[j] = v[(i - 1) * n + j];
+        v[i * n + j] = 0_f64;
+      }
+    }
+
+    d[i] = h;
+  }
+
+  // Accumulates the transformations.
+  for i in 0..n - 1 {
+    v[(n - 1) * n + i] = v[i * n + i];
+    v[i * n + i] = 1_f64;
+
+    let h = d[i + 1];
+    if h != 0_f64 {
+      for k in 0..=i {
+        d[k] = v[k * n + i + 1] / h;
+      }
+      for j in 0..=i {
+        let g: f64 = (0..=i).map(|k| v[k * n + i + 1] * v[k * n + j]).sum();
+        for k in 0..=i {
+          v[k * n + j] -= g * d[k];
+        }
+      }
+    }
+
+    for k in 0..=i {
+      v[k * n + i + 1] = 0_f64;
+    }
+  }
+
+  for j in 0..n {
+    d[j] = v[(n - 1) * n + j];
+    v[(n - 1) * n + j] = 0_f64;
+  }
+  v[(n - 1) * n + n - 1] = 1_f64;
+  e[0] = 0_f64;
+}
+
+/// Implicit QL iterations on the tridiagonal matrix. Leaves the eigenvalues
+/// in d and rotates v into the eigenvectors. Fails when the iterations do not
+/// converge, which well formed matrices reach in a few steps per eigenvalue.
+fn diagonalize (v: &mut [f64], d: &mut [f64], e: &mut [f64], n: usize) -> Result<()> {
+  for i in 1..n {
+    e[i - 1] = e[i];
+  }
+  e[n - 1] = 0_f64;
+
+  let mut f = 0_f64;
+  let mut largest = 0_f64;
+  let mut iterations = 30 * n;
+
+  for l in 0..n {
+    // Finds the first negligible subdiagonal entry, the last one is 0.
+    largest = largest.max(d[l].abs() + e[l].abs());
+    let m = (l..n).find(|&m| e[m].abs() <= f64::EPSILON * largest).unwrap_or(n - 1);
+
+    if m > l {
+      loop {
+        if iterations == 0 {
+          return Err(Error::Invalid(
+            "Cannot fit PCA, the eigen decomposition does not converge.".to_string(),
+          ));
+        }
+        iterations -= 1;
+
+        // Computes the implicit shift.
+        let g = d[l];
+        let p = (d[l + 1] - g) / (2_f64 * e[l]);
+        let r = if p < 0_f64 { -p.hypot(1_f64) } else { p.hypot(1_f64) };
+        d[l] = e[l] / (p + r);
+        d[l + 1] = e[l] * (p + r);
+        let next = d[l + 1];
+        let h = g - d[l];
+        for value in d[l + 2..].iter_mut() {
+          *value -= h;
+        }
+        f += h;
+
+        let mut p = d[m];
+        let (mut c, mut c2, mut c3) = (1_f64, 1_f64, 1_f64);
+        let (mut s, mut s2) = (0_f64, 0_f64);
+        let following = e[l + 1];
+
+        for i in (l..m).rev() {
+          c3 = c2;
+          c2 = c;
+          s2 = s;
+          let g = c * e[i];
+          let h = c * p;
+          let r = p.hypot(e[i]);
+          e[i + 1] = s * r;
+          s = e[i] / r;
+          c = p / r;
+          p = c * d[i] - s * g;
+          d[i + 1] = h + s * (c * g + s * d[i]);
+
+          for k in 0..n {
+            let h = v[k * n + i + 1];
+            v[k * n + i + 1] = s * v[k * n + i] + c * h;
+            v[k * n + i] = c * v[k * n + i] - s * h;
+          }
+        }
+
+        p = -s * s2 * c3 * following * e[l] / next;
+        e[l] = s * p;
+        d[l] = c * p;
+
+        if e[l].abs() <= f64::EPSILON * largest {
+          break;
+        }
+      }
+    }
+
+    d[l] += f;
+    e[l] = 0_f64;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use dataset::in_memory::InMemory;
+  use super::*;
+
+  #[test]
+  fn finds_direction_of_largest_variance() {
+    let mut dataset = InMemory::new(3, vec!("a".to_string()));
+    let noise = [0.1_f64, -0.2_f64, 0.2_f64, -0.2_f64, 0.1_f64];
+    for (x, noise) in (-2..3).zip(noise.iter()) {
+      dataset.push(0, &[f64::from(x) + 5_f64, f64::from(x) + 5_f64, *noise]);
+    }
+
+    let pca = Pca::fit(&dataset, Components::Variance(0.9_f64)).unwrap();
+    assert_eq!(pca.components(), 1);
+    assert!(pca.explained_variance() > 0.99_f64);
+
+    let projected = pca.apply(&[7_f64, 7_f64, 0_f64]);
+    assert!((projected[0] - 8_f64.sqrt()).abs() < 1e-9);
+
+    assert_eq!(Pca::from_json(&pca.to_json()).unwrap(), pca);
+    assert_eq!(Pca::fit(&dataset, Components::Count(5)).unwrap().components(), 3);
+  }
+}
@@ -0,0 +1,185 @@
+use dataset::Dataset;
+use dataset::in_memory::InMemory;
+use error::{Error, Result};
+use super::sample::{Format, Shape};
+
+/// Reads the original optdigits bitmaps. Each digit is written as rows of
+/// "0" and "1" characters, 32 rows of 32 pixels in the UCI files, followed by
+/// a line with the class. Lines before the first bitmap row, such as the
+/// description at the top of the UCI files, are skipped.
+///
+/// @param source Text of the bitmap file
+/// @param known_labels Label names, by default classes are numbered
+/// @return Data set of binary pixels shaped as the bitmaps
+pub fn read_bitmaps(source: &str, known_labels: Option<&[String]>) -> Result<InMemory> {
+  let mut lines = source.lines()
+    .enumerate()
+    .map(|(index, line)| (index + 1, line.trim()))
+    .skip_while(|(_, line)| !is_row(line, None))
+    .peekable();
+
+  let width = match lines.peek() {
+    Some((_, row)) => row.len(),
+    None => return Err(Error::Invalid("File does not contain any bitmap.".to_string())),
+  };
+
+  let mut height = None;
+  let mut pixels: Vec<f64> = Vec::new();
+  let mut classes: Vec<usize> = Vec::new();
+  let mut rows = 0;
+
+  for (line, text) in lines {
+    if is_row(text, Some(width)) {
+      pixels.extend(text.chars().map(|pixel| if pixel == '1' { 1_f64 } else { 0_f64 }));
+      rows += 1;
+      continue;
+    }
+
+    if text.is_empty() {
+      continue;
+    }
+
+    let class = text.parse::<usize>().map_err(|_| Error::Parse {
+      line,
+      column: 1,
+      message: format!("expected a row of {} pixels or a class, found '{}'", width, text),
+    })?;
+
+    // Every bitmap must have as many rows as the first one.
+    if rows != *height.get_or_insert(rows) || rows == 0 {
+      return Err(Error::Parse {
+        line,
+        column: 1,
+        message: format!("bitmap has {} rows, expected {}", rows, height.unwrap()),
+      });
+    }
+
+    classes.push(class);
+    rows = 0;
+  }
+
+  if rows > 0 {
+    return Err(Error::Invalid("Last bitmap is not followed by its class.".to_string()));
+  }
+
+  let names: Vec<String> = match known_labels {
+    Some(names) => names.to_vec(),
+    None => {
+      let count = classes.iter().max().map_or(0, |max| max + 1);
+
+      (0..count).map(|class| class.to_string()).collect()
+    },
+  };
+
+  let shape = Shape::new(width, height.unwrap_or(0), 1);
+  let mut dataset = InMemory::with_format(&Format::image(shape, names));
+
+  for (index, (&class, features)) in classes.iter().zip(pixels.chunks(shape.len())).enumerate() {
+    if class >= dataset.classes() {
+      return Err(Error::Invalid(format!(
+        "Class {} of bitmap {} is not a known class.", class, index + 1,
+      )));
+    }
+
+    dataset.push(class, features);
+  }
+
+  Ok(dataset)
+}
+
+/// Shrinks each image of the data set by averaging non overlapping square
+/// blocks of pixels. Binary bitmaps become the share of set pixels in each
+/// block, so 32x32 optdigits bitmaps with blocks of 4 turn into the 8x8
+/// features the preprocessed UCI files hold, divided by 16.
+///
+/// @param dataset Data set of image shaped samples
+/// @param block Width and height of a block in pixels
+/// @return Data set of the smaller images
+pub fn downsample(dataset: &dyn Dataset, block: usize) -> Result<InMemory> {
+  let shape = dataset.shape().ok_or_else(|| {
+    Error::Invalid("Only image shaped samples can be downsampled.".to_string())
+  })?;
+
+  let smaller = downsampled_shape(&shape, block)?;
+  let mut result = InMemory::with_format(&Format::image(smaller, dataset.labels().to_vec()));
+
+  for (class, features) in dataset.iter() {
+    result.push(class, &downsample_image(features, &shape, block));
+  }
+
+  Ok(result)
+}
+
+/// Checks that blocks of given size tile the image.
+///
+/// @param shape Dimensions of the image
+/// @param block Width and height of a block in pixels
+/// @return Dimensions of the downsampled image
+pub fn downsampled_shape(shape: &Shape, block: usize) -> Result<Shape> {
+  if block == 0 || !shape.width.is_multiple_of(block) || !shape.height.is_multiple_of(block) {
+    return Err(Error::Invalid(format!(
+      "Blocks of {} pixels do not tile {}x{} images.", block, shape.width, shape.height,
+    )));
+  }
+
+  Ok(Shape::new(shape.width / block, shape.height / block, shape.channels))
+}
+
+/// Averages non overlapping square blocks of one image, each channel on its
+/// own. Blocks are expected to tile the image, see `downsampled_shape`.
+///
+/// @param pixels Features of the image
+/// @param shape Dimensions of the image
+/// @param block Width and height of a block in pixels
+/// @return Features of the downsampled image
+pub fn downsample_image(pixels: &[f64], shape: &Shape, block: usize) -> Vec<f64> {
+  let (width, height, channels) = (shape.width / block, shape.height / block, shape.channels);
+  let area = (block * block) as f64;
+  let mut result = vec!(0_f64; width * height * channels);
+
+  for y in 0..shape.height {
+    for x in 0..shape.width {
+      let source = (y * shape.width + x) * channels;
+      let target = ((y / block) * width + x / block) * channels;
+
+      for channel in 0..channels {
+        result[target + channel] += pixels[source + channel] / area;
+      }
+    }
+  }
+
+  result
+}
+
+/// @param line Trimmed line of the file
+/// @param width Expected number of pixels, any width of at least two if None
+/// @return Whether the line is a row of a bitmap
+fn is_row(line: &str, width: Option<usize>) -> bool {
+  let fits = match width {
+    Some(width) => line.len() == width,
+    None => line.len() > 1,
+  };
+
+  fits && line.chars().all(|pixel| pixel == '0' || pixel == '1')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn downsamples_bitmaps_into_block_counts() {
+    let source = "Header of the file\n\n0011\n0011\n1000\n0000\n 7\n1111\n1111\n1111\n1111\n 2\n";
+
+    let bitmaps = read_bitmaps(source, None).unwrap();
+    assert_eq!(bitmaps.len(), 2);
+    assert_eq!(bitmaps.shape(), Some(Shape::new(4, 4, 1)));
+    assert_eq!(bitmaps.classes(), 8);
+
+    let blocks = downsample(&bitmaps, 2).unwrap();
+    assert_eq!(blocks.shape(), Some(Shape::new(2, 2, 1)));
+    assert_eq!(blocks.sample(0), (7, &[0_f64, 1_f64, 0.25_f64, 0_f64][..]));
+    assert_eq!(blocks.sample(1), (2, &[1_f64; 4][..]));
+    assert!(downsample(&bitmaps, 3).is_err());
+  }
+}
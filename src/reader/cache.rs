@@ -0,0 +1,339 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use checksum::Crc32;
+use dataset::Dataset;
+use dataset::in_memory::InMemory;
+use error::{Error, Result};
+use super::sample::{Format, Shape};
+
+/// First bytes of every cache file.
+const MAGIC: [u8; 8] = *b"CLSCACHE";
+
+/// Version of the cache file layout.
+const VERSION: u32 = 1;
+
+/// Size of the magic number, the version and the checksum.
+const PREAMBLE: u64 = 16;
+
+/// Bytes converted at once while streaming the blocks.
+const CHUNK: usize = 8192;
+
+/// How the features are stored in a cache file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precision {
+
+  /// Half the size, exact for pixels and small counts such as optdigits.
+  F32,
+
+  /// Exactly the values the data set holds.
+  F64,
+
+}
+
+impl Precision {
+
+  /// @return Bytes per feature
+  pub fn size (self) -> usize {
+    match self {
+      Precision::F32 => 4,
+      Precision::F64 => 8,
+    }
+  }
+
+  /// @param value Feature to store
+  /// @param bytes Where to append the little endian bytes
+  fn encode (self, value: f64, bytes: &mut Vec<u8>) {
+    match self {
+      Precision::F32 => bytes.extend_from_slice(&(value as f32).to_le_bytes()),
+      Precision::F64 => bytes.extend_from_slice(&value.to_le_bytes()),
+    }
+  }
+
+  /// @param bytes Little endian bytes of one feature
+  /// @return Stored feature
+  fn decode (self, bytes: &[u8]) -> f64 {
+    match self {
+      Precision::F32 => f64::from(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+      Precision::F64 => f64::from_le_bytes([
+        bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7],
+      ]),
+    }
+  }
+
+}
+
+impl FromStr for Precision {
+  type Err = Error;
+
+  fn from_str (text: &str) -> Result<Precision> {
+    match text {
+      "f32" => Ok(Precision::F32),
+      "f64" => Ok(Precision::F64),
+      _ => Err(Error::Invalid(format!("Invalid precision '{}', use f32 or f64.", text))),
+    }
+  }
+}
+
+/// Writes a data set into a cache file. All numbers are little endian:
+///
+/// - magic "CLSCACHE", version (u32) and CRC-32 of everything after it (u32)
+/// - bytes per feature (u32), samples (u64), features (u64), classes (u32)
+/// - image width, height and channels (u32 each, zeros without a shape)
+/// - each label name as its length in bytes (u32) followed by UTF-8
+/// - features of all samples one after another (f32 or f64)
+/// - class index of each sample (u32)
+///
+/// @param dataset Data set to store
+/// @param precision How to store the features
+/// @param path Where to write the cache
+pub fn save_cache<P: AsRef<Path>> (dataset: &dyn Dataset, precision: Precision, path: P) -> Result<()> {
+  let mut header = Vec::new();
+  let shape = dataset.shape().unwrap_or_else(|| Shape::new(0, 0, 0));
+
+  header.extend_from_slice(&(precision.size() as u32).to_le_bytes());
+  header.extend_from_slice(&(dataset.len() as u64).to_le_bytes());
+  header.extend_from_slice(&(dataset.features() as u64).to_le_bytes());
+  header.extend_from_slice(&(dataset.classes() as u32).to_le_bytes());
+  for dimension in &[shape.width, shape.height, shape.channels] {
+    header.extend_from_slice(&(*dimension as u32).to_le_bytes());
+  }
+  for label in dataset.labels() {
+    header.extend_from_slice(&(label.len() as u32).to_le_bytes());
+    header.extend_from_slice(label.as_bytes());
+  }
+
+  // The checksum precedes the data, so the data is encoded twice: once to
+  // compute the checksum and once to write it.
+  let mut crc = Crc32::new();
+  crc.update(&header);
+  blocks(dataset, precision, |bytes| { crc.update(bytes); Ok(()) })?;
+
+  let mut writer = BufWriter::new(File::create(path)?);
+  writer.write_all(&MAGIC)?;
+  writer.write_all(&VERSION.to_le_bytes())?;
+  writer.write_all(&crc.finish().to_le_bytes())?;
+  writer.write_all(&header)?;
+  blocks(dataset, precision, |bytes| writer.write_all(bytes).map_err(Error::from))?;
+  writer.flush()?;
+
+  Ok(())
+}
+
+/// Encodes the feature block and the label block in parts.
+///
+/// @param dataset Data set to encode
+/// @param precision How to store the features
+/// @param consume Called with each encoded part
+fn blocks<F> (dataset: &dyn Dataset, precision: Precision, mut consume: F) -> Result<()>
+  where F: FnMut(&[u8]) -> Result<()>
+{
+  let mut bytes = Vec::with_capacity(CHUNK + 8);
+
+  for (_, features) in dataset.iter() {
+    for &feature in features {
+      precision.encode(feature, &mut bytes);
+
+      if bytes.len() >= CHUNK {
+        consume(&bytes)?;
+        bytes.clear();
+      }
+    }
+  }
+
+  for (class, _) in dataset.iter() {
+    bytes.extend_from_slice(&(class as u32).to_le_bytes());
+
+    if bytes.len() >= CHUNK {
+      consume(&bytes)?;
+      bytes.clear();
+    }
+  }
+
+  consume(&bytes)
+}
+
+/// Reads a data set from a cache file written by `save_cache`. The features
+/// are decoded straight into the vector the data set keeps, which is
+/// allocated once at its final size.
+///
+/// @param path Path to the cache
+/// @param known_labels Label names, e.g. of the training data or a model.
+///        Classes of the cache are mapped onto them by name.
+/// @return Data set with the samples
+pub fn load_cache<P: AsRef<Path>> (path: P, known_labels: Option<&[String]>) -> Result<InMemory> {
+  let path = path.as_ref();
+  let length = fs::metadata(path)?.len();
+  let mut reader = Reader { inner: BufReader::new(File::open(path)?), crc: Crc32::new(), read: 0 };
+
+  let mut preamble = [0_u8; PREAMBLE as usize];
+  reader.inner.read_exact(&mut preamble)?;
+
+  if preamble[..8] != MAGIC {
+    return Err(Error::Invalid("Not a cache file, bad magic number.".to_string()));
+  }
+
+  let version = u32::from_le_bytes([preamble[8], preamble[9], preamble[10], preamble[11]]);
+  if version != VERSION {
+    return Err(Error::Invalid(format!("Unsupported cache version {}.", version)));
+  }
+
+  let checksum = u32::from_le_bytes([preamble[12], preamble[13], preamble[14], preamble[15]]);
+
+  let precision = match reader.u32()? {
+    4 => Precision::F32,
+    8 => Precision::F64,
+    size => return Err(Error::Invalid(format!("Unsupported feature size {}.", size))),
+  };
+  let samples = reader.u64()?;
+  let features = reader.u64()?;
+  let classes = reader.u32()?;
+  let (width, height, channels) = (reader.u32()?, reader.u32()?, reader.u32()?);
+
+  let mut labels = Vec::new();
+  for _ in 0..classes {
+    let size = u64::from(reader.u32()?);
+    if PREAMBLE + reader.read + size > length {
+      return Err(Error::Invalid("Cache file is truncated.".to_string()));
+    }
+
+    let mut name = vec!(0_u8; size as usize);
+    reader.bytes(&mut name)?;
+    labels.push(String::from_utf8(name)
+      .map_err(|_| Error::Invalid("Label is not valid UTF-8.".to_string()))?);
+  }
+
+  // The blocks must fill the rest of the file exactly. This is checked before
+  // anything is allocated, so that a corrupted header cannot ask for more
+  // memory than the file could hold.
+  let values = samples.checked_mul(features);
+  let expected = values
+    .and_then(|values| values.checked_mul(precision.size() as u64))
+    .and_then(|size| samples.checked_mul(4).and_then(|labels| size.checked_add(labels)));
+
+  if expected != Some(length.saturating_sub(PREAMBLE + reader.read)) {
+    return Err(Error::Invalid("Cache file size does not match its header.".to_string()));
+  }
+
+  let (samples, features) = (samples as usize, features as usize);
+  let mut data = Vec::with_capacity(samples * features);
+  let mut targets = Vec::with_capacity(samples);
+  let mut buffer = [0_u8; CHUNK];
+
+  let size = precision.size();
+  let mut remaining = samples * features * size;
+  while remaining > 0 {
+    let chunk = &mut buffer[..remaining.min(CHUNK)];
+    reader.bytes(chunk)?;
+    data.extend(chunk.chunks(size).map(|bytes| precision.decode(bytes)));
+    remaining -= chunk.len();
+  }
+
+  let mut remaining = samples * 4;
+  while remaining > 0 {
+    let chunk = &mut buffer[..remaining.min(CHUNK)];
+    reader.bytes(chunk)?;
+    targets.extend(chunk.chunks(4).map(|bytes| {
+      u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+    }));
+    remaining -= chunk.len();
+  }
+
+  if reader.crc.finish() != checksum {
+    return Err(Error::Invalid("Cache file is corrupted, checksum does not match.".to_string()));
+  }
+
+  if let Some(index) = targets.iter().position(|&class| class >= labels.len()) {
+    return Err(Error::Invalid(format!(
+      "Sample {} has class {} but there are only {} classes.", index + 1, targets[index], labels.len(),
+    )));
+  }
+
+  if let Some(known) = known_labels {
+    let classes = labels.iter()
+      .map(|label| known.iter().position(|name| name == label).ok_or_else(|| {
+        Error::Invalid(format!("Label '{}' is not a known class.", label))
+      }))
+      .collect::<Result<Vec<usize>>>()?;
+
+    targets.iter_mut().for_each(|class| *class = classes[*class]);
+    labels = known.to_vec();
+  }
+
+  let shape = match (width, height, channels) {
+    (0, 0, 0) => None,
+    _ => Some(Shape::new(width as usize, height as usize, channels as usize)),
+  };
+
+  let format = Format { features, labels, shape };
+
+  if shape.is_some_and(|shape| shape.len() != features) {
+    return Err(Error::Invalid("Cache shape does not match its features.".to_string()));
+  }
+
+  Ok(InMemory::from_parts(&format, data, targets))
+}
+
+/// Reads the checksummed part of a cache file.
+struct Reader<R: Read> {
+  inner: R,
+  crc: Crc32,
+  read: u64,
+}
+
+impl<R: Read> Reader<R> {
+
+  fn bytes (&mut self, buffer: &mut [u8]) -> Result<()> {
+    self.inner.read_exact(buffer)?;
+    self.crc.update(buffer);
+    self.read += buffer.len() as u64;
+
+    Ok(())
+  }
+
+  fn u32 (&mut self) -> Result<u32> {
+    let mut bytes = [0_u8; 4];
+    self.bytes(&mut bytes)?;
+
+    Ok(u32::from_le_bytes(bytes))
+  }
+
+  fn u64 (&mut self) -> Result<u64> {
+    let mut bytes = [0_u8; 8];
+    self.bytes(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env;
+  use super::*;
+
+  #[test]
+  fn loads_what_it_saved() {
+    let mut dataset = InMemory::with_format(&Format::image(
+      Shape::new(2, 1, 1),
+      vec!("cat".to_string(), "dog".to_string()),
+    ));
+    dataset.push(1, &[0.25_f64, 1_f64]);
+    dataset.push(0, &[0.5_f64, 0_f64]);
+
+    let path = env::temp_dir().join(format!("classifier-cache-{}.bin", ::std::process::id()));
+    save_cache(&dataset, Precision::F32, &path).unwrap();
+
+    let loaded = load_cache(&path, None);
+    let known = vec!("dog".to_string(), "cat".to_string());
+    let relabelled = load_cache(&path, Some(&known));
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap(), dataset);
+
+    let relabelled = relabelled.unwrap();
+    assert_eq!(relabelled.sample(0), (0, &[0.25_f64, 1_f64][..]));
+    assert_eq!(relabelled.labels(), &known[..]);
+  }
+}
@@ -0,0 +1,662 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use dataset::Dataset;
+use dataset::in_memory::InMemory;
+use error::{Error, Result};
+use super::sample::{Format, Sample, Shape};
+
+/// Which column of a row holds the label.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelColumn {
+
+  /// The first column.
+  First,
+
+  /// The last column, as in the optdigits files.
+  Last,
+
+  /// Column at given position, starting at 0.
+  Index(usize),
+
+  /// Column with given name in the header row.
+  Name(String),
+
+}
+
+impl FromStr for LabelColumn {
+  type Err = Error;
+
+  /// Parses "first", "last", a column position or a column name.
+  fn from_str (text: &str) -> Result<LabelColumn> {
+    Ok(match text {
+      "first" => LabelColumn::First,
+      "last" => LabelColumn::Last,
+      _ => match text.parse::<usize>() {
+        Ok(index) => LabelColumn::Index(index),
+        Err(_) => LabelColumn::Name(text.to_string()),
+      },
+    })
+  }
+}
+
+impl fmt::Display for LabelColumn {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      LabelColumn::First => write!(f, "first"),
+      LabelColumn::Last => write!(f, "last"),
+      LabelColumn::Index(index) => write!(f, "{}", index),
+      LabelColumn::Name(ref name) => write!(f, "{}", name),
+    }
+  }
+}
+
+/// Describes the layout of a delimited text file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+
+  /// Character separating the fields.
+  pub delimiter: char,
+
+  /// Whether the first row holds column names rather than a sample.
+  pub header: bool,
+
+  /// Which column holds the label.
+  pub label_column: LabelColumn,
+
+  /// Lines starting with this character are skipped.
+  pub comment: Option<char>,
+
+  /// Number to divide each feature by.
+  pub scale: f64,
+
+  /// Image shape of the features, if they are pixels.
+  pub shape: Option<Shape>,
+
+}
+
+impl CsvOptions {
+
+  /// Layout of the optdigits files, whose features are block counts from 0
+  /// to 16.
+  ///
+  /// @return Options for the optdigits files
+  pub fn optdigits () -> Self {
+    CsvOptions { scale: 16_f64, ..CsvOptions::default() }
+  }
+
+}
+
+impl Default for CsvOptions {
+
+  /// Comma separated, no header, label in the last column, as in the
+  /// optdigits files but without scaling.
+  fn default () -> Self {
+    CsvOptions {
+      delimiter: ',',
+      header: false,
+      label_column: LabelColumn::Last,
+      comment: None,
+      scale: 1_f64,
+      shape: None,
+    }
+  }
+
+}
+
+/// Column names from the header, if any, and the fields of each row.
+type Rows = (Option<Vec<String>>, Vec<Vec<Field>>);
+
+/// Field of a row together with its position for error messages.
+struct Field {
+
+  /// Line the field is on, starting at 1.
+  line: usize,
+
+  /// Column of the first character of the field, starting at 1.
+  column: usize,
+
+  /// Unquoted content of the field.
+  text: String,
+
+}
+
+/// Reads labelled samples from delimited text. Labels are mapped to class
+/// indices in one of three ways:
+///
+/// - if labels are given (e.g. from the training data or a model), each label
+///   must be one of them,
+/// - if all labels are whole numbers, the number is the class index, so that
+///   the optdigits classes stay 0 to 9,
+/// - otherwise the distinct labels are sorted and numbered.
+///
+/// @param source Text of the file
+/// @param options Layout of the file
+/// @param labels Known label names
+/// @return Data set with the samples
+pub fn read_csv (
+  source: &str,
+  options: &CsvOptions,
+  labels: Option<&[String]>,
+) -> Result<InMemory> {
+  let (header, rows) = read_rows(source, options)?;
+  let first = match rows.first() {
+    Some(row) => row,
+    None => return Err(Error::Invalid("File has no samples.".to_string())),
+  };
+
+  let label_index = label_index(&options.label_column, header.as_ref(), first)?;
+  let features = first.len() - 1;
+
+  // Labels are only inferred once every row is known to have one.
+  for row in rows.iter() {
+    check_fields(row, features)?;
+  }
+
+  let labels: Vec<String> = match labels {
+    Some(labels) => labels.to_vec(),
+    None => infer_labels(rows.iter().map(|row| row[label_index].text.as_str())),
+  };
+
+  if let Some(shape) = options.shape {
+    if shape.len() != features {
+      return Err(Error::Invalid(format!(
+        "Shape {} needs {} features but rows have {}.",
+        shape, shape.len(), features,
+      )));
+    }
+  }
+
+  let format = Format { features, labels, shape: options.shape };
+
+  let mut dataset = InMemory::with_format(&format);
+  let mut values = Vec::with_capacity(features);
+
+  for row in rows.iter() {
+    let label = &row[label_index];
+    let class = class_of(&format.labels, &label.text)
+      .ok_or_else(|| parse_error(label, &format!("unknown label '{}'", label.text)))?;
+
+    values.clear();
+    for (index, field) in row.iter().enumerate() {
+      if index != label_index {
+        values.push(number(field)? / options.scale);
+      }
+    }
+
+    dataset.push(class, &values);
+  }
+
+  Ok(dataset)
+}
+
+/// Writes a data set as delimited text that `read_csv` reads back with the
+/// same options. Features are multiplied by the scale again and labels are
+/// written by name, quoted when needed. A label column given by name is
+/// written last.
+///
+/// @param dataset Data set to write
+/// @param options Layout of the file
+/// @param writer Where to write the rows
+pub fn write_csv<W: Write> (dataset: &dyn Dataset, options: &CsvOptions, mut writer: W) -> Result<()> {
+  let features = dataset.features();
+  let position = match options.label_column {
+    LabelColumn::First => 0,
+    LabelColumn::Index(index) => index.min(features),
+    LabelColumn::Last | LabelColumn::Name(_) => features,
+  };
+  let delimiter = options.delimiter.to_string();
+
+  if options.header {
+    let mut names: Vec<String> = (0..features).map(|feature| format!("x{}", feature)).collect();
+    names.insert(position, match options.label_column {
+      LabelColumn::Name(ref name) => quote(name, options.delimiter),
+      _ => "label".to_string(),
+    });
+
+    writeln!(writer, "{}", names.join(&delimiter))?;
+  }
+
+  let labels: Vec<String> = dataset.labels().iter()
+    .map(|label| quote(label, options.delimiter))
+    .collect();
+
+  let mut fields = Vec::with_capacity(features + 1);
+  for (class, values) in dataset.iter() {
+    fields.clear();
+    fields.extend(values.iter().map(|value| (value * options.scale).to_string()));
+    fields.insert(position, labels[class].clone());
+
+    writeln!(writer, "{}", fields.join(&delimiter))?;
+  }
+
+  writer.flush()?;
+
+  Ok(())
+}
+
+/// Quotes a field that would otherwise not be read back as it is.
+///
+/// @param text Content of the field
+/// @param delimiter Field separator
+/// @return Field as written into the file
+pub fn quote (text: &str, delimiter: char) -> String {
+  let plain = !text.contains(delimiter)
+    && !text.contains('"')
+    && !text.starts_with(' ')
+    && !text.ends_with(' ');
+
+  if plain {
+    text.to_string()
+  } else {
+    format!("\"{}\"", text.replace('"', "\"\""))
+  }
+}
+
+/// Reads unlabelled rows of features, e.g. inputs to classify. The label
+/// column option is ignored.
+///
+/// @param source Text of the file
+/// @param options Layout of the file
+/// @return Features of each row
+pub fn read_features (source: &str, options: &CsvOptions) -> Result<Vec<Vec<f64>>> {
+  let (_, rows) = read_rows(source, options)?;
+
+  rows.iter()
+    .map(|row| {
+      row.iter()
+        .map(|field| number(field).map(|value| value / options.scale))
+        .collect()
+    })
+    .collect()
+}
+
+/// Parses labelled rows one line at a time, for readers that never hold the
+/// whole file. Unlike `read_csv` it cannot look at all labels first, so the
+/// label names must be known upfront, e.g. from `infer_labels`.
+pub struct RowParser {
+
+  /// Layout of the file.
+  options: CsvOptions,
+
+  /// Name of each class.
+  labels: Vec<String>,
+
+  /// Column names once the header was read.
+  header: Option<Vec<String>>,
+
+  /// Position of the label column, resolved from the first row.
+  label_index: Option<usize>,
+
+  /// Number of features, taken from the first row.
+  features: Option<usize>,
+
+  /// Number of the last line parsed.
+  line: usize,
+
+}
+
+impl RowParser {
+
+  /// @param options Layout of the file
+  /// @param labels Name of each class
+  /// @return New RowParser instance
+  pub fn new (options: CsvOptions, labels: Vec<String>) -> Self {
+    RowParser { options, labels, header: None, label_index: None, features: None, line: 0 }
+  }
+
+  /// Starts over at the first line, e.g. for another pass over the file.
+  pub fn restart (&mut self) {
+    self.header = None;
+    self.line = 0;
+  }
+
+  /// @return Number of features once the first row was parsed
+  pub fn features (&self) -> Option<usize> {
+    self.features
+  }
+
+  /// @return Name of each class
+  pub fn labels (&self) -> &[String] {
+    &self.labels
+  }
+
+  /// @return Layout of the file
+  pub fn options (&self) -> &CsvOptions {
+    &self.options
+  }
+
+  /// @param line Next line of the file without its ending
+  /// @return Sample of the line, None for blank, comment and header lines
+  pub fn parse (&mut self, line: &str) -> Result<Option<Sample>> {
+    let row = match self.fields(line)? {
+      Some(row) => row,
+      None => return Ok(None),
+    };
+
+    let (label_index, features) = match (self.label_index, self.features) {
+      (Some(label_index), Some(features)) => (label_index, features),
+      _ => unreachable!("fields resolves the label column"),
+    };
+
+    check_fields(&row, features)?;
+
+    let label = &row[label_index];
+    let class = class_of(&self.labels, &label.text)
+      .ok_or_else(|| parse_error(label, &format!("unknown label '{}'", label.text)))?;
+
+    let mut values = Vec::with_capacity(features);
+    for (index, field) in row.iter().enumerate() {
+      if index != label_index {
+        values.push(number(field)? / self.options.scale);
+      }
+    }
+
+    Ok(Some(Sample { class, features: values }))
+  }
+
+  /// @param line Next line of the file without its ending
+  /// @return Label of the line, None for blank, comment and header lines
+  pub fn label (&mut self, line: &str) -> Result<Option<String>> {
+    let mut row = match self.fields(line)? {
+      Some(row) => row,
+      None => return Ok(None),
+    };
+
+    let (label_index, features) = match (self.label_index, self.features) {
+      (Some(label_index), Some(features)) => (label_index, features),
+      _ => unreachable!("fields resolves the label column"),
+    };
+    check_fields(&row, features)?;
+
+    Ok(Some(row.swap_remove(label_index).text))
+  }
+
+  /// Splits a line into fields, skipping lines without a sample.
+  fn fields (&mut self, line: &str) -> Result<Option<Vec<Field>>> {
+    self.line += 1;
+    let trimmed = line.trim();
+
+    if trimmed.is_empty() || self.options.comment.is_some_and(|c| trimmed.starts_with(c)) {
+      return Ok(None);
+    }
+
+    let row = split_line(line, self.line, self.options.delimiter)?;
+
+    if self.options.header && self.header.is_none() {
+      self.header = Some(row.into_iter().map(|field| field.text).collect());
+      return Ok(None);
+    }
+
+    if self.label_index.is_none() {
+      let features = row.len() - 1;
+
+      if let Some(shape) = self.options.shape {
+        if shape.len() != features {
+          return Err(Error::Invalid(format!(
+            "Shape {} needs {} features but rows have {}.", shape, shape.len(), features,
+          )));
+        }
+      }
+
+      self.label_index = Some(label_index(&self.options.label_column, self.header.as_ref(), &row)?);
+      self.features = Some(features);
+    }
+
+    Ok(Some(row))
+  }
+
+}
+
+/// Splits the text into rows of fields, skipping blank and comment lines and
+/// the header.
+///
+/// @param source Text of the file
+/// @param options Layout of the file
+/// @return Tuple in format (header, rows)
+fn read_rows (
+  source: &str,
+  options: &CsvOptions,
+) -> Result<Rows> {
+  let mut header = None;
+  let mut rows = Vec::new();
+
+  // Lines iterator strips both \n and \r\n endings.
+  for (index, line) in source.lines().enumerate() {
+    let trimmed = line.trim();
+
+    if trimmed.is_empty() || options.comment.is_some_and(|c| trimmed.starts_with(c)) {
+      continue;
+    }
+
+    let fields = split_line(line, index + 1, options.delimiter)?;
+
+    if options.header && header.is_none() {
+      header = Some(fields.into_iter().map(|field| field.text).collect());
+    } else {
+      rows.push(fields);
+    }
+  }
+
+  Ok((header, rows))
+}
+
+/// Splits a line into fields. Fields can be enclosed in double quotes to
+/// contain the delimiter, a double quote inside is written twice.
+///
+/// @param line Line without its ending
+/// @param number Line number for errors
+/// @param delimiter Character separating the fields
+/// @return Fields of the line
+fn split_line (line: &str, number: usize, delimiter: char) -> Result<Vec<Field>> {
+  let mut fields = Vec::new();
+  let mut chars = line.chars().enumerate().peekable();
+
+  loop {
+    // Skips spaces in front of the field.
+    while let Some(&(_, ' ')) = chars.peek() {
+      if delimiter == ' ' {
+        break;
+      }
+      chars.next();
+    }
+
+    let column = chars.peek().map_or(line.chars().count(), |&(column, _)| column) + 1;
+    let mut text = String::new();
+
+    if let Some(&(_, '"')) = chars.peek() {
+      chars.next();
+
+      loop {
+        match chars.next() {
+          None => return Err(Error::Parse {
+            line: number,
+            column,
+            message: "unterminated quoted field".to_string(),
+          }),
+          Some((_, '"')) => {
+            if let Some(&(_, '"')) = chars.peek() {
+              chars.next();
+              text.push('"');
+            } else {
+              break;
+            }
+          },
+          Some((_, character)) => text.push(character),
+        }
+      }
+
+      // Only spaces may follow the closing quote.
+      while let Some(&(position, character)) = chars.peek() {
+        if character == delimiter {
+          break;
+        }
+        if character != ' ' {
+          return Err(Error::Parse {
+            line: number,
+            column: position + 1,
+            message: "unexpected character after quoted field".to_string(),
+          });
+        }
+        chars.next();
+      }
+    } else {
+      while let Some(&(_, character)) = chars.peek() {
+        if character == delimiter {
+          break;
+        }
+        text.push(character);
+        chars.next();
+      }
+
+      let length = text.trim_end().len();
+      text.truncate(length);
+    }
+
+    fields.push(Field { line: number, column, text });
+
+    match chars.next() {
+      Some(_) => continue,
+      None => return Ok(fields),
+    }
+  }
+}
+
+/// Finds the position of the label column.
+///
+/// @param label_column Which column holds the label
+/// @param header Column names, if the file has a header
+/// @param row First row, used to resolve the last column
+/// @return Position of the label column in the rows
+fn label_index (
+  label_column: &LabelColumn,
+  header: Option<&Vec<String>>,
+  row: &[Field],
+) -> Result<usize> {
+  let index = match *label_column {
+    LabelColumn::First => 0,
+    LabelColumn::Last => row.len() - 1,
+    LabelColumn::Index(index) => index,
+    LabelColumn::Name(ref name) => header
+      .ok_or_else(|| Error::Invalid(
+        format!("Label column '{}' needs a header row.", name),
+      ))?
+      .iter()
+      .position(|column| column == name)
+      .ok_or_else(|| Error::Invalid(format!("Header has no column '{}'.", name)))?,
+  };
+
+  if index >= row.len() || row.len() < 2 {
+    return Err(parse_error(&row[0], &format!(
+      "label column {} does not exist in a row with {} fields", index, row.len(),
+    )));
+  }
+
+  Ok(index)
+}
+
+/// Builds the label names when none are known upfront: whole numbers are
+/// class indices, other labels are sorted. Numbers far beyond the count of
+/// distinct labels, such as IDs, are sorted names too.
+///
+/// @param labels Label of each row
+/// @return Name of each class
+pub fn infer_labels<'a, I: Iterator<Item = &'a str>> (labels: I) -> Vec<String> {
+  let distinct: BTreeSet<&str> = labels.collect();
+
+  let numbers: Option<Vec<usize>> = distinct.iter()
+    .map(|label| label.parse::<usize>().ok())
+    .collect();
+
+  let max = numbers
+    .and_then(|numbers| numbers.into_iter().max())
+    .filter(|&max| max < 2 * distinct.len());
+
+  match max {
+    // Whole numbers are class indices, so there are as many classes as the
+    // highest one plus one.
+    Some(max) => (0..=max).map(|class| class.to_string()).collect(),
+    None => distinct.into_iter().map(str::to_string).collect(),
+  }
+}
+
+/// Looks up the class index of a label. Numbered labels also match when
+/// written differently, e.g. "07" is class "7".
+///
+/// @param labels Name of each class
+/// @param label Label to look up
+/// @return Class index if the label is known
+fn class_of (labels: &[String], label: &str) -> Option<usize> {
+  labels.iter()
+    .position(|name| name == label)
+    .or_else(|| {
+      let number = label.parse::<usize>().ok()?;
+
+      labels.get(number).filter(|name| **name == number.to_string()).map(|_| number)
+    })
+}
+
+/// @param field Field to parse
+/// @return Field as a finite number
+fn number (field: &Field) -> Result<f64> {
+  field.text.parse::<f64>()
+    .ok()
+    .filter(|value| value.is_finite())
+    .ok_or_else(|| parse_error(field, &format!("'{}' is not a finite number", field.text)))
+}
+
+/// @param row Fields of a row, never empty
+/// @param features Number of features every row has
+/// @return Error pointing at the last field unless the row has a label and
+///         all features
+fn check_fields (row: &[Field], features: usize) -> Result<()> {
+  if row.len() != features + 1 {
+    return Err(parse_error(&row[row.len() - 1], &format!(
+      "expected {} fields, found {}", features + 1, row.len(),
+    )));
+  }
+
+  Ok(())
+}
+
+/// @param field Field the error is in
+/// @param message What is wrong
+/// @return Error pointing at the field
+fn parse_error (field: &Field, message: &str) -> Error {
+  Error::Parse { line: field.line, column: field.column, message: message.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+  use dataset::Dataset;
+  use super::{infer_labels, read_csv, CsvOptions, LabelColumn};
+
+  #[test]
+  fn reads_quoted_labels_with_header() {
+    let source = "# animals\r\nname;x;y\r\n\"dog; big\";1;2\r\n\r\ncat;3;\"4\"\r\n";
+    let options = CsvOptions {
+      delimiter: ';',
+      header: true,
+      label_column: LabelColumn::First,
+      comment: Some('#'),
+      ..CsvOptions::default()
+    };
+
+    let dataset = read_csv(source, &options, None).unwrap();
+
+    assert_eq!(dataset.labels(), &["cat".to_string(), "dog; big".to_string()]);
+    assert_eq!(dataset.sample(0), (1, &[1_f64, 2_f64][..]));
+    assert_eq!(dataset.sample(1), (0, &[3_f64, 4_f64][..]));
+  }
+
+  #[test]
+  fn guards_against_hostile_values() {
+    assert!(read_csv("1,NaN,a\n", &CsvOptions::default(), None).is_err());
+    assert!(read_csv("1,inf,a\n", &CsvOptions::default(), None).is_err());
+    assert!(read_csv("1,2,0\n3,1\n", &CsvOptions::default(), None).is_err());
+
+    assert_eq!(infer_labels(["2", "0"].iter().cloned()), vec!("0", "1", "2"));
+    assert_eq!(infer_labels(["100000000000", "7"].iter().cloned()), vec!("100000000000", "7"));
+  }
+}
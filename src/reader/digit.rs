@@ -1,4 +1,7 @@
+use super::sample::{Format, Sample, Shape};
 
+/// Digit from the UCI optdigits data set: 8x8 grid of 4x4 block counts
+/// scaled to range 0 to 1 and a class between 0 and 9.
 pub struct Digit {
   pub class: u8,
   pub grid: Vec<f64>
@@ -12,4 +15,20 @@ impl Digit {
 
     Digit { class, grid }
   }
+
+  /// Format of the optdigits samples, an 8x8 grayscale image with 10 classes.
+  ///
+  /// @return Format of the digits
+  pub fn format() -> Format {
+    Format::image(
+      Shape::new(8, 8, 1),
+      (0..10).map(|class: u8| class.to_string()).collect(),
+    )
+  }
+}
+
+impl From<Digit> for Sample {
+  fn from(digit: Digit) -> Sample {
+    Sample { class: usize::from(digit.class), features: digit.grid }
+  }
 }
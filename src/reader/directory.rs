@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use dataset::Dataset;
+use dataset::in_memory::InMemory;
+use error::{Error, Result};
+use image::Image;
+use super::sample::{Format, Shape};
+
+/// How images are turned into samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageOptions {
+
+  /// Every image is converted and resized to this shape.
+  pub shape: Shape,
+
+  /// Swap dark and light, for dark ink on white paper.
+  pub invert: bool,
+
+  /// Stretch intensities of each image to the whole range 0 to 1.
+  pub stretch: bool,
+
+}
+
+impl ImageOptions {
+
+  /// @param shape Shape of the samples
+  /// @return Options that only resize the images
+  pub fn new (shape: Shape) -> Self {
+    ImageOptions { shape, invert: false, stretch: false }
+  }
+
+  /// Converts an image into the features of a sample.
+  ///
+  /// @param image Decoded image
+  /// @return Features of the sample
+  pub fn features (&self, image: &Image) -> Result<Vec<f64>> {
+    let mut image = if self.invert { image.invert() } else { image.clone() };
+
+    if self.stretch {
+      image = image.stretch();
+    }
+
+    image.features(&self.shape)
+  }
+
+}
+
+/// File left out of a data set and the reason why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+  pub path: PathBuf,
+  pub reason: String,
+}
+
+/// Reads a data set from a directory with one subdirectory of images per
+/// class, such as root/cat/1.png. Classes are named after the
+/// subdirectories and ordered by name. Files that are not in a class
+/// directory or cannot be decoded are skipped rather than failing the whole
+/// data set, hidden files are ignored.
+///
+/// @param root Directory with the class directories
+/// @param options How images are turned into samples
+/// @param known_labels Label names, e.g. of the training data or a model.
+///        Directories of other classes are skipped.
+/// @return Data set of the images and the files that were skipped
+pub fn read_directory (
+  root: &Path,
+  options: &ImageOptions,
+  known_labels: Option<&[String]>,
+) -> Result<(InMemory, Vec<Skipped>)> {
+  let mut skipped = Vec::new();
+  let mut classes: Vec<(String, PathBuf)> = Vec::new();
+
+  for path in entries(root)? {
+    match path.file_name().and_then(|name| name.to_str()) {
+      Some(name) if path.is_dir() => classes.push((name.to_string(), path.clone())),
+      _ => skipped.push(Skipped { path, reason: "not in a class directory".to_string() }),
+    }
+  }
+
+  let labels: Vec<String> = match known_labels {
+    Some(labels) => labels.to_vec(),
+    None => classes.iter().map(|(name, _)| name.clone()).collect(),
+  };
+
+  let mut dataset = InMemory::with_format(&Format::image(options.shape, labels));
+
+  for (name, directory) in classes {
+    let class = match dataset.labels().iter().position(|label| *label == name) {
+      Some(class) => class,
+      None => {
+        skipped.push(Skipped { path: directory, reason: "unknown class".to_string() });
+        continue;
+      },
+    };
+
+    for path in entries(&directory)? {
+      let features = if path.is_dir() {
+        Err(Error::Invalid("nested directory".to_string()))
+      } else {
+        fs::read(&path)
+          .map_err(Error::from)
+          .and_then(|bytes| Image::decode(&bytes))
+          .and_then(|image| options.features(&image))
+      };
+
+      match features {
+        Ok(features) => dataset.push(class, &features),
+        Err(error) => skipped.push(Skipped { path, reason: error.to_string() }),
+      }
+    }
+  }
+
+  Ok((dataset, skipped))
+}
+
+/// @param directory Directory to list
+/// @return Paths of the visible entries ordered by name
+fn entries (directory: &Path) -> Result<Vec<PathBuf>> {
+  let mut paths = Vec::new();
+
+  for entry in fs::read_dir(directory)? {
+    let entry = entry?;
+
+    if !entry.file_name().to_string_lossy().starts_with('.') {
+      paths.push(entry.path());
+    }
+  }
+
+  paths.sort();
+
+  Ok(paths)
+}
@@ -0,0 +1,152 @@
+use dataset::Dataset;
+use dataset::in_memory::InMemory;
+use error::{Error, Result};
+use super::sample::{Format, Shape};
+
+/// Array read from an IDX file, the binary format of the MNIST and
+/// Fashion-MNIST data sets.
+pub struct Idx {
+
+  /// Size of each dimension, the first one is the number of items.
+  pub dimensions: Vec<usize>,
+
+  /// Values of the array in row major order.
+  pub data: Vec<u8>,
+
+}
+
+/// Type code of unsigned bytes, the only element type MNIST uses.
+const UNSIGNED_BYTE: u8 = 0x08;
+
+/// Parses an IDX file. The file starts with a magic number of two zero bytes,
+/// the element type and the number of dimensions, followed by the size of
+/// each dimension as big endian 32 bit integers and the data itself.
+///
+/// @param bytes Content of the file
+/// @return Parsed array
+pub fn read_idx(bytes: &[u8]) -> Result<Idx> {
+  if bytes.starts_with(&[0x1f, 0x8b]) {
+    return Err(Error::Invalid(
+      "File is gzip compressed, decompress it first.".to_string(),
+    ));
+  }
+
+  if bytes.len() < 4 || bytes[0] != 0 || bytes[1] != 0 {
+    return Err(Error::Invalid("Not an IDX file, bad magic number.".to_string()));
+  }
+
+  if bytes[2] != UNSIGNED_BYTE {
+    return Err(Error::Invalid(format!(
+      "Unsupported IDX element type 0x{:02x}, only unsigned bytes are supported.",
+      bytes[2],
+    )));
+  }
+
+  let count = usize::from(bytes[3]);
+  let header = 4 + 4 * count;
+
+  if bytes.len() < header {
+    return Err(Error::Invalid("IDX header is truncated.".to_string()));
+  }
+
+  let dimensions: Vec<usize> = bytes[4..header].chunks(4)
+    .map(|size| u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize)
+    .collect();
+
+  let length = dimensions.iter().try_fold(1_usize, |length, &size| length.checked_mul(size));
+
+  if length != Some(bytes.len() - header) {
+    return Err(Error::Invalid(format!(
+      "IDX data has {} bytes, dimensions {:?} need {}.",
+      bytes.len() - header, dimensions,
+      length.map_or("more than fit in memory".to_string(), |length| length.to_string()),
+    )));
+  }
+
+  Ok(Idx { dimensions, data: bytes[header..].to_vec() })
+}
+
+/// Builds a data set from an IDX image file and an IDX label file. Pixels
+/// are scaled from 0..255 to 0..1 and the samples keep the image shape, e.g.
+/// 28x28 for MNIST.
+///
+/// @param images Content of the images file, dimensions (count, rows, columns)
+/// @param labels Content of the labels file, dimensions (count)
+/// @param known_labels Label names, by default classes are numbered
+/// @return Data set with the images
+pub fn read_mnist(
+  images: &[u8],
+  labels: &[u8],
+  known_labels: Option<&[String]>,
+) -> Result<InMemory> {
+  let images = read_idx(images)?;
+  let labels = read_idx(labels)?;
+
+  let (count, height, width) = match images.dimensions[..] {
+    [count, height, width] => (count, height, width),
+    _ => return Err(Error::Invalid(format!(
+      "Images must have 3 dimensions, found {:?}.", images.dimensions,
+    ))),
+  };
+
+  if labels.dimensions != [count] {
+    return Err(Error::Invalid(format!(
+      "Expected {} labels for {} images, found dimensions {:?}.",
+      count, count, labels.dimensions,
+    )));
+  }
+
+  let names: Vec<String> = match known_labels {
+    Some(names) => names.to_vec(),
+    None => {
+      let classes = labels.data.iter().max().map_or(0, |max| usize::from(*max) + 1);
+
+      (0..classes).map(|class| class.to_string()).collect()
+    },
+  };
+
+  let mut dataset = InMemory::with_format(&Format::image(Shape::new(width, height, 1), names));
+  let pixels = width * height;
+  let mut features = Vec::with_capacity(pixels);
+
+  for (index, &label) in labels.data.iter().enumerate() {
+    if usize::from(label) >= dataset.classes() {
+      return Err(Error::Invalid(format!(
+        "Label {} of image {} is not a known class.", label, index + 1,
+      )));
+    }
+
+    features.clear();
+    features.extend(
+      images.data[index * pixels..(index + 1) * pixels].iter()
+        .map(|&pixel| f64::from(pixel) / 255_f64)
+    );
+
+    dataset.push(usize::from(label), &features);
+  }
+
+  Ok(dataset)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_mnist_images_and_labels() {
+    let images = [
+      0, 0, 8, 3, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2,
+      0, 255, 51, 0,
+    ];
+    let labels = [0, 0, 8, 1, 0, 0, 0, 2, 3, 1];
+
+    let dataset = read_mnist(&images, &labels, None).unwrap();
+
+    assert_eq!(dataset.len(), 2);
+    assert_eq!(dataset.classes(), 4);
+    assert_eq!(dataset.shape(), Some(Shape::new(2, 1, 1)));
+    assert_eq!(dataset.sample(0), (3, &[0_f64, 1_f64][..]));
+    assert_eq!(dataset.sample(1), (1, &[0.2_f64, 0_f64][..]));
+    assert!(read_mnist(&[0x1f, 0x8b, 8, 0], &labels, None).is_err());
+  }
+}
@@ -1,23 +1,156 @@
 pub mod digit;
+pub mod sample;
+pub mod csv;
+pub mod idx;
+pub mod bitmap;
+pub mod directory;
+pub mod cache;
+pub mod stream;
 
+use std::fs;
+use std::path::Path;
+use self::csv::CsvOptions;
+use self::directory::ImageOptions;
 use self::digit::Digit;
+use self::sample::{Format, Sample};
+use dataset::Dataset;
+use dataset::in_memory::InMemory;
+use error::{Error, Result};
 
-pub fn read_digits(source: &str) -> Vec<Digit> {
-  source.split("\n")
-    .filter_map(|line| {
-      if line.len() == 0 {
-        return None;
+/// Kind of data files a data set can be loaded from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataFormat {
+
+  /// Delimited text with one sample per row.
+  Csv(CsvOptions),
+
+  /// IDX image file with a separate IDX label file, as used by MNIST.
+  Idx,
+
+  /// Original optdigits bitmaps, averaged over square blocks of given size.
+  /// Blocks of 1 keep the full resolution.
+  Bitmap { block: usize },
+
+  /// Directory with a directory of images for each class.
+  Images(ImageOptions),
+
+  /// Binary cache written by the convert command.
+  Cache,
+
+}
+
+impl DataFormat {
+
+  /// @return Whether the format keeps labels in a separate file
+  pub fn needs_labels_file(&self) -> bool {
+    matches!(*self, DataFormat::Idx)
+  }
+
+}
+
+/// Loads a labelled data set from files of given format. Errors name the
+/// file they come from. Images that cannot be read are reported on the
+/// standard error and left out.
+///
+/// @param path Path to the data file
+/// @param labels_path Path to the labels file for formats that need one
+/// @param format Kind of the files
+/// @param known_labels Label names, e.g. of the training data or a model
+/// @return Data set with the samples
+pub fn load_dataset(
+  path: &Path,
+  labels_path: Option<&Path>,
+  format: &DataFormat,
+  known_labels: Option<&[String]>,
+) -> Result<InMemory> {
+  match *format {
+    DataFormat::Csv(ref options) => {
+      let source = fs::read_to_string(path).map_err(|error| in_file(path, error.into()))?;
+
+      csv::read_csv(&source, options, known_labels).map_err(|error| in_file(path, error))
+    },
+    DataFormat::Idx => {
+      let labels_path = labels_path.ok_or_else(|| Error::Invalid(format!(
+        "Images {} need a labels file.", path.display(),
+      )))?;
+
+      let images = fs::read(path).map_err(|error| in_file(path, error.into()))?;
+      let labels = fs::read(labels_path).map_err(|error| in_file(labels_path, error.into()))?;
+
+      idx::read_mnist(&images, &labels, known_labels).map_err(|error| in_file(path, error))
+    },
+    DataFormat::Bitmap { block } => {
+      let source = fs::read_to_string(path).map_err(|error| in_file(path, error.into()))?;
+      let bitmaps = bitmap::read_bitmaps(&source, known_labels)
+        .map_err(|error| in_file(path, error))?;
+
+      match block {
+        1 => Ok(bitmaps),
+        _ => bitmap::downsample(&bitmaps, block).map_err(|error| in_file(path, error)),
+      }
+    },
+    DataFormat::Images(ref options) => {
+      let (dataset, skipped) = directory::read_directory(path, options, known_labels)
+        .map_err(|error| in_file(path, error))?;
+
+      for file in &skipped {
+        eprintln!("Skipped {}: {}", file.path.display(), file.reason);
       }
 
-      let mut numbers: Vec<&str> = line.split(",").collect();
+      if dataset.is_empty() {
+        return Err(Error::Invalid(format!("No images found in {}.", path.display())));
+      }
+
+      Ok(dataset)
+    },
+    DataFormat::Cache => cache::load_cache(path, known_labels).map_err(|error| in_file(path, error)),
+  }
+}
+
+/// @param path File the error comes from
+/// @param error What went wrong
+/// @return Error naming the file
+pub fn in_file(path: &Path, error: Error) -> Error {
+  Error::Invalid(format!("Cannot read {}: {}", path.display(), error))
+}
+
+pub fn read_digits(source: &str) -> Vec<Digit> {
+  read_samples(source, &Digit::format(), 16_f64)
+    .expect("Incorrect input values.")
+    .into_iter()
+    .map(|sample| Digit::new(sample.class as u8, sample.features))
+    .collect()
+}
+
+/// Reads comma separated rows of features followed by the class index. Empty
+/// lines are skipped.
+///
+/// @param source Text with one sample per line
+/// @param format What the samples should look like
+/// @param scale Number to divide each feature by
+/// @return Samples in the order they appear in
+pub fn read_samples(source: &str, format: &Format, scale: f64) -> Result<Vec<Sample>> {
+  source.lines()
+    .enumerate()
+    .filter(|(_, line)| !line.trim().is_empty())
+    .map(|(index, line)| {
+      let error = |message: String| Error::Parse { line: index + 1, column: 1, message };
+
+      let mut numbers: Vec<&str> = line.split(',').collect();
 
-      let class: u8 = numbers.pop().unwrap().trim().parse::<u8>().unwrap();
+      let class: usize = numbers.pop().unwrap().trim().parse::<usize>()
+        .map_err(|_| error("class must be a whole number".to_string()))?;
 
-      let grid: Vec<f64> = numbers.iter()
-        .map(|x| x.parse::<f64>().unwrap() / 16_f64)
-        .collect();
+      let features: Vec<f64> = numbers.iter()
+        .map(|x| x.trim().parse::<f64>().ok().filter(|x| x.is_finite()).map(|x| x / scale))
+        .collect::<Option<Vec<f64>>>()
+        .ok_or_else(|| error("features must be finite numbers".to_string()))?;
 
-      Some(Digit::new(class, grid))
+      format.sample(class, features).map_err(|error| Error::Parse {
+        line: index + 1,
+        column: 1,
+        message: error.to_string(),
+      })
     })
     .collect()
 }
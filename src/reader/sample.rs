@@ -0,0 +1,149 @@
+use std::fmt;
+use std::str::FromStr;
+use error::{Error, Result};
+
+/// Dimensions of image shaped samples. Features of such samples are stored
+/// row by row with the channels of each pixel next to each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shape {
+  pub width: usize,
+  pub height: usize,
+  pub channels: usize,
+}
+
+impl Shape {
+
+  /// @param width Number of pixels in a row
+  /// @param height Number of rows
+  /// @param channels Number of values per pixel, 1 for grayscale
+  /// @return New Shape instance
+  pub fn new (width: usize, height: usize, channels: usize) -> Self {
+    Shape { width, height, channels }
+  }
+
+  /// @return Number of features a sample of this shape has
+  pub fn len (&self) -> usize {
+    self.width * self.height * self.channels
+  }
+
+  /// @return Whether the shape has no pixels
+  pub fn is_empty (&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Guesses the shape of samples that come without one, such as rows of a
+  /// delimited file, assuming a square grayscale image.
+  ///
+  /// @param features Number of features of a sample
+  /// @return Square shape, if the number of features is a square
+  pub fn square (features: usize) -> Option<Shape> {
+    let side = (features as f64).sqrt().round() as usize;
+
+    if side * side == features && features > 0 {
+      Some(Shape::new(side, side, 1))
+    } else {
+      None
+    }
+  }
+
+}
+
+impl FromStr for Shape {
+  type Err = Error;
+
+  /// Parses shape written as WIDTHxHEIGHT or WIDTHxHEIGHTxCHANNELS.
+  fn from_str (text: &str) -> Result<Shape> {
+    let dimensions = text.split('x')
+      .map(|dimension| dimension.trim().parse::<usize>())
+      .collect::<::std::result::Result<Vec<usize>, _>>();
+
+    match dimensions.as_ref().map(|dimensions| dimensions.as_slice()) {
+      Ok(&[width, height]) => Ok(Shape::new(width, height, 1)),
+      Ok(&[width, height, channels]) => Ok(Shape::new(width, height, channels)),
+      _ => Err(Error::Invalid(format!(
+        "Invalid shape '{}', expected WIDTHxHEIGHT or WIDTHxHEIGHTxCHANNELS.", text,
+      ))),
+    }
+  }
+}
+
+impl fmt::Display for Shape {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}x{}x{}", self.width, self.height, self.channels)
+  }
+}
+
+/// One labelled sample of any size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+
+  /// Index of the class in the label set.
+  pub class: usize,
+
+  /// Input values of the sample.
+  pub features: Vec<f64>,
+
+}
+
+/// Describes what samples of a data set look like: how many features they
+/// have, which classes exist and optionally the image shape of the features.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Format {
+
+  /// Number of features of each sample.
+  pub features: usize,
+
+  /// Name of each class. The class of a sample is a position in this vector.
+  pub labels: Vec<String>,
+
+  /// Image shape of the features, if they are pixels.
+  pub shape: Option<Shape>,
+
+}
+
+impl Format {
+
+  /// Builds format of flat samples with numbered classes "0", "1", ...
+  ///
+  /// @param features Number of features of each sample
+  /// @param classes Number of classes
+  /// @return New Format instance
+  pub fn numbered (features: usize, classes: usize) -> Self {
+    Format {
+      features,
+      labels: (0..classes).map(|class| class.to_string()).collect(),
+      shape: None,
+    }
+  }
+
+  /// Builds format of image shaped samples.
+  ///
+  /// @param shape Dimensions of the images
+  /// @param labels Name of each class
+  /// @return New Format instance
+  pub fn image (shape: Shape, labels: Vec<String>) -> Self {
+    Format { features: shape.len(), labels, shape: Some(shape) }
+  }
+
+  /// Checks the sample against the format and wraps it.
+  ///
+  /// @param class Index of the class
+  /// @param features Input values
+  /// @return New Sample instance
+  pub fn sample (&self, class: usize, features: Vec<f64>) -> Result<Sample> {
+    if features.len() != self.features {
+      return Err(Error::Invalid(format!(
+        "Sample has {} features, expected {}.", features.len(), self.features,
+      )));
+    }
+
+    if class >= self.labels.len() {
+      return Err(Error::Invalid(format!(
+        "Sample has class {}, expected less than {}.", class, self.labels.len(),
+      )));
+    }
+
+    Ok(Sample { class, features })
+  }
+
+}
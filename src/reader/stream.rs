@@ -0,0 +1,271 @@
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::mem;
+use std::path::Path;
+use dataset::in_memory::InMemory;
+use dataset::stream::Stream;
+use error::{Error, Result};
+use super::csv::{self, CsvOptions, RowParser};
+use super::sample::{Format, Sample};
+
+/// Reads labelled rows of a delimited text file batch by batch, so that files
+/// larger than memory can be trained on. Rows are parsed as they are needed
+/// and every pass seeks back to the start of the file.
+pub struct CsvStream<R: BufRead + Seek> {
+
+  /// Source of the rows.
+  reader: R,
+
+  /// Turns lines into samples.
+  parser: RowParser,
+
+  /// Mixes the samples before they are batched.
+  shuffle: Option<Shuffle>,
+
+  /// Line being parsed, kept to reuse its allocation.
+  line: String,
+
+  /// Whether the pass reached the end of the file.
+  exhausted: bool,
+
+}
+
+/// Shuffles samples within a window of bounded size. Each sample read
+/// replaces a random one of the window, which is emitted in its place.
+struct Shuffle {
+  capacity: usize,
+  rng: StdRng,
+  buffer: Vec<Sample>,
+}
+
+impl CsvStream<BufReader<File>> {
+
+  /// Opens a file for streaming. Without labels, the file is scanned once
+  /// upfront to collect them.
+  ///
+  /// @param path Path to the file
+  /// @param options Layout of the file
+  /// @param labels Label names, e.g. of a model
+  /// @return New CsvStream instance
+  pub fn open<P: AsRef<Path>> (
+    path: P,
+    options: &CsvOptions,
+    labels: Option<&[String]>,
+  ) -> Result<Self> {
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path)?);
+
+    let labels = match labels {
+      Some(labels) => labels.to_vec(),
+      None => scan_labels(BufReader::new(File::open(path)?), options)
+        .map_err(|error| super::in_file(path, error))?,
+    };
+
+    Ok(CsvStream::new(reader, options, labels))
+  }
+
+}
+
+impl<R: BufRead + Seek> CsvStream<R> {
+
+  /// @param reader Source of the rows, at its start
+  /// @param options Layout of the rows
+  /// @param labels Name of each class
+  /// @return New CsvStream instance
+  pub fn new (reader: R, options: &CsvOptions, labels: Vec<String>) -> Self {
+    CsvStream {
+      reader,
+      parser: RowParser::new(options.clone(), labels),
+      shuffle: None,
+      line: String::new(),
+      exhausted: false,
+    }
+  }
+
+  /// Shuffles the samples within a window of given size. A larger window
+  /// mixes better but holds more samples in memory.
+  ///
+  /// @param capacity Number of samples in the window
+  /// @param seed Seed of the random number generator
+  /// @return Stream with shuffled samples
+  pub fn shuffled (mut self, capacity: usize, seed: u64) -> Self {
+    self.shuffle = Some(Shuffle {
+      capacity: capacity.max(1),
+      rng: StdRng::seed_from_u64(seed),
+      buffer: Vec::new(),
+    });
+
+    self
+  }
+
+  /// @return Next sample in file order, None at the end of the file
+  fn next_sample (&mut self) -> Result<Option<Sample>> {
+    while !self.exhausted {
+      self.line.clear();
+
+      if self.reader.read_line(&mut self.line)? == 0 {
+        self.exhausted = true;
+        break;
+      }
+
+      let line = self.line.trim_end_matches(&['\n', '\r'][..]);
+      if let Some(sample) = self.parser.parse(line)? {
+        return Ok(Some(sample));
+      }
+    }
+
+    Ok(None)
+  }
+
+  /// @return Next sample of the pass, None once the pass is over
+  fn next_shuffled (&mut self) -> Result<Option<Sample>> {
+    let filled = match self.shuffle {
+      Some(ref shuffle) => shuffle.buffer.len() >= shuffle.capacity,
+      None => return self.next_sample(),
+    };
+
+    if !filled {
+      while let Some(sample) = self.next_sample()? {
+        let shuffle = self.shuffle.as_mut().unwrap();
+        shuffle.buffer.push(sample);
+
+        if shuffle.buffer.len() >= shuffle.capacity {
+          break;
+        }
+      }
+    }
+
+    let incoming = self.next_sample()?;
+    let shuffle = self.shuffle.as_mut().unwrap();
+
+    if shuffle.buffer.is_empty() {
+      return Ok(incoming);
+    }
+
+    let index = shuffle.rng.gen_range(0, shuffle.buffer.len());
+
+    Ok(Some(match incoming {
+      Some(sample) => mem::replace(&mut shuffle.buffer[index], sample),
+      None => shuffle.buffer.swap_remove(index),
+    }))
+  }
+
+}
+
+impl<R: BufRead + Seek> Stream for CsvStream<R> {
+
+  fn labels (&self) -> &[String] {
+    self.parser.labels()
+  }
+
+  fn features (&self) -> Option<usize> {
+    self.parser.features()
+  }
+
+  fn rewind (&mut self) -> Result<()> {
+    self.reader.seek(SeekFrom::Start(0))?;
+    self.parser.restart();
+    self.exhausted = false;
+
+    if let Some(ref mut shuffle) = self.shuffle {
+      shuffle.buffer.clear();
+    }
+
+    Ok(())
+  }
+
+  fn next_batch (&mut self, size: usize) -> Result<Option<InMemory>> {
+    let mut samples = Vec::with_capacity(size);
+
+    while samples.len() < size.max(1) {
+      match self.next_shuffled()? {
+        Some(sample) => samples.push(sample),
+        None => break,
+      }
+    }
+
+    if samples.is_empty() {
+      return Ok(None);
+    }
+
+    let format = Format {
+      features: samples[0].features.len(),
+      labels: self.parser.labels().to_vec(),
+      shape: self.parser.options().shape,
+    };
+
+    Ok(Some(InMemory::from_samples(&format, samples)))
+  }
+
+}
+
+/// Collects the labels of all rows without keeping the rows.
+///
+/// @param reader Source of the rows
+/// @param options Layout of the rows
+/// @return Name of each class
+fn scan_labels<R: BufRead> (reader: R, options: &CsvOptions) -> Result<Vec<String>> {
+  let mut parser = RowParser::new(options.clone(), Vec::new());
+  let mut labels = BTreeSet::new();
+
+  for line in reader.lines() {
+    if let Some(label) = parser.label(&line?)? {
+      labels.insert(label);
+    }
+  }
+
+  if labels.is_empty() {
+    return Err(Error::Invalid("No samples found.".to_string()));
+  }
+
+  Ok(csv::infer_labels(labels.iter().map(String::as_str)))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+  use dataset::Dataset;
+  use super::*;
+
+  #[test]
+  fn shuffles_every_row_once_per_pass() {
+    let rows: String = (0..10).map(|row| format!("{},{}\n", row, row % 2)).collect();
+    let options = CsvOptions::default();
+    let labels = vec!("0".to_string(), "1".to_string());
+    let mut stream = CsvStream::new(Cursor::new(rows.into_bytes()), &options, labels)
+      .shuffled(4, 3);
+
+    let mut passes = Vec::new();
+    for _ in 0..2 {
+      stream.rewind().unwrap();
+
+      let mut pass = Vec::new();
+      while let Some(batch) = stream.next_batch(3).unwrap() {
+        assert!(batch.len() <= 3);
+        pass.extend(batch.iter().map(|(class, features)| features[0] as usize + 10 * class));
+      }
+      passes.push(pass);
+    }
+
+    for pass in &passes {
+      let mut sorted = pass.clone();
+      sorted.sort();
+      assert_eq!(sorted, vec!(0, 2, 4, 6, 8, 11, 13, 15, 17, 19));
+    }
+    assert_ne!(passes[0], passes[1]);
+    assert_ne!(passes[0], vec!(0, 11, 2, 13, 4, 15, 6, 17, 8, 19));
+  }
+
+  #[test]
+  fn reports_short_rows_with_their_line() {
+    let options = CsvOptions::default();
+
+    match scan_labels(Cursor::new("1,2,0\n3,1\n"), &options) {
+      Err(Error::Parse { line, .. }) => assert_eq!(line, 2),
+      other => panic!("expected parse error, got {:?}", other.map(|_| ())),
+    }
+  }
+}
@@ -0,0 +1,221 @@
+use std::fmt;
+use std::str::FromStr;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use dataset::{self, Dataset};
+use error::{Error, Result};
+
+/// How much the loss of each class counts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassWeights {
+
+  /// Inversely proportional to the number of samples of the class, so that
+  /// every class contributes to the loss alike.
+  Balanced,
+
+  /// Given weight for each class index.
+  Manual(Vec<f64>),
+
+}
+
+impl ClassWeights {
+
+  /// @param dataset Training data
+  /// @return Weight of each class of the data
+  pub fn resolve (&self, dataset: &dyn Dataset) -> Result<Vec<f64>> {
+    match *self {
+      ClassWeights::Balanced => Ok(inverse_frequency(&dataset::class_counts(dataset))),
+      ClassWeights::Manual(ref weights) if weights.len() == dataset.classes() => {
+        Ok(weights.clone())
+      },
+      ClassWeights::Manual(ref weights) => Err(Error::Invalid(format!(
+        "Got {} class weights but the data has {} classes.", weights.len(), dataset.classes(),
+      ))),
+    }
+  }
+
+}
+
+impl FromStr for ClassWeights {
+  type Err = Error;
+
+  /// Parses "balanced" or weights separated by commas.
+  fn from_str (text: &str) -> Result<ClassWeights> {
+    if text == "balanced" {
+      return Ok(ClassWeights::Balanced);
+    }
+
+    text.split(',')
+      .map(|weight| weight.trim().parse::<f64>().ok().filter(|weight| *weight >= 0_f64))
+      .collect::<Option<Vec<f64>>>()
+      .map(ClassWeights::Manual)
+      .ok_or_else(|| Error::Invalid(format!(
+        "Invalid class weights '{}', use balanced or non negative numbers.", text,
+      )))
+  }
+}
+
+impl fmt::Display for ClassWeights {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      ClassWeights::Balanced => write!(f, "balanced"),
+      ClassWeights::Manual(ref weights) => write!(
+        f,
+        "{}",
+        weights.iter().map(|weight| weight.to_string()).collect::<Vec<String>>().join(","),
+      ),
+    }
+  }
+}
+
+/// Weights each class by samples / (classes * class_samples), which is 1 for
+/// every class of balanced data. Classes without samples get 1.
+///
+/// @param counts Number of samples of each class
+/// @return Weight of each class
+pub fn inverse_frequency (counts: &[usize]) -> Vec<f64> {
+  let total: usize = counts.iter().sum();
+
+  counts.iter()
+    .map(|&count| if count == 0 {
+      1_f64
+    } else {
+      total as f64 / (counts.len() * count) as f64
+    })
+    .collect()
+}
+
+/// How the samples of each epoch are drawn to even out the classes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+
+  /// Every class is drawn as often as the largest one, samples of smaller
+  /// classes repeat.
+  Oversample,
+
+  /// Every class is drawn as often as the smallest one, each epoch leaves
+  /// out different samples of larger classes.
+  Undersample,
+
+}
+
+impl FromStr for Sampling {
+  type Err = Error;
+
+  fn from_str (text: &str) -> Result<Sampling> {
+    match text {
+      "oversample" => Ok(Sampling::Oversample),
+      "undersample" => Ok(Sampling::Undersample),
+      _ => Err(Error::Invalid(format!(
+        "Unknown sampling '{}', use oversample or undersample.", text,
+      ))),
+    }
+  }
+}
+
+impl fmt::Display for Sampling {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Sampling::Oversample => write!(f, "oversample"),
+      Sampling::Undersample => write!(f, "undersample"),
+    }
+  }
+}
+
+/// Draws a class balanced order of the training samples for each epoch.
+#[derive(Clone)]
+pub struct Sampler {
+
+  /// How the classes are evened out.
+  sampling: Sampling,
+
+  /// Draws the samples and shuffles them.
+  rng: StdRng,
+
+}
+
+impl Sampler {
+
+  /// Builds sampler whose epochs differ between runs.
+  ///
+  /// @param sampling How the classes are evened out
+  /// @return New Sampler instance
+  pub fn new (sampling: Sampling) -> Self {
+    Sampler { sampling, rng: StdRng::from_entropy() }
+  }
+
+  /// Builds sampler that draws the same epochs for every run with the same
+  /// seed.
+  ///
+  /// @param sampling How the classes are evened out
+  /// @param seed Seed of the draws
+  /// @return New Sampler instance
+  pub fn seeded (sampling: Sampling, seed: u64) -> Self {
+    Sampler { sampling, rng: StdRng::seed_from_u64(seed) }
+  }
+
+  /// Draws the samples of one epoch. Classes without samples are left out.
+  /// The order is shuffled, since drawing class by class would otherwise
+  /// fill batches with a single class.
+  ///
+  /// @param dataset Training data
+  /// @return Indices of the samples in the order to train on them
+  pub fn epoch (&mut self, dataset: &dyn Dataset) -> Vec<usize> {
+    let mut classes: Vec<Vec<usize>> = vec!(Vec::new(); dataset.classes());
+    for index in 0..dataset.len() {
+      let (class, _) = dataset.sample(index);
+
+      if class >= classes.len() {
+        classes.resize(class + 1, Vec::new());
+      }
+      classes[class].push(index);
+    }
+    classes.retain(|indices| !indices.is_empty());
+
+    let sizes = classes.iter().map(Vec::len);
+    let size = match self.sampling {
+      Sampling::Oversample => sizes.max().unwrap_or(0),
+      Sampling::Undersample => sizes.min().unwrap_or(0),
+    };
+
+    let mut order = Vec::with_capacity(size * classes.len());
+    for mut indices in classes {
+      indices.shuffle(&mut self.rng);
+
+      // Every sample is drawn once before any of them repeats.
+      for draw in 0..size {
+        order.push(indices[draw % indices.len()]);
+      }
+    }
+    order.shuffle(&mut self.rng);
+
+    order
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{inverse_frequency, Sampler, Sampling};
+  use dataset::in_memory::InMemory;
+
+  #[test]
+  fn evens_out_skewed_classes() {
+    assert_eq!(inverse_frequency(&[6, 2, 0]), vec!(8_f64 / 18_f64, 8_f64 / 6_f64, 1_f64));
+
+    let mut data = InMemory::numbered(1, 2);
+    for (class, count) in [(0, 6), (1, 2)] {
+      for _ in 0..count {
+        data.push(class, &[0_f64]);
+      }
+    }
+
+    for (sampling, size) in [(Sampling::Oversample, 6), (Sampling::Undersample, 2)] {
+      let order = Sampler::seeded(sampling, 1).epoch(&data);
+      let minority = order.iter().filter(|&&index| index >= 6).count();
+
+      assert_eq!(order.len(), 2 * size);
+      assert_eq!(minority, size);
+    }
+  }
+}
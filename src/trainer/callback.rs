@@ -0,0 +1,147 @@
+use std::time::Duration;
+use neural_network::network::Network;
+
+/// Statistics of one processed batch.
+pub struct BatchStats {
+
+  /// Index of the epoch the batch belongs to.
+  pub epoch: usize,
+
+  /// Index of the batch within the epoch.
+  pub batch: usize,
+
+  /// Number of samples in the batch.
+  pub samples: usize,
+
+  /// Mean loss of the samples in the batch.
+  pub loss: f64,
+
+}
+
+/// Statistics of one finished epoch.
+pub struct EpochStats {
+
+  /// Index of the epoch starting at 0.
+  pub epoch: usize,
+
+  /// Learning rate used throughout the epoch.
+  pub learning_rate: f64,
+
+  /// Mean loss over all training samples.
+  pub loss: f64,
+
+  /// Ratio of training samples the network classified correctly while being
+  /// trained on them.
+  pub accuracy: f64,
+
+  /// Ratio of validation samples classified correctly after the epoch, if the
+  /// trainer was given a validation set.
+  pub validation_accuracy: Option<f64>,
+
+  /// Euclidean norm of the mean batch gradient of each layer, averaged over
+  /// all batches of the epoch.
+  pub gradient_norms: Vec<f64>,
+
+  /// How long the epoch took.
+  pub duration: Duration,
+
+}
+
+/// Tells the trainer whether it should carry on with the next epoch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+  Continue,
+  Stop,
+}
+
+/// Hooks into the training loop. All methods have an empty default
+/// implementation so that a callback only implements what it cares about.
+pub trait Callback {
+
+  /// Called before the first batch of each epoch.
+  ///
+  /// @param epoch Index of the epoch
+  /// @param learning_rate Learning rate for the epoch
+  fn on_epoch_start (&mut self, _epoch: usize, _learning_rate: f64) {}
+
+  /// Called after the updates of a batch have been written into the network.
+  ///
+  /// @param network Network with the new weights
+  fn on_commit (&mut self, _network: &Network) {}
+
+  /// Called after each batch.
+  ///
+  /// @param stats Statistics of the batch
+  fn on_batch_end (&mut self, _stats: &BatchStats) {}
+
+  /// Called after each epoch. Returning Flow::Stop ends the training.
+  ///
+  /// @param stats Statistics of the epoch
+  /// @param network Network after the epoch
+  /// @return Whether the training should continue
+  fn on_epoch_end (&mut self, _stats: &EpochStats, _network: &Network) -> Flow {
+    Flow::Continue
+  }
+
+}
+
+/// Prints a line with loss and accuracy after every epoch.
+pub struct Progress;
+
+impl Callback for Progress {
+
+  fn on_epoch_end (&mut self, stats: &EpochStats, _network: &Network) -> Flow {
+    println!(
+      "Epoch {}: learning rate {:.4}, loss {:.6}, accuracy {:.4} in {:?}.",
+      stats.epoch, stats.learning_rate, stats.loss, stats.accuracy, stats.duration,
+    );
+
+    Flow::Continue
+  }
+
+}
+
+/// Stops the training once the epoch loss has not improved by at least
+/// min_delta for patience epochs in a row.
+pub struct EarlyStopping {
+
+  /// How many epochs without improvement are tolerated.
+  pub patience: usize,
+
+  /// Smallest decrease of loss that counts as an improvement.
+  pub min_delta: f64,
+
+  /// Lowest loss seen so far.
+  best: Option<f64>,
+
+  /// Epochs since the last improvement.
+  waited: usize,
+
+}
+
+impl EarlyStopping {
+
+  /// @param patience How many epochs without improvement are tolerated
+  /// @param min_delta Smallest decrease of loss that counts as an improvement
+  /// @return New EarlyStopping instance
+  pub fn new (patience: usize, min_delta: f64) -> Self {
+    EarlyStopping { patience, min_delta, best: None, waited: 0 }
+  }
+
+}
+
+impl Callback for EarlyStopping {
+
+  fn on_epoch_end (&mut self, stats: &EpochStats, _network: &Network) -> Flow {
+    match self.best {
+      Some(best) if best - stats.loss < self.min_delta => self.waited += 1,
+      _ => {
+        self.best = Some(stats.loss);
+        self.waited = 0;
+      },
+    }
+
+    if self.waited > self.patience { Flow::Stop } else { Flow::Continue }
+  }
+
+}
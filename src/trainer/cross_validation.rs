@@ -0,0 +1,89 @@
+use dataset::Dataset;
+use dataset::subset::Subset;
+use error::Result;
+use neural_network::network::Network;
+use preprocess::Pipeline;
+use super::{argmax, loss};
+
+/// How a network trained without one fold did on that fold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoldScore {
+
+  /// Index of the held out fold.
+  pub fold: usize,
+
+  /// Number of samples the network was trained on.
+  pub train_samples: usize,
+
+  /// Number of held out samples.
+  pub test_samples: usize,
+
+  /// Ratio of held out samples classified correctly.
+  pub accuracy: f64,
+
+  /// Mean loss over the held out samples.
+  pub loss: f64,
+
+}
+
+/// Trains a fresh network for each fold on all other folds and scores it on
+/// the held out fold.
+///
+/// @param dataset Data set the folds divide
+/// @param folds Positions of the samples in each fold, see `split::folds`
+/// @param train Fits the preprocessing and trains a new network on given
+///        data, called with the index of the held out fold
+/// @return Score of each fold, or the first error of the training
+pub fn cross_validate<F> (dataset: &dyn Dataset, folds: &[Vec<usize>], mut train: F) -> Result<Vec<FoldScore>>
+  where F: FnMut(usize, &dyn Dataset) -> Result<(Pipeline, Network)>
+{
+  folds.iter()
+    .enumerate()
+    .map(|(fold, held_out)| {
+      let rest: Vec<usize> = folds.iter()
+        .enumerate()
+        .filter(|&(other, _)| other != fold)
+        .flat_map(|(_, indices)| indices.iter().cloned())
+        .collect();
+
+      let training_data = Subset::new(dataset, rest);
+      let test_data = Subset::new(dataset, held_out.clone());
+      let (pipeline, network) = train(fold, &training_data)?;
+
+      let (correct, total_loss) = test_data.iter()
+        .fold((0, 0_f64), |(correct, total_loss), (target, inputs)| {
+          let outputs = network.compute(&pipeline.apply(inputs));
+          let hit = if argmax(&outputs) == target { 1 } else { 0 };
+
+          (correct + hit, total_loss + loss(target, &outputs))
+        });
+
+      let samples = test_data.len().max(1) as f64;
+
+      Ok(FoldScore {
+        fold,
+        train_samples: training_data.len(),
+        test_samples: test_data.len(),
+        accuracy: correct as f64 / samples,
+        loss: total_loss / samples,
+      })
+    })
+    .collect()
+}
+
+/// @param values Values of a sample
+/// @return Tuple in format (mean, standard_deviation) using the sample
+///         standard deviation, 0 for a single value
+pub fn mean_std (values: &[f64]) -> (f64, f64) {
+  let count = values.len().max(1) as f64;
+  let mean = values.iter().sum::<f64>() / count;
+
+  if values.len() < 2 {
+    return (mean, 0_f64);
+  }
+
+  let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>()
+    / (values.len() - 1) as f64;
+
+  (mean, variance.sqrt())
+}
@@ -0,0 +1,72 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use json::Json;
+use neural_network::layer::Layer;
+use neural_network::network::Network;
+use super::callback::{Callback, EpochStats, Flow};
+
+/// Writes one JSON object per epoch into a file, so that training runs can be
+/// consumed by other tools without scraping the standard output.
+///
+/// Each line has the following fields: epoch, learning_rate, loss,
+/// train_accuracy, validation_accuracy (null without a validation set),
+/// duration (in seconds), gradient_norms and weight_norms (one per layer).
+pub struct JsonLog {
+
+  /// Buffered log file.
+  writer: BufWriter<File>,
+
+}
+
+impl JsonLog {
+
+  /// Creates the log file, truncating it if it already exists.
+  ///
+  /// @param path Where to write the log
+  /// @return New JsonLog instance
+  pub fn create<P: AsRef<Path>> (path: P) -> io::Result<Self> {
+    Ok(JsonLog { writer: BufWriter::new(File::create(path)?) })
+  }
+
+  /// Serializes statistics of an epoch.
+  ///
+  /// @param stats Statistics of the epoch
+  /// @param network Network after the epoch
+  /// @return JSON object for the log line
+  pub fn entry (stats: &EpochStats, network: &Network) -> Json {
+    let duration = stats.duration.as_secs() as f64
+      + f64::from(stats.duration.subsec_nanos()) / 1e9_f64;
+
+    let weight_norms: Vec<f64> = network.layers.iter()
+      .map(|layer| Layer::norm(&layer.neurons))
+      .collect();
+
+    Json::object(vec!(
+      ("epoch", stats.epoch.into()),
+      ("learning_rate", stats.learning_rate.into()),
+      ("loss", stats.loss.into()),
+      ("train_accuracy", stats.accuracy.into()),
+      ("validation_accuracy", stats.validation_accuracy.into()),
+      ("duration", duration.into()),
+      ("gradient_norms", stats.gradient_norms.clone().into()),
+      ("weight_norms", weight_norms.into()),
+    ))
+  }
+
+}
+
+impl Callback for JsonLog {
+
+  fn on_epoch_end (&mut self, stats: &EpochStats, network: &Network) -> Flow {
+    let line = JsonLog::entry(stats, network);
+
+    // A broken log should not throw away the training, so we only warn.
+    if let Err(error) = writeln!(self.writer, "{}", line).and_then(|_| self.writer.flush()) {
+      eprintln!("Could not write training log: {}", error);
+    }
+
+    Flow::Continue
+  }
+
+}
@@ -0,0 +1,153 @@
+use rand::distributions::Beta;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use reader::sample::Shape;
+
+/// How pairs of training samples are blended together with their labels.
+/// The share of the first sample is drawn from Beta(alpha, alpha), small
+/// alpha keeps most blends close to one of the samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mixing {
+
+  /// Every feature is the weighted mean of both samples.
+  Mixup { alpha: f64 },
+
+  /// A box of the second image is pasted into the first one, the labels are
+  /// weighted by the area each image covers.
+  CutMix { alpha: f64, shape: Shape },
+
+}
+
+/// Draws partners and blend weights for the samples of each batch.
+#[derive(Clone)]
+pub struct Mixer {
+
+  /// How the samples are blended.
+  mixing: Mixing,
+
+  /// Draws the partners and the weights.
+  rng: StdRng,
+
+}
+
+impl Mixer {
+
+  /// Builds mixer whose blends differ between runs.
+  ///
+  /// @param mixing How the samples are blended
+  /// @return New Mixer instance
+  pub fn new (mixing: Mixing) -> Self {
+    Mixer { mixing, rng: StdRng::from_entropy() }
+  }
+
+  /// Builds mixer that blends the same way for every run with the same seed.
+  ///
+  /// @param mixing How the samples are blended
+  /// @param seed Seed of the partners and weights
+  /// @return New Mixer instance
+  pub fn seeded (mixing: Mixing, seed: u64) -> Self {
+    Mixer { mixing, rng: StdRng::seed_from_u64(seed) }
+  }
+
+  /// Picks another sample of the batch to blend a sample with. A batch of a
+  /// single sample, such as the last one of an epoch, leaves it as it is.
+  ///
+  /// @param position Position of the sample within the batch
+  /// @param samples Number of samples in the batch
+  /// @return Position of the partner within the batch
+  pub fn partner (&mut self, position: usize, samples: usize) -> usize {
+    if samples < 2 {
+      return position;
+    }
+
+    // Draws from the other positions by skipping over the sample itself.
+    let other = self.rng.gen_range(0, samples - 1);
+    if other >= position { other + 1 } else { other }
+  }
+
+  /// Blends two samples.
+  ///
+  /// @param first Features of the sample being trained on
+  /// @param second Features of its partner
+  /// @return Tuple in format (blended_features, share_of_first_label)
+  pub fn mix (&mut self, first: &[f64], second: &[f64]) -> (Vec<f64>, f64) {
+    match self.mixing {
+      Mixing::Mixup { alpha } => {
+        let weight = self.rng.sample(Beta::new(alpha, alpha));
+        let features = first.iter().zip(second.iter())
+          .map(|(a, b)| weight * a + (1_f64 - weight) * b)
+          .collect();
+
+        (features, weight)
+      },
+      Mixing::CutMix { alpha, shape } => {
+        let weight = self.rng.sample(Beta::new(alpha, alpha));
+        let Shape { width, height, channels } = shape;
+
+        // The box covers the share 1 - weight of the image unless it sticks
+        // out of it, the labels follow the area that is actually pasted.
+        let side = (1_f64 - weight).sqrt();
+        let (box_width, box_height) = (
+          (width as f64 * side).round() as usize,
+          (height as f64 * side).round() as usize,
+        );
+        let (centre_x, centre_y) = (self.rng.gen_range(0, width), self.rng.gen_range(0, height));
+        let (left, top) = (
+          centre_x.saturating_sub(box_width / 2),
+          centre_y.saturating_sub(box_height / 2),
+        );
+        let (right, bottom) = (
+          (centre_x + box_width - box_width / 2).min(width),
+          (centre_y + box_height - box_height / 2).min(height),
+        );
+
+        let mut features = first.to_vec();
+        for y in top..bottom {
+          let (start, end) = ((y * width + left) * channels, (y * width + right) * channels);
+          features[start..end].copy_from_slice(&second[start..end]);
+        }
+
+        let pasted = ((right - left) * (bottom - top)) as f64 / (width * height).max(1) as f64;
+
+        (features, 1_f64 - pasted)
+      },
+    }
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Mixer, Mixing};
+  use reader::sample::Shape;
+
+  #[test]
+  fn label_weight_matches_the_pasted_area() {
+    let shape = Shape::new(5, 4, 1);
+    let (first, second) = (vec!(0_f64; shape.len()), vec!(1_f64; shape.len()));
+    let mut mixer = Mixer::seeded(Mixing::CutMix { alpha: 1_f64, shape }, 7);
+
+    for _ in 0..20 {
+      let (features, weight) = mixer.mix(&first, &second);
+      let pasted: f64 = features.iter().sum();
+      assert!((1_f64 - weight - pasted / shape.len() as f64).abs() < 1e-12);
+    }
+
+    let mut mixer = Mixer::seeded(Mixing::Mixup { alpha: 0.4 }, 7);
+    let (features, weight) = mixer.mix(&first, &second);
+    assert!(features.iter().all(|&value| (value - (1_f64 - weight)).abs() < 1e-12));
+  }
+
+  #[test]
+  fn partners_are_other_samples_of_the_batch() {
+    let mut mixer = Mixer::seeded(Mixing::Mixup { alpha: 1_f64 }, 7);
+
+    for position in 0..3 {
+      for _ in 0..20 {
+        let partner = mixer.partner(position, 3);
+        assert!(partner < 3 && partner != position);
+      }
+    }
+    assert_eq!(mixer.partner(0, 1), 0);
+  }
+}
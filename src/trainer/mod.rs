@@ -0,0 +1,553 @@
+
+/*
+ * Exports learning rate schedules.
+ */
+
+pub mod schedule;
+
+/*
+ * Exports the optimizer trait and the optimizers implementing it.
+ */
+
+pub mod optimizer;
+
+/*
+ * Exports the callback trait for hooking into the training loop plus some
+ * default callbacks.
+ */
+
+pub mod callback;
+
+/*
+ * Exports the callback that writes training statistics as JSON lines.
+ */
+
+pub mod json_log;
+
+/*
+ * Exports k-fold cross validation.
+ */
+
+pub mod cross_validation;
+
+/*
+ * Exports mixup and CutMix blending of training samples.
+ */
+
+pub mod mixing;
+
+/*
+ * Exports class weights and class balanced sampling.
+ */
+
+pub mod balance;
+
+use std::borrow::Cow;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::time::{Duration, Instant};
+use dataset::Dataset;
+use dataset::stream::Stream;
+use error::Result;
+use neural_network::layer::Layer;
+use neural_network::network::Network;
+use self::balance::Sampler;
+use self::callback::{BatchStats, Callback, EpochStats, Flow};
+use self::mixing::Mixer;
+use self::optimizer::{Optimizer, Sgd};
+use self::schedule::Schedule;
+
+/// Order in which the training samples are presented to the network.
+pub enum Ordering {
+
+  /// Samples are processed in the order they come in.
+  Sequential,
+
+  /// Samples are shuffled before each epoch by a generator with given seed.
+  Shuffled(u64),
+
+}
+
+/// Trainer owns everything that is needed to train a network but is not part
+/// of the model itself: hyperparameters, learning rate schedule, optimizer,
+/// data ordering and the epoch loop.
+pub struct Trainer {
+
+  /// How many samples are averaged into one update of the network.
+  pub batch_size: usize,
+
+  /// How many times should the training data be processed.
+  pub epochs: usize,
+
+  /// Learning rate for each epoch.
+  pub schedule: Schedule,
+
+  /// Writes the gradients of each batch into the network.
+  pub optimizer: Box<dyn Optimizer>,
+
+  /// Order of the samples in each epoch.
+  pub ordering: Ordering,
+
+  /// Share of the label spread evenly over all classes, 0 for one hot
+  /// targets.
+  pub label_smoothing: f64,
+
+  /// Blends each training sample with another one of its batch.
+  pub mixer: Option<Mixer>,
+
+  /// Factor of the loss of each class, empty to count all classes alike.
+  pub class_weights: Vec<f64>,
+
+  /// Draws class balanced epochs instead of passing over every sample once.
+  /// Only used for data held in memory.
+  pub sampler: Option<Sampler>,
+
+  /// Hooks called along the training.
+  callbacks: Vec<Box<dyn Callback>>,
+
+  /// Changes applied to each training sample before it is trained on.
+  transforms: Vec<Box<dyn Transform>>,
+
+}
+
+/// Changes a training sample on the fly, such as a random distortion of an
+/// image. Unlike preprocessing, it is applied anew each time the sample is
+/// trained on, so that the network sees a different variant every epoch.
+pub trait Transform {
+
+  /// @param features Features of the sample
+  /// @return Transformed features
+  fn apply (&mut self, features: &[f64]) -> Vec<f64>;
+
+}
+
+/// Outcome of the whole training.
+pub struct Summary {
+
+  /// How many epochs were run, can be less than planned due to a callback.
+  pub epochs: usize,
+
+  /// How long the training took.
+  pub duration: Duration,
+
+}
+
+impl Trainer {
+
+  /// Builds new trainer with given learning rate schedule. Other
+  /// hyperparameters default to one sample per batch, one epoch, plain SGD,
+  /// sequential ordering and one hot targets.
+  ///
+  /// @param schedule Learning rate schedule
+  /// @return New Trainer instance
+  pub fn new (schedule: Schedule) -> Self {
+    Trainer {
+      schedule,
+      batch_size: 1,
+      epochs: 1,
+      optimizer: Box::new(Sgd),
+      ordering: Ordering::Sequential,
+      label_smoothing: 0_f64,
+      mixer: None,
+      class_weights: Vec::new(),
+      sampler: None,
+      callbacks: Vec::new(),
+      transforms: Vec::new(),
+    }
+  }
+
+  /// Registers a callback. Callbacks are called in the order they were added.
+  ///
+  /// @param callback Callback to register
+  pub fn add_callback (&mut self, callback: Box<dyn Callback>) {
+    self.callbacks.push(callback);
+  }
+
+  /// Registers a transform of the training samples. Transforms are applied in
+  /// the order they were added, the validation data is never transformed.
+  ///
+  /// @param transform Transform to register
+  pub fn add_transform (&mut self, transform: Box<dyn Transform>) {
+    self.transforms.push(transform);
+  }
+
+  /// Trains the network with back prop algorithm.
+  ///
+  /// @param network Network instance we want to train
+  /// @param training_data Training data
+  /// @return Summary of the training
+  pub fn fit (
+    &mut self,
+    network: &mut Network,
+    training_data: &dyn Dataset,
+  ) -> Summary {
+    self.train(network, training_data, None)
+  }
+
+  /// Trains the network and measures its accuracy on the validation data
+  /// after each epoch. The validation data is never trained on.
+  ///
+  /// @param network Network instance we want to train
+  /// @param training_data Training data
+  /// @param validation_data Data to measure accuracy on after each epoch
+  /// @return Summary of the training
+  pub fn fit_with_validation (
+    &mut self,
+    network: &mut Network,
+    training_data: &dyn Dataset,
+    validation_data: &dyn Dataset,
+  ) -> Summary {
+    self.train(network, training_data, Some(validation_data))
+  }
+
+  /// Runs the epoch loop.
+  ///
+  /// @param network Network instance we want to train
+  /// @param training_data Training data
+  /// @param validation_data Optional data to measure accuracy on
+  /// @return Summary of the training
+  fn train (
+    &mut self,
+    network: &mut Network,
+    training_data: &dyn Dataset,
+    validation_data: Option<&dyn Dataset>,
+  ) -> Summary {
+    let started_at = Instant::now();
+    let mut order: Vec<usize> = (0..training_data.len()).collect();
+    let mut rng = match self.ordering {
+      Ordering::Shuffled(seed) => Some(StdRng::seed_from_u64(seed)),
+      Ordering::Sequential => None,
+    };
+
+    let mut epochs = 0;
+    for epoch in 0..self.epochs {
+      if let Some(ref mut sampler) = self.sampler {
+        order = sampler.epoch(training_data);
+      } else if let Some(ref mut rng) = rng {
+        order.shuffle(rng);
+      }
+
+      epochs += 1;
+      let flow = self.run_epoch(
+        network,
+        training_data,
+        validation_data,
+        &order,
+        epoch,
+      );
+
+      if flow == Flow::Stop {
+        break;
+      }
+    }
+
+    Summary { epochs, duration: started_at.elapsed() }
+  }
+
+  /// Trains the network on data that is read batch by batch, such as files
+  /// larger than memory. Each epoch is one pass over the stream; the ordering
+  /// of the trainer is ignored, a stream shuffles its samples itself.
+  ///
+  /// @param network Network instance we want to train
+  /// @param stream Source of the training batches
+  /// @param validation_data Optional data to measure accuracy on
+  /// @return Summary of the training
+  pub fn fit_stream (
+    &mut self,
+    network: &mut Network,
+    stream: &mut dyn Stream,
+    validation_data: Option<&dyn Dataset>,
+  ) -> Result<Summary> {
+    let started_at = Instant::now();
+
+    let mut epochs = 0;
+    for epoch in 0..self.epochs {
+      stream.rewind()?;
+      epochs += 1;
+
+      let mut totals = self.start_epoch(network, epoch);
+      let mut batch = 0;
+
+      while let Some(data) = stream.next_batch(self.batch_size.max(1))? {
+        let indices: Vec<usize> = (0..data.len()).collect();
+        self.run_batch(network, &data, &indices, &mut totals, batch);
+        batch += 1;
+      }
+
+      if self.end_epoch(network, validation_data, totals) == Flow::Stop {
+        break;
+      }
+    }
+
+    Ok(Summary { epochs, duration: started_at.elapsed() })
+  }
+
+  /// Processes the training data once in given order.
+  ///
+  /// @param network Network instance we want to train
+  /// @param training_data Training data
+  /// @param validation_data Optional data to measure accuracy on
+  /// @param order Indices of the samples in the order to process them in
+  /// @param epoch Index of the epoch
+  /// @return Whether the training should continue
+  fn run_epoch (
+    &mut self,
+    network: &mut Network,
+    training_data: &dyn Dataset,
+    validation_data: Option<&dyn Dataset>,
+    order: &[usize],
+    epoch: usize,
+  ) -> Flow {
+    let mut totals = self.start_epoch(network, epoch);
+
+    for (batch, indices) in order.chunks(self.batch_size.max(1)).enumerate() {
+      self.run_batch(network, training_data, indices, &mut totals, batch);
+    }
+
+    self.end_epoch(network, validation_data, totals)
+  }
+
+  /// Announces the epoch to the callbacks.
+  ///
+  /// @param network Network instance we want to train
+  /// @param epoch Index of the epoch
+  /// @return Empty totals of the epoch
+  fn start_epoch (&mut self, network: &Network, epoch: usize) -> Totals {
+    let learning_rate = self.schedule.learning_rate(epoch);
+
+    for callback in self.callbacks.iter_mut() {
+      callback.on_epoch_start(epoch, learning_rate);
+    }
+
+    Totals {
+      epoch,
+      learning_rate,
+      started_at: Instant::now(),
+      loss: 0_f64,
+      correct: 0,
+      samples: 0,
+      gradient_norms: vec!(0_f64; network.layers.len()),
+      batches: 0,
+    }
+  }
+
+  /// Averages the gradients of a batch and commits them into the network.
+  ///
+  /// @param network Network instance we want to train
+  /// @param training_data Training data
+  /// @param indices Positions of the samples of the batch
+  /// @param totals Totals of the epoch to add the batch to
+  /// @param batch Index of the batch within the epoch
+  fn run_batch (
+    &mut self,
+    network: &mut Network,
+    training_data: &dyn Dataset,
+    indices: &[usize],
+    totals: &mut Totals,
+    batch: usize,
+  ) {
+    // Mean of the gradients of all samples in the batch.
+    let mut gradients = network.zero_gradients();
+    let mut batch_loss = 0_f64;
+    let classes = network.layers.last().map_or(0, |layer| layer.neurons.len());
+
+    let samples: Vec<(usize, Cow<[f64]>)> = indices.iter()
+      .map(|&index| {
+        let (target, inputs) = training_data.sample(index);
+        let transformed = self.transforms.iter_mut()
+          .fold(None, |features: Option<Vec<f64>>, transform| {
+            Some(transform.apply(features.as_deref().unwrap_or(inputs)))
+          });
+
+        (target, transformed.map_or(Cow::Borrowed(inputs), Cow::Owned))
+      })
+      .collect();
+
+    for (position, (target, inputs)) in samples.iter().enumerate() {
+      let mut targets = soft_targets(*target, classes, self.label_smoothing);
+
+      let mixed;
+      let inputs = match self.mixer {
+        Some(ref mut mixer) => {
+          let (partner, ref partner_inputs) = samples[mixer.partner(position, samples.len())];
+          let (features, weight) = mixer.mix(inputs, partner_inputs);
+          let partner_targets = soft_targets(partner, classes, self.label_smoothing);
+
+          for (expected, other) in targets.iter_mut().zip(partner_targets.iter()) {
+            *expected = weight * *expected + (1_f64 - weight) * other;
+          }
+
+          mixed = features;
+          &mixed[..]
+        },
+        None => &inputs[..],
+      };
+
+      // Blended targets weight their classes by their share.
+      let weight = if self.class_weights.is_empty() {
+        1_f64
+      } else {
+        targets.iter().zip(self.class_weights.iter()).map(|(share, weight)| share * weight).sum()
+      };
+      let (outputs, sample_gradients) = network.backpropagate_weighted(&targets, weight, inputs);
+
+      batch_loss += weight * soft_loss(&targets, &outputs);
+      if argmax(&outputs) == argmax(&targets) {
+        totals.correct += 1;
+      }
+
+      for (sum, nudges) in gradients.iter_mut().zip(sample_gradients.iter()) {
+        Layer::add_weights(sum, nudges, 1_f64 / indices.len() as f64);
+      }
+    }
+
+    for (norm, nudges) in totals.gradient_norms.iter_mut().zip(gradients.iter()) {
+      *norm += Layer::norm(nudges);
+    }
+
+    // Commits the averaged updates into each layer.
+    self.optimizer.step(network, &gradients, totals.learning_rate);
+    totals.loss += batch_loss;
+    totals.samples += indices.len();
+    totals.batches += 1;
+
+    let stats = BatchStats {
+      epoch: totals.epoch,
+      batch,
+      samples: indices.len(),
+      loss: batch_loss / indices.len() as f64,
+    };
+
+    for callback in self.callbacks.iter_mut() {
+      callback.on_commit(network);
+      callback.on_batch_end(&stats);
+    }
+  }
+
+  /// Reports the statistics of the epoch to the callbacks.
+  ///
+  /// @param network Network instance we want to train
+  /// @param validation_data Optional data to measure accuracy on
+  /// @param totals Totals of the epoch
+  /// @return Whether the training should continue
+  fn end_epoch (
+    &mut self,
+    network: &mut Network,
+    validation_data: Option<&dyn Dataset>,
+    totals: Totals,
+  ) -> Flow {
+    let samples = totals.samples.max(1) as f64;
+    let batches = totals.batches.max(1) as f64;
+    let stats = EpochStats {
+      epoch: totals.epoch,
+      learning_rate: totals.learning_rate,
+      loss: totals.loss / samples,
+      accuracy: totals.correct as f64 / samples,
+      validation_accuracy: validation_data.map(|data| accuracy(network, data)),
+      gradient_norms: totals.gradient_norms.into_iter()
+        .map(|norm| norm / batches)
+        .collect(),
+      duration: totals.started_at.elapsed(),
+    };
+
+    // Every callback gets to see the end of the epoch even if an earlier one
+    // already asked to stop.
+    self.callbacks.iter_mut()
+      .fold(Flow::Continue, |flow, callback| {
+        match callback.on_epoch_end(&stats, network) {
+          Flow::Stop => Flow::Stop,
+          Flow::Continue => flow,
+        }
+      })
+  }
+
+}
+
+/// Running sums of an epoch, turned into EpochStats at its end.
+struct Totals {
+  epoch: usize,
+  learning_rate: f64,
+  started_at: Instant,
+  loss: f64,
+  correct: usize,
+  samples: usize,
+  gradient_norms: Vec<f64>,
+  batches: usize,
+}
+
+/// Half of the squared error between the one hot target and the outputs,
+/// which is the loss the output deltas are derived from.
+///
+/// @param target The expected outcome
+/// @param outputs Outputs from the network
+/// @return Loss of the sample
+pub fn loss (target: usize, outputs: &[f64]) -> f64 {
+  outputs.iter()
+    .enumerate()
+    .map(|(neuron, output)| {
+      let expected = if neuron == target { 1_f64 } else { 0_f64 };
+
+      0.5_f64 * (expected - output).powi(2)
+    })
+    .sum()
+}
+
+/// Half of the squared error between the expected activations and the
+/// outputs, the loss of a soft label.
+///
+/// @param targets Expected activation of each output neuron
+/// @param outputs Outputs from the network
+/// @return Loss of the sample
+pub fn soft_loss (targets: &[f64], outputs: &[f64]) -> f64 {
+  targets.iter()
+    .zip(outputs.iter())
+    .map(|(expected, output)| 0.5_f64 * (expected - output).powi(2))
+    .sum()
+}
+
+/// Spreads a class into expected activations of the output neurons. With
+/// label smoothing the target class gets 1 - smoothing and the smoothing is
+/// shared evenly by all classes, so that the network is not pushed towards
+/// ever more confident outputs.
+///
+/// @param target The expected class
+/// @param classes Number of output neurons
+/// @param smoothing Share of the label spread over all classes
+/// @return Expected activation of each output neuron
+pub fn soft_targets (target: usize, classes: usize, smoothing: f64) -> Vec<f64> {
+  let mut targets = vec!(smoothing / classes.max(1) as f64; classes);
+  if let Some(expected) = targets.get_mut(target) {
+    *expected += 1_f64 - smoothing;
+  }
+
+  targets
+}
+
+/// Ratio of samples the network classifies correctly.
+///
+/// @param network Network to evaluate
+/// @param data Labelled samples
+/// @return Accuracy between 0 and 1
+pub fn accuracy (network: &Network, data: &dyn Dataset) -> f64 {
+  let correct = data.iter()
+    .filter(|&(target, inputs)| network.classify(inputs) == target)
+    .count();
+
+  correct as f64 / data.len().max(1) as f64
+}
+
+/// Finds the output neuron that fired the most.
+///
+/// @param outputs Outputs from the network
+/// @return Position of the strongest neuron
+pub fn argmax (outputs: &[f64]) -> usize {
+  outputs.iter()
+    .enumerate()
+    .fold((0, None), |(strongest, intensity), (neuron, &output)| {
+      match intensity {
+        Some(x) if x >= output => (strongest, intensity),
+        _ => (neuron, Some(output)),
+      }
+    })
+    .0
+}
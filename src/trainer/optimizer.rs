@@ -0,0 +1,71 @@
+use neural_network::network::Network;
+use neural_network::network_train::Gradients;
+use neural_network::layer::Layer;
+
+/// Optimizer decides how the averaged gradients of a batch are written into
+/// the network.
+pub trait Optimizer {
+
+  /// Updates the network weights and biases.
+  ///
+  /// @param network Network to update
+  /// @param gradients Mean gradients of the batch
+  /// @param learning_rate Learning rate for current epoch
+  fn step (&mut self, network: &mut Network, gradients: &Gradients, learning_rate: f64);
+
+}
+
+/// Plain stochastic gradient descent. Each weight is moved against its
+/// gradient by the learning rate.
+pub struct Sgd;
+
+impl Optimizer for Sgd {
+
+  fn step (&mut self, network: &mut Network, gradients: &Gradients, learning_rate: f64) {
+    network.apply_gradients(gradients, -learning_rate);
+  }
+
+}
+
+/// Gradient descent with classical momentum. The update is an exponentially
+/// decaying sum of the past gradients, which smooths out noisy batches.
+pub struct Momentum {
+
+  /// How much of the previous velocity is kept, usually around 0.9.
+  pub momentum: f64,
+
+  /// Accumulated updates from the previous steps.
+  velocity: Option<Gradients>,
+
+}
+
+impl Momentum {
+
+  /// @param momentum How much of the previous velocity is kept
+  /// @return New Momentum instance
+  pub fn new (momentum: f64) -> Self {
+    Momentum { momentum, velocity: None }
+  }
+
+}
+
+impl Optimizer for Momentum {
+
+  fn step (&mut self, network: &mut Network, gradients: &Gradients, learning_rate: f64) {
+    let momentum = self.momentum;
+    let velocity = self.velocity.get_or_insert_with(|| network.zero_gradients());
+
+    // v = momentum * v - learning_rate * gradients
+    for (layer, nudges) in velocity.iter_mut().zip(gradients.iter()) {
+      for neuron in layer.iter_mut() {
+        neuron.0 *= momentum;
+        neuron.1.iter_mut().for_each(|weight| *weight *= momentum);
+      }
+
+      Layer::add_weights(layer, nudges, -learning_rate);
+    }
+
+    network.apply_gradients(velocity, 1_f64);
+  }
+
+}
@@ -0,0 +1,41 @@
+
+/// Describes how the learning rate changes from epoch to epoch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+
+  /// The same learning rate for every epoch.
+  Constant(f64),
+
+  /// Triangular cyclical learning rate. The rate oscillates between min_lr
+  /// and max_lr, where step_size is the number of epochs it takes to get from
+  /// one to the other.
+  Cyclical {
+    min_lr: f64,
+    max_lr: f64,
+    step_size: f64,
+  },
+
+}
+
+impl Schedule {
+
+  /// Calculates the learning rate for given epoch.
+  ///
+  /// @param epoch Index of the epoch starting at 0
+  /// @return Learning rate to use for the whole epoch
+  pub fn learning_rate (&self, epoch: usize) -> f64 {
+    match *self {
+      Schedule::Constant(learning_rate) => learning_rate,
+      Schedule::Cyclical { min_lr, max_lr, step_size } => {
+        let step = 1_f64 + epoch as f64 / (2_f64 * step_size);
+        let cycle = step.floor();
+        let progress = (0.5_f64 - (step - cycle)).abs();
+
+        max_lr - (
+          2_f64 * (max_lr - min_lr) * (0.5_f64 - progress).abs()
+        )
+      },
+    }
+  }
+
+}